@@ -0,0 +1,15 @@
+// Set a tag on an ogg vorbis stream read from stdin, writing it to stdout
+
+extern crate oggvorbismeta;
+
+use oggvorbismeta::{transform_comment_header, VorbisComments};
+use std::env;
+use std::io;
+
+fn main() {
+    let tag = env::args().nth(1).expect("Please specify a tag name.");
+    let value = env::args().nth(2).expect("Please specify a value.");
+    transform_comment_header(io::stdin().lock(), io::stdout().lock(), |header| {
+        header.set_tag_single(&tag, &value)
+    });
+}
@@ -0,0 +1,144 @@
+// Editing the tags of all tracks of an album together
+
+use crate::batch::{read_path, safe_replace_comment_headers, VorbisBatchError};
+use crate::keys;
+use crate::numbering::{write_position_padded, Position};
+use crate::{CommentHeader, VorbisComments};
+use std::path::{Path, PathBuf};
+
+/// Tags that should have the same value on every track of an album.
+const ALBUM_TAGS: [&str; 3] = [keys::ALBUM, keys::ALBUMARTIST, keys::DATE];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AlbumInconsistency {
+    /// An album-level tag differs between tracks, or is missing on some.
+    MismatchedTag { tag: String, values: Vec<String> },
+    /// Several tracks share the same track number.
+    DuplicateTrackNumber { number: String, paths: Vec<PathBuf> },
+}
+
+/// The comment headers of a set of files, edited in memory and written
+/// together with `save`.
+pub struct AlbumSession {
+    tracks: Vec<(PathBuf, CommentHeader)>,
+}
+
+impl AlbumSession {
+    /// Read the headers of the given files, in album order.
+    pub fn open<P: AsRef<Path>>(paths: &[P]) -> Result<AlbumSession, VorbisBatchError> {
+        let mut tracks = vec![];
+        for path in paths.iter() {
+            let path = path.as_ref();
+            tracks.push((path.to_path_buf(), read_path(path)?));
+        }
+        Ok(AlbumSession { tracks })
+    }
+
+    pub fn tracks(&self) -> &[(PathBuf, CommentHeader)] {
+        &self.tracks
+    }
+
+    pub fn header_mut(&mut self, index: usize) -> Option<&mut CommentHeader> {
+        self.tracks.get_mut(index).map(|track| &mut track.1)
+    }
+
+    fn set_all(&mut self, tag: &str, value: &str) {
+        for (_, header) in self.tracks.iter_mut() {
+            header.clear_tag(tag);
+            header.add_tag_single(tag, value);
+        }
+    }
+
+    pub fn set_album(&mut self, album: &str) {
+        self.set_all(keys::ALBUM, album);
+    }
+
+    pub fn set_album_artist(&mut self, album_artist: &str) {
+        self.set_all(keys::ALBUMARTIST, album_artist);
+    }
+
+    pub fn set_date(&mut self, date: &str) {
+        self.set_all(keys::DATE, date);
+    }
+
+    /// Number the tracks in session order, starting at `start` and
+    /// zero-padded to `width` digits, and set TRACKTOTAL to the number of
+    /// tracks, removing TOTALTRACKS. Fails with `TrackNumberOverflow`,
+    /// changing nothing, when the last number doesn't fit a `u32`.
+    pub fn renumber_tracks(&mut self, start: u32, width: usize) -> Result<(), VorbisBatchError> {
+        let overflow = || VorbisBatchError::TrackNumberOverflow(start);
+        let total = u32::try_from(self.tracks.len()).map_err(|_| overflow())?;
+        let last = match total.checked_sub(1) {
+            Some(last) => start.checked_add(last).ok_or_else(overflow)?,
+            None => return Ok(()),
+        };
+        for (number, (_, header)) in (start..=last).zip(self.tracks.iter_mut()) {
+            let position = Position {
+                number,
+                total: Some(total),
+            };
+            write_position_padded(
+                header,
+                keys::TRACKNUMBER,
+                &[keys::TRACKTOTAL, keys::TOTALTRACKS],
+                Some(position),
+                width,
+            );
+        }
+        Ok(())
+    }
+
+    /// Check that album-level tags agree and track numbers are unique.
+    pub fn validate(&self) -> Vec<AlbumInconsistency> {
+        let mut issues = vec![];
+        for tag in ALBUM_TAGS.iter() {
+            let values = self
+                .tracks
+                .iter()
+                .map(|(_, header)| header.get_tag_multi(tag).join("; "))
+                .collect::<Vec<String>>();
+            let mut distinct = values.clone();
+            distinct.sort_unstable();
+            distinct.dedup();
+            if distinct.len() > 1 {
+                issues.push(AlbumInconsistency::MismatchedTag {
+                    tag: tag.to_lowercase(),
+                    values,
+                });
+            }
+        }
+        let mut numbers: Vec<(String, Vec<PathBuf>)> = vec![];
+        for (path, header) in self.tracks.iter() {
+            if let Some(number) = header.get_tag_single(keys::TRACKNUMBER) {
+                match numbers.iter_mut().find(|entry| entry.0 == number) {
+                    Some(entry) => entry.1.push(path.clone()),
+                    None => numbers.push((number, vec![path.clone()])),
+                }
+            }
+        }
+        for (number, paths) in numbers {
+            if paths.len() > 1 {
+                issues.push(AlbumInconsistency::DuplicateTrackNumber { number, paths });
+            }
+        }
+        issues
+    }
+
+    /// Write all files. Either every file gets its new header, or (unless the
+    /// final renames fail) none of them is touched.
+    pub fn save(self) -> Result<(), VorbisBatchError> {
+        safe_replace_comment_headers(self.tracks)
+    }
+}
+
+/// Rewrite TRACKNUMBER and TRACKTOTAL of the files, numbered in the given
+/// order starting at `start` and zero-padded to `width` digits.
+pub fn renumber_tracks<P: AsRef<Path>>(
+    files: &[P],
+    start: u32,
+    width: usize,
+) -> Result<(), VorbisBatchError> {
+    let mut session = AlbumSession::open(files)?;
+    session.renumber_tracks(start, width)?;
+    session.save()
+}
@@ -0,0 +1,85 @@
+// Non-standard tag names written by some taggers, and their standard names
+
+use crate::keys;
+use crate::{CommentHeader, VorbisComments};
+use std::collections::HashMap;
+
+/// The aliases known by `KeyAliases::default`.
+const DEFAULT_ALIASES: [(&str, &str); 16] = [
+    ("ALBUM ARTIST", keys::ALBUMARTIST),
+    ("ALBUM_ARTIST", keys::ALBUMARTIST),
+    ("ALBUMARTIST_CREDIT", keys::ALBUMARTIST),
+    ("ALBUM ARTIST SORT", keys::ALBUMARTISTSORT),
+    ("ALBUMARTISTSORTORDER", keys::ALBUMARTISTSORT),
+    ("TRACK", keys::TRACKNUMBER),
+    ("TRACKNUM", keys::TRACKNUMBER),
+    ("DISC", keys::DISCNUMBER),
+    ("DISCNUM", keys::DISCNUMBER),
+    ("YEAR", keys::DATE),
+    ("COMMENTS", keys::COMMENT),
+    ("ENCODED-BY", keys::ENCODEDBY),
+    ("ENCODED_BY", keys::ENCODEDBY),
+    ("UNSYNCED LYRICS", keys::UNSYNCEDLYRICS),
+    ("ORGANISATION", keys::ORGANIZATION),
+    ("ARTISTSORTORDER", keys::ARTISTSORT),
+];
+
+/// A table from alias to standard tag name, both case insensitive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyAliases {
+    aliases: HashMap<String, String>,
+}
+
+impl Default for KeyAliases {
+    /// The common aliases, such as "ALBUM ARTIST" and "YEAR".
+    fn default() -> KeyAliases {
+        let mut aliases = KeyAliases::empty();
+        for (alias, canonical) in DEFAULT_ALIASES {
+            aliases.insert(alias, canonical);
+        }
+        aliases
+    }
+}
+
+impl KeyAliases {
+    pub fn new() -> KeyAliases {
+        KeyAliases::default()
+    }
+
+    /// A table without any aliases.
+    pub fn empty() -> KeyAliases {
+        KeyAliases {
+            aliases: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, alias: &str, canonical: &str) -> &mut KeyAliases {
+        self.aliases
+            .insert(alias.to_lowercase(), canonical.to_lowercase());
+        self
+    }
+
+    pub fn remove(&mut self, alias: &str) -> &mut KeyAliases {
+        self.aliases.remove(&alias.to_lowercase());
+        self
+    }
+
+    /// The lowercase standard name `key` is an alias of.
+    pub fn canonical(&self, key: &str) -> Option<&str> {
+        self.aliases
+            .get(&key.to_lowercase())
+            .map(|name| name.as_str())
+    }
+}
+
+pub trait VorbisAliases {
+    /// Rename every tag that is an alias to its standard name, keeping the
+    /// entries where they are. Returns how many entries were renamed.
+    fn canonicalize_keys(&mut self, aliases: &KeyAliases) -> usize;
+}
+
+impl VorbisAliases for CommentHeader {
+    fn canonicalize_keys(&mut self, aliases: &KeyAliases) -> usize {
+        self.map_keys(|key| aliases.canonical(key).map(|name| name.to_string()))
+    }
+}
@@ -0,0 +1,144 @@
+// Rewriting several files so that a crash never leaves a half-written one
+
+use crate::scan::file_size;
+use crate::throttle::{Throttle, Throttler};
+use crate::{
+    safe_read_comment_header, safe_replace_comment_header_into_with_options, CommentHeader,
+    ReplaceOptions, VorbisReadCommentError, VorbisReplaceCommentError,
+};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum VorbisBatchError {
+    #[error("failed to access {0}")]
+    Io(PathBuf, #[source] io::Error),
+    #[error("failed to read comments of {0}")]
+    Read(PathBuf, #[source] VorbisReadCommentError),
+    /// Reading the comments of a source given as a reader failed.
+    #[error("failed to read comments of the source")]
+    ReadSource(#[source] VorbisReadCommentError),
+    #[error("failed to rewrite {0}")]
+    Replace(PathBuf, #[source] VorbisReplaceCommentError),
+    #[error("{0} was changed by someone else since it was read")]
+    ConcurrentModification(PathBuf),
+    /// Numbering the tracks from `0` on goes past `u32::MAX`.
+    #[error("track numbers starting at {0} overflow")]
+    TrackNumberOverflow(u32),
+}
+
+static STAGING_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Sibling path the new contents of `path` are staged in before the rename.
+/// The process id and a counter make it unique, so concurrent saves of the
+/// same file never share one.
+pub(crate) fn staging_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let count = STAGING_COUNT.fetch_add(1, Ordering::Relaxed);
+    path.with_file_name(format!(
+        ".{}.oggvorbismeta-{}-{}.tmp",
+        name,
+        process::id(),
+        count
+    ))
+}
+
+/// Create the file at a new `staging_path`, failing rather than reusing one
+/// that exists.
+pub(crate) fn create_staging(path: &Path) -> io::Result<(File, PathBuf)> {
+    let staged = staging_path(path);
+    let file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&staged)?;
+    Ok((file, staged))
+}
+
+pub(crate) fn read_path(path: &Path) -> Result<CommentHeader, VorbisBatchError> {
+    let f_in = File::open(path).map_err(|err| VorbisBatchError::Io(path.to_path_buf(), err))?;
+    safe_read_comment_header(BufReader::new(f_in))
+        .map_err(|err| VorbisBatchError::Read(path.to_path_buf(), err))
+}
+
+/// Write the file with its new header to the staging path, returning that path.
+pub(crate) fn stage(path: &Path, header: CommentHeader) -> Result<PathBuf, VorbisBatchError> {
+    stage_with_options(path, header, &ReplaceOptions::default())
+}
+
+pub(crate) fn stage_with_options(
+    path: &Path,
+    header: CommentHeader,
+    options: &ReplaceOptions,
+) -> Result<PathBuf, VorbisBatchError> {
+    let io_err = |err| VorbisBatchError::Io(path.to_path_buf(), err);
+    let f_in = File::open(path).map_err(io_err)?;
+    let (f_staged, staged) = create_staging(path).map_err(io_err)?;
+    let written = safe_replace_comment_header_into_with_options(
+        BufReader::new(f_in),
+        BufWriter::new(&f_staged),
+        header,
+        options,
+    )
+    .map_err(|err| VorbisBatchError::Replace(path.to_path_buf(), err))
+    .and_then(|_| f_staged.sync_all().map_err(io_err));
+    if let Err(err) = written {
+        let _ = fs::remove_file(&staged);
+        return Err(err);
+    }
+    Ok(staged)
+}
+
+/// Replace the comment headers of several files.
+///
+/// Every rewritten file is first staged next to its original. Only when all
+/// of them were written successfully are they renamed into place, so a
+/// failure part way leaves the originals untouched.
+pub fn safe_replace_comment_headers<P: AsRef<Path>>(
+    jobs: Vec<(P, CommentHeader)>,
+) -> Result<(), VorbisBatchError> {
+    safe_replace_comment_headers_throttled(jobs, &Throttle::default())
+}
+
+/// Same as `safe_replace_comment_headers`, pacing the staging of the files
+/// according to `throttle`.
+pub fn safe_replace_comment_headers_throttled<P: AsRef<Path>>(
+    jobs: Vec<(P, CommentHeader)>,
+    throttle: &Throttle,
+) -> Result<(), VorbisBatchError> {
+    let mut throttler = Throttler::new(throttle);
+    let mut staged: Vec<(PathBuf, PathBuf)> = vec![];
+    for (path, header) in jobs {
+        let path = path.as_ref();
+        throttler.wait();
+        let result = stage(path, header);
+        throttler.file_done(file_size(path));
+        match result {
+            Ok(staged_path) => staged.push((staged_path, path.to_path_buf())),
+            Err(err) => {
+                for (staged_path, _) in staged.iter() {
+                    let _ = fs::remove_file(staged_path);
+                }
+                return Err(err);
+            }
+        }
+    }
+    let mut renames = staged.into_iter();
+    while let Some((staged_path, path)) = renames.next() {
+        if let Err(err) = fs::rename(&staged_path, &path) {
+            let _ = fs::remove_file(&staged_path);
+            for (staged_path, _) in renames {
+                let _ = fs::remove_file(staged_path);
+            }
+            return Err(VorbisBatchError::Io(path, err));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,107 @@
+// Building a comment header in one expression, checked against the spec
+
+use crate::picture::{Picture, VorbisPictures};
+use crate::{CommentHeader, VorbisComments};
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum VorbisBuildError {
+    /// Field names are printable ASCII (0x20 to 0x7D) other than '='.
+    #[error("invalid tag name: {0:?}")]
+    InvalidTagName(String),
+    #[error("vendor string does not fit in a comment header")]
+    VendorTooLong,
+    #[error("comment {0:?} does not fit in a comment header")]
+    CommentTooLong(String),
+    #[error("too many comments for a comment header")]
+    TooManyComments,
+    /// The packet would be `size` bytes, more than the limit set with
+    /// `max_size`.
+    #[error("comment header of {size} bytes exceeds the limit of {limit} bytes")]
+    TooLarge { size: usize, limit: usize },
+}
+
+/// Whether `name` may be used as a field name, see
+/// <https://xiph.org/vorbis/doc/v-comment.html>
+pub fn is_valid_tag_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|byte| (0x20..=0x7d).contains(&byte) && byte != b'=')
+}
+
+/// Chained construction of a `CommentHeader`:
+/// `CommentHeaderBuilder::new().vendor("me").tag("title", "Noise").build()`.
+#[derive(Clone, Debug, Default)]
+pub struct CommentHeaderBuilder {
+    header: CommentHeader,
+    max_size: Option<usize>,
+}
+
+impl CommentHeaderBuilder {
+    pub fn new() -> CommentHeaderBuilder {
+        CommentHeaderBuilder::default()
+    }
+
+    pub fn vendor(mut self, vendor: &str) -> CommentHeaderBuilder {
+        self.header.set_vendor(vendor);
+        self
+    }
+
+    /// Add a value, as `add_tag_single` would.
+    pub fn tag<K: AsRef<str>>(mut self, tag: K, value: &str) -> CommentHeaderBuilder {
+        self.header.add_tag_single(tag, value);
+        self
+    }
+
+    pub fn tags<I, K, V>(mut self, tags: I) -> CommentHeaderBuilder
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        for (tag, value) in tags {
+            self.header.add_tag_single(tag, value.as_ref());
+        }
+        self
+    }
+
+    pub fn picture(mut self, picture: &Picture) -> CommentHeaderBuilder {
+        self.header.add_picture(picture);
+        self
+    }
+
+    /// Fail to build if the encoded packet would be larger than `bytes`.
+    pub fn max_size(mut self, bytes: usize) -> CommentHeaderBuilder {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// The header, if every tag name is valid and every length fits the
+    /// 32-bit fields of the packet.
+    pub fn build(self) -> Result<CommentHeader, VorbisBuildError> {
+        let header = self.header;
+        if u32::try_from(header.get_vendor().len()).is_err() {
+            return Err(VorbisBuildError::VendorTooLong);
+        }
+        if u32::try_from(header.len()).is_err() {
+            return Err(VorbisBuildError::TooManyComments);
+        }
+        for (key, value) in header.iter() {
+            if !is_valid_tag_name(key) {
+                return Err(VorbisBuildError::InvalidTagName(key.to_string()));
+            }
+            if u32::try_from(key.len() + 1 + value.len()).is_err() {
+                return Err(VorbisBuildError::CommentTooLong(key.to_string()));
+            }
+        }
+        if let Some(limit) = self.max_size {
+            let size = header.encoded_size();
+            if size > limit {
+                return Err(VorbisBuildError::TooLarge { size, limit });
+            }
+        }
+        Ok(header)
+    }
+}
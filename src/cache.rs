@@ -0,0 +1,235 @@
+// Storage for the scan cache
+
+use crate::base64;
+use crate::batch::create_staging;
+use crate::parse::parse_comment_header;
+use crate::{safe_make_comment_header, CommentHeader};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// What is remembered about a scanned file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CachedHeader {
+    pub size: u64,
+    /// Modification time since the Unix epoch.
+    pub modified: Duration,
+    pub fingerprint: u32,
+    pub header: CommentHeader,
+}
+
+pub trait ScanCache {
+    fn get(&self, path: &Path) -> Option<CachedHeader>;
+    fn insert(&mut self, path: PathBuf, entry: CachedHeader);
+    /// Persist the entries, if the storage needs it.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MemoryCache {
+    entries: HashMap<PathBuf, CachedHeader>,
+}
+
+impl MemoryCache {
+    pub fn new() -> MemoryCache {
+        MemoryCache::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl ScanCache for MemoryCache {
+    fn get(&self, path: &Path) -> Option<CachedHeader> {
+        self.entries.get(path).cloned()
+    }
+
+    fn insert(&mut self, path: PathBuf, entry: CachedHeader) {
+        self.entries.insert(path, entry);
+    }
+}
+
+/// A cache kept in a text file, one entry per line. Headers are stored as
+/// base64 comment packets. Files whose path isn't valid UTF-8 are not persisted.
+pub struct FileCache {
+    path: PathBuf,
+    entries: MemoryCache,
+}
+
+/// The modification time stored in a cache, `None` if the nanoseconds are
+/// out of range, as they are in a corrupt entry.
+fn stored_time(secs: u64, nanos: u32) -> Option<Duration> {
+    (nanos < 1_000_000_000).then(|| Duration::new(secs, nanos))
+}
+
+fn parse_line(line: &str) -> Option<(PathBuf, CachedHeader)> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    if fields.len() != 6 {
+        return None;
+    }
+    let path = String::from_utf8(base64::decode(fields[0])?).ok()?;
+    let packet = base64::decode(fields[5])?;
+    Some((
+        PathBuf::from(path),
+        CachedHeader {
+            size: fields[1].parse().ok()?,
+            modified: stored_time(fields[2].parse().ok()?, fields[3].parse().ok()?)?,
+            fingerprint: fields[4].parse().ok()?,
+            header: parse_comment_header(&packet).ok()?,
+        },
+    ))
+}
+
+impl FileCache {
+    /// Open the cache at `path`. A missing file gives an empty cache, lines
+    /// that can't be parsed are dropped.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<FileCache> {
+        let path = path.as_ref().to_path_buf();
+        let mut entries = MemoryCache::new();
+        match fs::read_to_string(&path) {
+            Ok(text) => {
+                for (file, entry) in text.lines().filter_map(parse_line) {
+                    entries.insert(file, entry);
+                }
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+        Ok(FileCache { path, entries })
+    }
+}
+
+impl ScanCache for FileCache {
+    fn get(&self, path: &Path) -> Option<CachedHeader> {
+        self.entries.get(path)
+    }
+
+    fn insert(&mut self, path: PathBuf, entry: CachedHeader) {
+        self.entries.insert(path, entry);
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut text = String::new();
+        for (path, entry) in self.entries.entries.iter() {
+            let path = match path.to_str() {
+                Some(path) => path,
+                None => continue,
+            };
+            let packet = match safe_make_comment_header(&entry.header) {
+                Ok(packet) => packet,
+                Err(_) => continue,
+            };
+            text.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\n",
+                base64::encode(path.as_bytes()),
+                entry.size,
+                entry.modified.as_secs(),
+                entry.modified.subsec_nanos(),
+                entry.fingerprint,
+                base64::encode(&packet)
+            ));
+        }
+        let (mut f_out, staged) = create_staging(&self.path)?;
+        let written = f_out
+            .write_all(text.as_bytes())
+            .and_then(|_| f_out.sync_all())
+            .and_then(|_| fs::rename(&staged, &self.path));
+        if written.is_err() {
+            let _ = fs::remove_file(&staged);
+        }
+        written
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteCache;
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::{stored_time, CachedHeader, ScanCache};
+    use crate::parse::parse_comment_header;
+    use crate::safe_make_comment_header;
+    use rusqlite::{params, Connection, OptionalExtension};
+    use std::path::{Path, PathBuf};
+
+    /// A cache in an SQLite database, enabled by the `sqlite` feature.
+    /// Entries are written as they are inserted.
+    pub struct SqliteCache {
+        connection: Connection,
+    }
+
+    impl SqliteCache {
+        pub fn open<P: AsRef<Path>>(path: P) -> rusqlite::Result<SqliteCache> {
+            let connection = Connection::open(path)?;
+            connection.execute(
+                "CREATE TABLE IF NOT EXISTS scan_cache (
+                    path TEXT PRIMARY KEY,
+                    size INTEGER NOT NULL,
+                    modified_secs INTEGER NOT NULL,
+                    modified_nanos INTEGER NOT NULL,
+                    fingerprint INTEGER NOT NULL,
+                    packet BLOB NOT NULL
+                )",
+                [],
+            )?;
+            Ok(SqliteCache { connection })
+        }
+    }
+
+    impl ScanCache for SqliteCache {
+        fn get(&self, path: &Path) -> Option<CachedHeader> {
+            let path = path.to_str()?;
+            let row = self
+                .connection
+                .query_row(
+                    "SELECT size, modified_secs, modified_nanos, fingerprint, packet
+                     FROM scan_cache WHERE path = ?1",
+                    params![path],
+                    |row| {
+                        Ok((
+                            row.get::<_, i64>(0)?,
+                            row.get::<_, i64>(1)?,
+                            row.get::<_, u32>(2)?,
+                            row.get::<_, u32>(3)?,
+                            row.get::<_, Vec<u8>>(4)?,
+                        ))
+                    },
+                )
+                .optional()
+                .ok()??;
+            Some(CachedHeader {
+                size: row.0 as u64,
+                modified: stored_time(row.1 as u64, row.2)?,
+                fingerprint: row.3,
+                header: parse_comment_header(&row.4).ok()?,
+            })
+        }
+
+        fn insert(&mut self, path: PathBuf, entry: CachedHeader) {
+            let (path, packet) = match (path.to_str(), safe_make_comment_header(&entry.header)) {
+                (Some(path), Ok(packet)) => (path.to_string(), packet),
+                _ => return,
+            };
+            let _ = self.connection.execute(
+                "INSERT OR REPLACE INTO scan_cache VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    path,
+                    entry.size as i64,
+                    entry.modified.as_secs() as i64,
+                    entry.modified.subsec_nanos(),
+                    entry.fingerprint,
+                    packet
+                ],
+            );
+        }
+    }
+}
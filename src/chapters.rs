@@ -0,0 +1,126 @@
+// Chapters in the CHAPTER001 / CHAPTER001NAME / CHAPTER001URL convention,
+// see https://wiki.xiph.org/Chapter_Extension
+
+use crate::{CommentHeader, VorbisComments};
+use std::time::Duration;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chapter {
+    /// The number in the tag name, 1 for CHAPTER001.
+    pub index: u32,
+    pub start: Duration,
+    pub name: Option<String>,
+    pub url: Option<String>,
+}
+
+/// Parse "HH:MM:SS.mmm". The fraction is optional and may have any number of digits.
+pub fn parse_timestamp(value: &str) -> Option<Duration> {
+    let parts: Vec<&str> = value.trim().split(':').collect();
+    if parts.len() != 3 {
+        return None;
+    }
+    let hours = parts[0].parse::<u64>().ok()?;
+    let minutes = parts[1].parse::<u64>().ok()?;
+    let mut seconds_parts = parts[2].splitn(2, '.');
+    let seconds = seconds_parts.next()?.parse::<u64>().ok()?;
+    if minutes >= 60 || seconds >= 60 {
+        return None;
+    }
+    let nanos = match seconds_parts.next() {
+        Some(fraction) if !fraction.is_empty() && fraction.bytes().all(|b| b.is_ascii_digit()) => {
+            let digits: String = fraction
+                .chars()
+                .chain("000000000".chars())
+                .take(9)
+                .collect();
+            digits.parse::<u32>().ok()?
+        }
+        Some(_) => return None,
+        None => 0,
+    };
+    let seconds = hours
+        .checked_mul(3600)?
+        .checked_add(minutes * 60 + seconds)?;
+    Some(Duration::new(seconds, nanos))
+}
+
+/// Format as "HH:MM:SS.mmm".
+pub fn format_timestamp(time: Duration) -> String {
+    let secs = time.as_secs();
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        time.subsec_millis()
+    )
+}
+
+/// Split "CHAPTER012NAME" into (12, "NAME").
+fn parse_chapter_key(key: &str) -> Option<(u32, String)> {
+    let upper = key.to_uppercase();
+    let rest = upper.strip_prefix("CHAPTER")?;
+    let digits = rest.bytes().take_while(|b| b.is_ascii_digit()).count();
+    if digits == 0 {
+        return None;
+    }
+    let index = rest[..digits].parse::<u32>().ok()?;
+    Some((index, rest[digits..].to_string()))
+}
+
+pub trait VorbisChapters {
+    /// Chapters sorted by index. Chapters without a valid start time are skipped.
+    fn chapters(&self) -> Vec<Chapter>;
+    /// Replace all chapter tags. The chapters are sorted by start time and
+    /// numbered from 1, zero-padded to three digits.
+    fn set_chapters(&mut self, chapters: &[Chapter]);
+}
+
+impl VorbisChapters for CommentHeader {
+    fn chapters(&self) -> Vec<Chapter> {
+        let mut chapters: Vec<Chapter> = vec![];
+        for (key, value) in self.iter() {
+            if let Some((index, suffix)) = parse_chapter_key(key) {
+                if suffix.is_empty() {
+                    if let Some(start) = parse_timestamp(value) {
+                        chapters.push(Chapter {
+                            index,
+                            start,
+                            name: None,
+                            url: None,
+                        });
+                    }
+                }
+            }
+        }
+        for (key, value) in self.iter() {
+            if let Some((index, suffix)) = parse_chapter_key(key) {
+                if let Some(chapter) = chapters.iter_mut().find(|c| c.index == index) {
+                    match suffix.as_str() {
+                        "NAME" => chapter.name = Some(value.to_string()),
+                        "URL" => chapter.url = Some(value.to_string()),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        chapters.sort_by_key(|chapter| chapter.index);
+        chapters
+    }
+
+    fn set_chapters(&mut self, chapters: &[Chapter]) {
+        self.retain(|key, _| parse_chapter_key(key).is_none());
+        let mut sorted = chapters.to_vec();
+        sorted.sort_by_key(|chapter| chapter.start);
+        for (number, chapter) in sorted.iter().enumerate() {
+            let key = format!("CHAPTER{:03}", number + 1);
+            self.add_tag_single(&key, &format_timestamp(chapter.start));
+            if let Some(name) = &chapter.name {
+                self.add_tag_single(format!("{}NAME", key), name);
+            }
+            if let Some(url) = &chapter.url {
+                self.add_tag_single(format!("{}URL", key), url);
+            }
+        }
+    }
+}
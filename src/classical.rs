@@ -0,0 +1,98 @@
+// WORK and MOVEMENT tags for classical music
+
+use crate::keys;
+use crate::numbering::{read_position, write_position, Position};
+use crate::{CommentHeader, VorbisComments};
+
+/// Roman numeral for 1 to 3999, `None` outside that range.
+pub fn roman_numeral(number: u32) -> Option<String> {
+    if !(1..4000).contains(&number) {
+        return None;
+    }
+    let symbols = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut rest = number;
+    let mut numeral = String::new();
+    for (value, symbol) in symbols.iter() {
+        while rest >= *value {
+            numeral.push_str(symbol);
+            rest -= value;
+        }
+    }
+    Some(numeral)
+}
+
+fn set_optional(header: &mut CommentHeader, key: &str, value: Option<&str>) {
+    header.clear_tag(key);
+    if let Some(value) = value {
+        header.add_tag_single(key, value);
+    }
+}
+
+pub trait VorbisClassical {
+    fn work(&self) -> Option<String>;
+    fn set_work(&mut self, work: Option<&str>);
+    /// MOVEMENT, with the total taken from "n/total" or MOVEMENTTOTAL.
+    fn movement(&self) -> Option<Position>;
+    /// Write MOVEMENT and MOVEMENTTOTAL, or remove both when `None`.
+    fn set_movement(&mut self, movement: Option<Position>);
+    fn movement_name(&self) -> Option<String>;
+    fn set_movement_name(&mut self, name: Option<&str>);
+    /// "Work: II. Movement name" when WORK and MOVEMENTNAME are set, without
+    /// the numeral if there is no MOVEMENT. Otherwise TITLE.
+    fn display_title(&self) -> Option<String>;
+}
+
+impl VorbisClassical for CommentHeader {
+    fn work(&self) -> Option<String> {
+        self.get_tag_single(keys::WORK)
+    }
+
+    fn set_work(&mut self, work: Option<&str>) {
+        set_optional(self, keys::WORK, work);
+    }
+
+    fn movement(&self) -> Option<Position> {
+        read_position(self, keys::MOVEMENT, &[keys::MOVEMENTTOTAL])
+    }
+
+    fn set_movement(&mut self, movement: Option<Position>) {
+        write_position(self, keys::MOVEMENT, &[keys::MOVEMENTTOTAL], movement);
+    }
+
+    fn movement_name(&self) -> Option<String> {
+        self.get_tag_single(keys::MOVEMENTNAME)
+    }
+
+    fn set_movement_name(&mut self, name: Option<&str>) {
+        set_optional(self, keys::MOVEMENTNAME, name);
+    }
+
+    fn display_title(&self) -> Option<String> {
+        match (self.work(), self.movement_name()) {
+            (Some(work), Some(name)) => {
+                let numeral = self
+                    .movement()
+                    .and_then(|movement| roman_numeral(movement.number));
+                Some(match numeral {
+                    Some(numeral) => format!("{}: {}. {}", work, numeral, name),
+                    None => format!("{}: {}", work, name),
+                })
+            }
+            _ => self.get_tag_single(keys::TITLE),
+        }
+    }
+}
@@ -0,0 +1,26 @@
+// The COMPILATION flag
+
+use crate::keys;
+use crate::{CommentHeader, VorbisComments};
+
+pub trait VorbisCompilation {
+    /// COMPILATION read as "1"/"0", "true"/"false" or "yes"/"no", in any
+    /// case. A missing or unrecognized value is `false`.
+    fn is_compilation(&self) -> bool;
+    /// Write COMPILATION as "1" or "0".
+    fn set_compilation(&mut self, compilation: bool);
+}
+
+impl VorbisCompilation for CommentHeader {
+    fn is_compilation(&self) -> bool {
+        match self.get_tag_single(keys::COMPILATION) {
+            Some(value) => matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes"),
+            None => false,
+        }
+    }
+
+    fn set_compilation(&mut self, compilation: bool) {
+        self.clear_tag(keys::COMPILATION);
+        self.add_tag_single(keys::COMPILATION, if compilation { "1" } else { "0" });
+    }
+}
@@ -0,0 +1,148 @@
+// Checks of a written file against the Vorbis I Ogg mapping, see
+// https://xiph.org/vorbis/doc/Vorbis_I_spec.html#x1-132000A.2
+
+use crate::page::{pages_to_packets, parse_pages, Page, ParsedPages};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConformanceIssue {
+    /// The data stops being a valid Ogg page at this byte offset.
+    MalformedPage {
+        offset: usize,
+    },
+    ChecksumMismatch {
+        sequence: u32,
+    },
+    SequenceGap {
+        expected: u32,
+        found: u32,
+    },
+    /// The first page of the stream lacks the beginning-of-stream flag.
+    MissingBeginOfStream,
+    /// A page other than the first has the beginning-of-stream flag.
+    UnexpectedBeginOfStream {
+        sequence: u32,
+    },
+    /// The last page of the stream lacks the end-of-stream flag.
+    MissingEndOfStream,
+    /// A page other than the last has the end-of-stream flag.
+    UnexpectedEndOfStream {
+        sequence: u32,
+    },
+    /// Header packet `index` (0 to 2) is missing or has the wrong type.
+    BadHeader {
+        index: usize,
+    },
+    /// Header packet `index` doesn't end with its framing bit set.
+    MissingFramingBit {
+        index: usize,
+    },
+    /// The identification header isn't alone on the first page.
+    IdentificationNotAlone,
+    /// Audio data starts on the page where the setup header ends.
+    SetupNotPageAligned,
+    /// A page holding only header data has a granule position other than 0.
+    HeaderGranuleNotZero {
+        sequence: u32,
+    },
+}
+
+/// Validate a file produced by the replace functions. Only the first
+/// logical stream is checked. Returns an empty list for a conforming file.
+pub fn check_conformance(data: &[u8]) -> Vec<ConformanceIssue> {
+    let mut issues = vec![];
+    let ParsedPages { pages, error } = parse_pages(data);
+    if let Some((offset, _)) = error {
+        issues.push(ConformanceIssue::MalformedPage { offset });
+    }
+    let serial = match pages.first() {
+        Some((_, page)) => page.serial,
+        None => return issues,
+    };
+    let pages: Vec<&Page> = pages
+        .iter()
+        .map(|(_, page)| page)
+        .filter(|page| page.serial == serial)
+        .collect();
+
+    let last = pages.len() - 1;
+    for (index, page) in pages.iter().enumerate() {
+        if page.compute_checksum() != page.checksum {
+            issues.push(ConformanceIssue::ChecksumMismatch {
+                sequence: page.sequence,
+            });
+        }
+        if page.sequence != index as u32 {
+            issues.push(ConformanceIssue::SequenceGap {
+                expected: index as u32,
+                found: page.sequence,
+            });
+        }
+        if index == 0 && !page.is_first() {
+            issues.push(ConformanceIssue::MissingBeginOfStream);
+        }
+        if index != 0 && page.is_first() {
+            issues.push(ConformanceIssue::UnexpectedBeginOfStream {
+                sequence: page.sequence,
+            });
+        }
+        if index == last && !page.is_last() {
+            issues.push(ConformanceIssue::MissingEndOfStream);
+        }
+        if index != last && page.is_last() {
+            issues.push(ConformanceIssue::UnexpectedEndOfStream {
+                sequence: page.sequence,
+            });
+        }
+    }
+
+    let packets = pages_to_packets(pages.iter().copied());
+    for (index, packet_type) in [1u8, 3, 5].iter().enumerate() {
+        let packet = match packets.get(index) {
+            Some(packet) if packet.data.len() > 7 && packet.data[0] == *packet_type => packet,
+            _ => {
+                issues.push(ConformanceIssue::BadHeader { index });
+                continue;
+            }
+        };
+        if &packet.data[1..7] != b"vorbis" {
+            issues.push(ConformanceIssue::BadHeader { index });
+        }
+        let framing = packet.data[packet.data.len() - 1];
+        // The setup header's framing bit follows a bit-packed payload
+        let framed = if index == 2 {
+            framing != 0
+        } else {
+            framing & 1 == 1
+        };
+        if !framed {
+            issues.push(ConformanceIssue::MissingFramingBit { index });
+        }
+    }
+
+    if let Some(ident) = packets.first() {
+        let alone = ident.last_page == 0
+            && packets
+                .get(1)
+                .map(|next| next.first_page > 0)
+                .unwrap_or(true);
+        if !alone {
+            issues.push(ConformanceIssue::IdentificationNotAlone);
+        }
+    }
+    if let Some(setup) = packets.get(2) {
+        if let Some(audio) = packets.get(3) {
+            if audio.first_page == setup.last_page {
+                issues.push(ConformanceIssue::SetupNotPageAligned);
+            }
+        }
+        for page in pages.iter().take(setup.last_page + 1) {
+            // -1 marks a page on which no packet ends
+            if page.granule_position != 0 && page.granule_position != u64::MAX {
+                issues.push(ConformanceIssue::HeaderGranuleNotZero {
+                    sequence: page.sequence,
+                });
+            }
+        }
+    }
+    issues
+}
@@ -0,0 +1,63 @@
+// Copying the tags of one file to another, e.g. after transcoding
+
+use crate::batch::{read_path, stage, VorbisBatchError};
+use crate::find_replace::FieldFilter;
+use crate::{safe_read_comment_header, CommentHeader, VorbisComments};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+#[derive(Clone, Debug)]
+pub struct CopyOptions {
+    /// The tags to copy, all by default.
+    pub filter: FieldFilter,
+    /// Keep the vendor string of the destination, which names the encoder
+    /// that actually produced it, instead of copying the source's.
+    /// On by default.
+    pub keep_destination_vendor: bool,
+}
+
+impl Default for CopyOptions {
+    fn default() -> CopyOptions {
+        CopyOptions {
+            filter: FieldFilter::All,
+            keep_destination_vendor: true,
+        }
+    }
+}
+
+/// Replace the tags of the file at `dst` with the tags of the file at `src`.
+pub fn copy_comment_header<P: AsRef<Path>, Q: AsRef<Path>>(
+    src: P,
+    dst: Q,
+    options: &CopyOptions,
+) -> Result<(), VorbisBatchError> {
+    let header = read_path(src.as_ref())?;
+    write_copy(header, dst.as_ref(), options)
+}
+
+/// `copy_comment_header` with the source tags read from `src`.
+pub fn copy_comment_header_from<R: Read, Q: AsRef<Path>>(
+    src: R,
+    dst: Q,
+    options: &CopyOptions,
+) -> Result<(), VorbisBatchError> {
+    let header = safe_read_comment_header(src).map_err(VorbisBatchError::ReadSource)?;
+    write_copy(header, dst.as_ref(), options)
+}
+
+fn write_copy(
+    mut header: CommentHeader,
+    dst: &Path,
+    options: &CopyOptions,
+) -> Result<(), VorbisBatchError> {
+    header.retain(|key, _| options.filter.matches(key));
+    if options.keep_destination_vendor {
+        header.set_vendor(&read_path(dst)?.get_vendor());
+    }
+    let staged = stage(dst, header)?;
+    fs::rename(&staged, dst).map_err(|err| {
+        let _ = fs::remove_file(&staged);
+        VorbisBatchError::Io(dst.to_path_buf(), err)
+    })
+}
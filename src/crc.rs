@@ -0,0 +1,56 @@
+// The CRC32 variant used for Ogg page checksums: polynomial 0x04c11db7,
+// initial value 0, no reflection and no final xor.
+
+const fn make_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut r = (i as u32) << 24;
+        let mut bit = 0;
+        while bit < 8 {
+            r = if r & 0x8000_0000 != 0 {
+                (r << 1) ^ 0x04c1_1db7
+            } else {
+                r << 1
+            };
+            bit += 1;
+        }
+        table[i] = r;
+        i += 1;
+    }
+    table
+}
+
+static TABLE: [u32; 256] = make_table();
+
+pub(crate) fn crc32_update(crc: u32, bytes: &[u8]) -> u32 {
+    bytes.iter().fold(crc, |crc, byte| {
+        (crc << 8) ^ TABLE[((crc >> 24) as u8 ^ byte) as usize]
+    })
+}
+
+/// CRC32 of `bytes` as used for Ogg page checksums. A page's checksum is
+/// computed over the whole page with its checksum field set to zero.
+pub fn ogg_crc32(bytes: &[u8]) -> u32 {
+    crc32_update(0, bytes)
+}
+
+/// Incremental `ogg_crc32`, for data that arrives in pieces.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OggCrc32 {
+    crc: u32,
+}
+
+impl OggCrc32 {
+    pub fn new() -> OggCrc32 {
+        OggCrc32::default()
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.crc = crc32_update(self.crc, bytes);
+    }
+
+    pub fn finish(&self) -> u32 {
+        self.crc
+    }
+}
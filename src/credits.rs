@@ -0,0 +1,100 @@
+// PERFORMER values in the "Name (instrument)" convention
+
+use crate::keys;
+use crate::{CommentHeader, VorbisComments};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Performer {
+    pub name: String,
+    /// The part in parentheses, such as "piano" or "vocals".
+    pub role: Option<String>,
+}
+
+impl Performer {
+    /// Split "Name (role)". A value without a trailing parenthesized part
+    /// is only a name.
+    pub fn parse(value: &str) -> Performer {
+        let value = value.trim();
+        if let Some(rest) = value.strip_suffix(')') {
+            if let Some(open) = rest.rfind('(') {
+                let name = rest[..open].trim();
+                let role = rest[open + 1..].trim();
+                if !name.is_empty() && !role.is_empty() {
+                    return Performer {
+                        name: name.to_string(),
+                        role: Some(role.to_string()),
+                    };
+                }
+            }
+        }
+        Performer {
+            name: value.to_string(),
+            role: None,
+        }
+    }
+
+    pub fn to_tag_value(&self) -> String {
+        match &self.role {
+            Some(role) => format!("{} ({})", self.name, role),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// A performer with all the roles they are credited for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Credit {
+    pub name: String,
+    pub roles: Vec<String>,
+}
+
+pub trait VorbisCredits {
+    /// Every PERFORMER value, parsed.
+    fn performers(&self) -> Vec<Performer>;
+    /// Replace the PERFORMER tags, one per performer and role.
+    fn set_performers(&mut self, performers: &[Performer]);
+    /// The performers grouped by name, in order of first appearance.
+    fn credits(&self) -> Vec<Credit>;
+}
+
+impl VorbisCredits for CommentHeader {
+    fn performers(&self) -> Vec<Performer> {
+        self.get_tag_multi(keys::PERFORMER)
+            .iter()
+            .map(|value| Performer::parse(value))
+            .collect()
+    }
+
+    fn set_performers(&mut self, performers: &[Performer]) {
+        self.clear_tag(keys::PERFORMER);
+        for performer in performers.iter() {
+            self.add_tag_single(keys::PERFORMER, &performer.to_tag_value());
+        }
+    }
+
+    fn credits(&self) -> Vec<Credit> {
+        let mut credits: Vec<Credit> = vec![];
+        for performer in self.performers() {
+            let index = match credits
+                .iter()
+                .position(|credit| credit.name == performer.name)
+            {
+                Some(index) => index,
+                None => {
+                    credits.push(Credit {
+                        name: performer.name.clone(),
+                        roles: vec![],
+                    });
+                    credits.len() - 1
+                }
+            };
+            let roles = &mut credits[index].roles;
+            if let Some(role) = performer.role {
+                if !roles.contains(&role) {
+                    roles.push(role);
+                }
+            }
+        }
+        credits
+    }
+}
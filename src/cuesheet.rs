@@ -0,0 +1,250 @@
+// Cue sheets embedded in a CUESHEET tag
+
+use crate::keys;
+use crate::{CommentHeader, VorbisComments};
+use std::time::Duration;
+
+use thiserror::Error;
+
+/// Cue sheet positions count frames of 1/75 second.
+pub const CUE_FRAMES_PER_SECOND: u64 = 75;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum VorbisCueSheetError {
+    #[error("unterminated quote on cue sheet line {0}")]
+    UnterminatedQuote(usize),
+    #[error("missing or invalid argument on cue sheet line {0}")]
+    InvalidArgument(usize),
+    #[error("INDEX outside of a TRACK on cue sheet line {0}")]
+    IndexOutsideTrack(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CueIndex {
+    pub number: u32,
+    /// Position in frames, see `CUE_FRAMES_PER_SECOND`.
+    pub frames: u64,
+}
+
+impl CueIndex {
+    pub fn start(&self) -> Duration {
+        let frames = self.frames % CUE_FRAMES_PER_SECOND;
+        Duration::from_secs(self.frames / CUE_FRAMES_PER_SECOND)
+            + Duration::from_nanos(frames * 1_000_000_000 / CUE_FRAMES_PER_SECOND)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CueTrack {
+    pub number: u32,
+    /// The track datatype, "AUDIO" for audio tracks.
+    pub track_type: String,
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    pub isrc: Option<String>,
+    pub indexes: Vec<CueIndex>,
+    /// Other commands of the track (FLAGS, PREGAP, REM...), kept verbatim.
+    pub extra: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CueSheet {
+    pub catalog: Option<String>,
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    pub file: Option<String>,
+    /// The file type, such as "WAVE".
+    pub file_type: Option<String>,
+    pub tracks: Vec<CueTrack>,
+    /// Other commands before the first track, kept verbatim.
+    pub extra: Vec<String>,
+}
+
+/// Parse "MM:SS:FF" into frames, `None` if it doesn't fit a `u64`.
+pub fn parse_cue_time(value: &str) -> Option<u64> {
+    let parts: Vec<&str> = value.split(':').collect();
+    if parts.len() != 3 || parts.iter().any(|part| part.is_empty()) {
+        return None;
+    }
+    let minutes = parts[0].parse::<u64>().ok()?;
+    let seconds = parts[1].parse::<u64>().ok()?;
+    let frames = parts[2].parse::<u64>().ok()?;
+    if seconds >= 60 || frames >= CUE_FRAMES_PER_SECOND {
+        return None;
+    }
+    minutes
+        .checked_mul(60)?
+        .checked_add(seconds)?
+        .checked_mul(CUE_FRAMES_PER_SECOND)?
+        .checked_add(frames)
+}
+
+/// Format frames as "MM:SS:FF".
+pub fn format_cue_time(frames: u64) -> String {
+    let seconds = frames / CUE_FRAMES_PER_SECOND;
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 60,
+        seconds % 60,
+        frames % CUE_FRAMES_PER_SECOND
+    )
+}
+
+/// Split a line into words, where "quoted strings" are one word.
+fn split_words(line: &str, line_number: usize) -> Result<Vec<String>, VorbisCueSheetError> {
+    let mut words = vec![];
+    let mut chars = line.trim().chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut word = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(c) => word.push(c),
+                    None => return Err(VorbisCueSheetError::UnterminatedQuote(line_number)),
+                }
+            }
+            words.push(word);
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            words.push(word);
+        }
+    }
+    Ok(words)
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "'"))
+}
+
+impl CueSheet {
+    pub fn parse(text: &str) -> Result<CueSheet, VorbisCueSheetError> {
+        let mut sheet = CueSheet::default();
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let words = split_words(line, line_number)?;
+            let command = match words.first() {
+                Some(command) => command.to_uppercase(),
+                None => continue,
+            };
+            let argument = |n: usize| {
+                words
+                    .get(n)
+                    .cloned()
+                    .ok_or(VorbisCueSheetError::InvalidArgument(line_number))
+            };
+            let track = sheet.tracks.last_mut();
+            match (command.as_str(), track) {
+                ("TRACK", _) => {
+                    let number = argument(1)?
+                        .parse::<u32>()
+                        .map_err(|_| VorbisCueSheetError::InvalidArgument(line_number))?;
+                    sheet.tracks.push(CueTrack {
+                        number,
+                        track_type: argument(2)?,
+                        ..Default::default()
+                    });
+                }
+                ("INDEX", Some(track)) => {
+                    let number = argument(1)?
+                        .parse::<u32>()
+                        .map_err(|_| VorbisCueSheetError::InvalidArgument(line_number))?;
+                    let frames = parse_cue_time(&argument(2)?)
+                        .ok_or(VorbisCueSheetError::InvalidArgument(line_number))?;
+                    track.indexes.push(CueIndex { number, frames });
+                }
+                ("INDEX", None) => return Err(VorbisCueSheetError::IndexOutsideTrack(line_number)),
+                ("TITLE", Some(track)) => track.title = Some(argument(1)?),
+                ("PERFORMER", Some(track)) => track.performer = Some(argument(1)?),
+                ("ISRC", Some(track)) => track.isrc = Some(argument(1)?),
+                (_, Some(track)) => track.extra.push(line.trim().to_string()),
+                ("TITLE", None) => sheet.title = Some(argument(1)?),
+                ("PERFORMER", None) => sheet.performer = Some(argument(1)?),
+                ("CATALOG", None) => sheet.catalog = Some(argument(1)?),
+                ("FILE", None) => {
+                    sheet.file = Some(argument(1)?);
+                    sheet.file_type = words.get(2).cloned();
+                }
+                (_, None) => sheet.extra.push(line.trim().to_string()),
+            }
+        }
+        Ok(sheet)
+    }
+
+    /// Serialize as cue sheet text, with CRLF line endings.
+    pub fn to_tag_value(&self) -> String {
+        let mut lines = vec![];
+        lines.extend(self.extra.iter().cloned());
+        if let Some(catalog) = &self.catalog {
+            lines.push(format!("CATALOG {}", catalog));
+        }
+        if let Some(performer) = &self.performer {
+            lines.push(format!("PERFORMER {}", quote(performer)));
+        }
+        if let Some(title) = &self.title {
+            lines.push(format!("TITLE {}", quote(title)));
+        }
+        if let Some(file) = &self.file {
+            match &self.file_type {
+                Some(file_type) => lines.push(format!("FILE {} {}", quote(file), file_type)),
+                None => lines.push(format!("FILE {}", quote(file))),
+            }
+        }
+        for track in self.tracks.iter() {
+            lines.push(format!("  TRACK {:02} {}", track.number, track.track_type));
+            if let Some(title) = &track.title {
+                lines.push(format!("    TITLE {}", quote(title)));
+            }
+            if let Some(performer) = &track.performer {
+                lines.push(format!("    PERFORMER {}", quote(performer)));
+            }
+            if let Some(isrc) = &track.isrc {
+                lines.push(format!("    ISRC {}", isrc));
+            }
+            for extra in track.extra.iter() {
+                lines.push(format!("    {}", extra));
+            }
+            for index in track.indexes.iter() {
+                lines.push(format!(
+                    "    INDEX {:02} {}",
+                    index.number,
+                    format_cue_time(index.frames)
+                ));
+            }
+        }
+        let mut text = lines.join("\r\n");
+        text.push_str("\r\n");
+        text
+    }
+}
+
+pub trait VorbisCueSheet {
+    /// The parsed CUESHEET tag, `None` if there is none.
+    fn cue_sheet(&self) -> Result<Option<CueSheet>, VorbisCueSheetError>;
+    fn set_cue_sheet(&mut self, sheet: Option<&CueSheet>);
+}
+
+impl VorbisCueSheet for CommentHeader {
+    fn cue_sheet(&self) -> Result<Option<CueSheet>, VorbisCueSheetError> {
+        self.get_tag_single(keys::CUESHEET)
+            .map(|text| CueSheet::parse(&text))
+            .transpose()
+    }
+
+    fn set_cue_sheet(&mut self, sheet: Option<&CueSheet>) {
+        self.clear_tag(keys::CUESHEET);
+        if let Some(sheet) = sheet {
+            self.add_tag_single(keys::CUESHEET, &sheet.to_tag_value());
+        }
+    }
+}
@@ -0,0 +1,92 @@
+// Typed DATE and ORIGINALDATE values, enabled by the `chrono` feature
+
+use crate::keys;
+use crate::{CommentHeader, VorbisComments};
+use chrono::{Datelike, NaiveDate};
+
+/// A date tag value at the precision it was written with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TagDate {
+    Year(i32),
+    YearMonth(i32, u32),
+    Date(NaiveDate),
+    /// Anything that isn't YYYY, YYYY-MM or YYYY-MM-DD, kept verbatim.
+    Unparsed(String),
+}
+
+fn parse_part(part: &str, len: usize) -> Option<u32> {
+    if part.len() == len && part.bytes().all(|b| b.is_ascii_digit()) {
+        part.parse().ok()
+    } else {
+        None
+    }
+}
+
+impl TagDate {
+    pub fn parse(value: &str) -> TagDate {
+        let parts: Vec<&str> = value.trim().split('-').collect();
+        let year = parts.first().and_then(|part| parse_part(part, 4));
+        let month = parts.get(1).and_then(|part| parse_part(part, 2));
+        let day = parts.get(2).and_then(|part| parse_part(part, 2));
+        let parsed = match (parts.len(), year, month, day) {
+            (1, Some(year), _, _) => Some(TagDate::Year(year as i32)),
+            (2, Some(year), Some(month), _) if (1..=12).contains(&month) => {
+                Some(TagDate::YearMonth(year as i32, month))
+            }
+            (3, Some(year), Some(month), Some(day)) => {
+                NaiveDate::from_ymd_opt(year as i32, month, day).map(TagDate::Date)
+            }
+            _ => None,
+        };
+        parsed.unwrap_or_else(|| TagDate::Unparsed(value.to_string()))
+    }
+
+    /// The value in ISO 8601 form, or the original text if it was unparsed.
+    pub fn to_tag_value(&self) -> String {
+        match self {
+            TagDate::Year(year) => format!("{:04}", year),
+            TagDate::YearMonth(year, month) => format!("{:04}-{:02}", year, month),
+            TagDate::Date(date) => {
+                format!("{:04}-{:02}-{:02}", date.year(), date.month(), date.day())
+            }
+            TagDate::Unparsed(value) => value.clone(),
+        }
+    }
+
+    pub fn year(&self) -> Option<i32> {
+        match self {
+            TagDate::Year(year) | TagDate::YearMonth(year, _) => Some(*year),
+            TagDate::Date(date) => Some(date.year()),
+            TagDate::Unparsed(_) => None,
+        }
+    }
+}
+
+pub trait VorbisDates {
+    fn parsed_date(&self) -> Option<TagDate>;
+    fn set_parsed_date(&mut self, date: &TagDate);
+    fn parsed_original_date(&self) -> Option<TagDate>;
+    fn set_parsed_original_date(&mut self, date: &TagDate);
+}
+
+impl VorbisDates for CommentHeader {
+    fn parsed_date(&self) -> Option<TagDate> {
+        self.get_tag_single(keys::DATE)
+            .map(|date| TagDate::parse(&date))
+    }
+
+    fn set_parsed_date(&mut self, date: &TagDate) {
+        self.clear_tag(keys::DATE);
+        self.add_tag_single(keys::DATE, &date.to_tag_value());
+    }
+
+    fn parsed_original_date(&self) -> Option<TagDate> {
+        self.get_tag_single(keys::ORIGINALDATE)
+            .map(|date| TagDate::parse(&date))
+    }
+
+    fn set_parsed_original_date(&mut self, date: &TagDate) {
+        self.clear_tag(keys::ORIGINALDATE);
+        self.add_tag_single(keys::ORIGINALDATE, &date.to_tag_value());
+    }
+}
@@ -0,0 +1,82 @@
+// Differences between the tags of two headers
+
+use crate::{CommentHeader, VorbisComments};
+use std::fmt;
+
+/// A tag present in both headers with different values.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TagChange {
+    /// The lowercase tag name.
+    pub tag: String,
+    pub old: Vec<String>,
+    pub new: Vec<String>,
+}
+
+/// What changes going from one header to another, returned by `diff`.
+/// Tags are compared by lowercase name, in name order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TagDiff {
+    /// `(tag, value)` pairs of the tags only the new header has.
+    pub added: Vec<(String, String)>,
+    /// `(tag, value)` pairs of the tags only the old header has.
+    pub removed: Vec<(String, String)>,
+    pub changed: Vec<TagChange>,
+}
+
+impl TagDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for TagDiff {
+    /// One line per value: "+tag=value", "-tag=value", or "~tag=old -> new"
+    /// with multiple values joined by "; ".
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (tag, value) in self.removed.iter() {
+            writeln!(f, "-{}={}", tag, value)?;
+        }
+        for (tag, value) in self.added.iter() {
+            writeln!(f, "+{}={}", tag, value)?;
+        }
+        for change in self.changed.iter() {
+            writeln!(
+                f,
+                "~{}={} -> {}",
+                change.tag,
+                change.old.join("; "),
+                change.new.join("; ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The tags added, removed and changed from `old` to `new`. The vendor and
+/// the case of tag names are not compared.
+pub fn diff(old: &CommentHeader, new: &CommentHeader) -> TagDiff {
+    let mut result = TagDiff::default();
+    let old_map = old.to_map();
+    let new_map = new.to_map();
+    for (tag, old_values) in old_map.iter() {
+        match new_map.get(tag) {
+            None => result
+                .removed
+                .extend(old_values.iter().map(|value| (tag.clone(), value.clone()))),
+            Some(new_values) if new_values != old_values => result.changed.push(TagChange {
+                tag: tag.clone(),
+                old: old_values.clone(),
+                new: new_values.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (tag, new_values) in new_map.iter() {
+        if !old_map.contains_key(tag) {
+            result
+                .added
+                .extend(new_values.iter().map(|value| (tag.clone(), value.clone())));
+        }
+    }
+    result
+}
@@ -0,0 +1,65 @@
+//! Cross-checking the comment header parser against lewton.
+//!
+//! With the `differential` feature, every comment header read by this crate
+//! is also parsed with lewton. Any disagreement between the two is passed to
+//! the reporter set with `set_reporter`.
+
+use crate::parse::parse_comment_header;
+use crate::CommentHeader;
+use std::sync::RwLock;
+
+/// The outcome of one parser, errors are kept as their debug text.
+pub type ParseOutcome = Result<CommentHeader, String>;
+
+/// A comment packet the two parsers disagree on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Finding {
+    pub packet: Vec<u8>,
+    pub internal: ParseOutcome,
+    pub lewton: ParseOutcome,
+}
+
+static REPORTER: RwLock<Option<fn(&Finding)>> = RwLock::new(None);
+
+/// Set the function receiving findings, replacing any earlier one.
+/// Without a reporter, findings are dropped.
+pub fn set_reporter(reporter: Option<fn(&Finding)>) {
+    if let Ok(mut current) = REPORTER.write() {
+        *current = reporter;
+    }
+}
+
+/// Parse the packet with both parsers. Returns a finding if one of them
+/// fails where the other succeeds, or if they produce different headers.
+/// Failing with different errors is not considered a disagreement.
+pub fn compare(packet: &[u8]) -> Option<Finding> {
+    let internal = parse_comment_header(packet).map_err(|err| format!("{:?}", err));
+    let lewton = lewton::header::read_header_comment(packet)
+        .map(Into::into)
+        .map_err(|err| format!("{:?}", err));
+    let agree = match (&internal, &lewton) {
+        (Ok(a), Ok(b)) => a == b,
+        (Err(_), Err(_)) => true,
+        _ => false,
+    };
+    if agree {
+        None
+    } else {
+        Some(Finding {
+            packet: packet.to_vec(),
+            internal,
+            lewton,
+        })
+    }
+}
+
+/// Compare and pass any finding to the reporter.
+pub(crate) fn check(packet: &[u8]) {
+    if let Some(finding) = compare(packet) {
+        if let Ok(reporter) = REPORTER.read() {
+            if let Some(reporter) = *reporter {
+                reporter(&finding);
+            }
+        }
+    }
+}
@@ -0,0 +1,81 @@
+// Stream duration, from the last granule position and the sample rate
+
+use crate::page::{Page, CAPTURE_PATTERN};
+use crate::VorbisReadCommentError;
+use lewton::header::HeaderReadError;
+use ogg::OggReadError;
+use std::io::{Read, Seek, SeekFrom};
+use std::time::Duration;
+
+/// How far from the end to look for the last page first.
+const TAIL_LEN: u64 = 64 * 1024;
+
+/// Granule position of the last valid page of `serial` in `data`, found by
+/// scanning for the capture pattern.
+fn last_granule(data: &[u8], serial: u32) -> Option<u64> {
+    let mut last = None;
+    let mut pos = 0;
+    while pos + CAPTURE_PATTERN.len() <= data.len() {
+        if &data[pos..pos + CAPTURE_PATTERN.len()] != CAPTURE_PATTERN {
+            pos += 1;
+            continue;
+        }
+        match Page::parse(&data[pos..]) {
+            Ok((page, size)) if page.compute_checksum() == page.checksum => {
+                if page.serial == serial && page.granule_position != u64::MAX {
+                    last = Some(page.granule_position);
+                }
+                pos += size;
+            }
+            _ => pos += 1,
+        }
+    }
+    last
+}
+
+/// Duration of the first logical stream: the granule position of its last
+/// page divided by the sample rate of the identification header.
+pub fn safe_read_duration<T: Read + Seek>(mut f_in: T) -> Result<Duration, VorbisReadCommentError> {
+    let read_err = |err| VorbisReadCommentError::FailedReadOggFile(OggReadError::ReadError(err));
+    let mut start = vec![];
+    (&mut f_in)
+        .take(TAIL_LEN)
+        .read_to_end(&mut start)
+        .map_err(read_err)?;
+    let (first, _) = Page::parse(&start).map_err(|_| VorbisReadCommentError::Truncated)?;
+    let ident = &first.body;
+    if ident.len() < 16 || &ident[0..7] != b"\x01vorbis" {
+        return Err(HeaderReadError::NotVorbisHeader.into());
+    }
+    let rate = u32::from_le_bytes([ident[12], ident[13], ident[14], ident[15]]);
+    if rate == 0 {
+        return Err(HeaderReadError::HeaderBadFormat.into());
+    }
+
+    let len = f_in.seek(SeekFrom::End(0)).map_err(read_err)?;
+    let mut granule = if len <= TAIL_LEN {
+        last_granule(&start, first.serial)
+    } else {
+        let mut tail = vec![];
+        f_in.seek(SeekFrom::Start(len - TAIL_LEN))
+            .and_then(|_| f_in.read_to_end(&mut tail))
+            .map_err(read_err)?;
+        last_granule(&tail, first.serial)
+    };
+    if granule.is_none() && len > TAIL_LEN {
+        // The last page may be larger than the tail, read it all
+        let mut data = vec![];
+        f_in.seek(SeekFrom::Start(0))
+            .and_then(|_| f_in.read_to_end(&mut data))
+            .map_err(read_err)?;
+        granule = last_granule(&data, first.serial);
+    }
+    let samples = granule.unwrap_or(0);
+    let rate = rate as u64;
+    Ok(Duration::from_secs(samples / rate)
+        + Duration::from_nanos((samples % rate) * 1_000_000_000 / rate))
+}
+
+pub fn read_duration<T: Read + Seek>(f_in: T) -> Duration {
+    safe_read_duration(f_in).unwrap()
+}
@@ -0,0 +1,71 @@
+// Text written in the legacy 8-bit encodings by non-compliant taggers
+
+use crate::{CommentHeader, VorbisComments};
+
+/// An 8-bit encoding some old taggers wrote comments in instead of UTF-8.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LegacyEncoding {
+    /// ISO-8859-1, each byte is the code point of the same value.
+    Latin1,
+    /// Latin-1 with printable characters in 0x80 to 0x9F, such as '€' and
+    /// curly quotes. The five bytes it leaves undefined decode as in Latin-1.
+    Windows1252,
+}
+
+/// Characters of Windows-1252 for the bytes 0x80 to 0x9F.
+const WINDOWS_1252: [char; 32] = [
+    '\u{20ac}', '\u{81}', '\u{201a}', '\u{192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2c6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8d}', '\u{17d}', '\u{8f}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2dc}', '\u{2122}', '\u{161}', '\u{203a}', '\u{153}', '\u{9d}', '\u{17e}', '\u{178}',
+];
+
+impl LegacyEncoding {
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        bytes
+            .iter()
+            .map(|byte| match (self, byte) {
+                (LegacyEncoding::Windows1252, 0x80..=0x9f) => WINDOWS_1252[*byte as usize - 0x80],
+                _ => *byte as char,
+            })
+            .collect()
+    }
+
+    /// The bytes `text` decodes from, `None` if it has a character the
+    /// encoding lacks.
+    pub fn encode(&self, text: &str) -> Option<Vec<u8>> {
+        text.chars()
+            .map(|c| {
+                if let LegacyEncoding::Windows1252 = self {
+                    if let Some(pos) = WINDOWS_1252.iter().position(|w| *w == c) {
+                        return Some(0x80 + pos as u8);
+                    }
+                    if ('\u{80}'..='\u{9f}').contains(&c) {
+                        return None;
+                    }
+                }
+                u8::try_from(c as u32).ok()
+            })
+            .collect()
+    }
+}
+
+pub trait VorbisRecode {
+    /// Repair values that went through `encoding` on their way to UTF-8, for
+    /// instance "cafÃ©" for "café": a value whose characters encode to
+    /// bytes that are valid UTF-8 and not plain ASCII is replaced by the
+    /// decoding of those bytes. Returns how many values changed.
+    fn recode_values(&mut self, encoding: LegacyEncoding) -> usize;
+}
+
+impl VorbisRecode for CommentHeader {
+    fn recode_values(&mut self, encoding: LegacyEncoding) -> usize {
+        self.map_values(|_, value| {
+            if value.is_ascii() {
+                return None;
+            }
+            let bytes = encoding.encode(value)?;
+            String::from_utf8(bytes).ok()
+        })
+    }
+}
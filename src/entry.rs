@@ -0,0 +1,63 @@
+// Conditional edits of one tag, in the style of HashMap::entry
+
+use crate::{CommentHeader, VorbisComments};
+
+/// The values of one tag, returned by `VorbisComments::entry`.
+pub struct TagEntry<'a> {
+    header: &'a mut CommentHeader,
+    tag: String,
+}
+
+impl<'a> TagEntry<'a> {
+    pub(crate) fn new(header: &'a mut CommentHeader, tag: &str) -> TagEntry<'a> {
+        TagEntry {
+            header,
+            tag: tag.to_lowercase(),
+        }
+    }
+
+    /// The lowercase tag name.
+    pub fn key(&self) -> &str {
+        &self.tag
+    }
+
+    /// Whether the tag has at least one value.
+    pub fn is_occupied(&self) -> bool {
+        self.header.contains_tag(&self.tag)
+    }
+
+    pub fn values(&self) -> Vec<String> {
+        self.header.get_tag_multi(&self.tag)
+    }
+
+    /// Add `value` if the tag has no value yet.
+    pub fn or_insert(self, value: &str) -> TagEntry<'a> {
+        self.or_insert_with(|| value.to_string())
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> String>(self, default: F) -> TagEntry<'a> {
+        if !self.is_occupied() {
+            self.header.add_tag_single(&self.tag, &default());
+        }
+        self
+    }
+
+    /// Edit every value of the tag in place.
+    pub fn and_modify<F: FnMut(&mut String)>(self, mut modify: F) -> TagEntry<'a> {
+        self.header.comments.modify_tag(&self.tag, &mut modify);
+        self
+    }
+
+    /// Add another value after the existing ones.
+    pub fn append(self, value: &str) -> TagEntry<'a> {
+        self.header.add_tag_single(&self.tag, value);
+        self
+    }
+
+    /// Remove all values, returning them.
+    pub fn remove(self) -> Vec<String> {
+        let values = self.values();
+        self.header.clear_tag(&self.tag);
+        values
+    }
+}
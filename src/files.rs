@@ -0,0 +1,175 @@
+// Reading and rewriting files by path, with the buffering done here
+
+use crate::batch::{read_path, stage_with_options, VorbisBatchError};
+use crate::{
+    is_unchanged, safe_read_comment_packet, safe_replace_comment_header_into, CommentHeader,
+    ReplaceOptions, ReplaceReport,
+};
+use std::fs::{self, File, Metadata};
+use std::io::{self, BufReader, BufWriter, Read, Seek};
+use std::path::{Path, PathBuf};
+
+/// Options for `safe_replace_comment_header_in_path_with_options`.
+#[derive(Clone, Debug, Default)]
+pub struct SaveOptions {
+    /// Keep the original file as `<name>.bak` next to the new one,
+    /// replacing any earlier backup.
+    pub backup: bool,
+    /// Give the new file the modification time of the original, so tools
+    /// that sync on it don't see a change of the audio.
+    pub preserve_modified: bool,
+    /// Give the new file the permissions of the original and, on Unix, its
+    /// owner and group when the process is allowed to change them.
+    pub preserve_permissions: bool,
+    /// Leave the file alone, not even touching its modification time, when
+    /// the new comment header would be written byte for byte as it is.
+    pub skip_unchanged: bool,
+    pub replace: ReplaceOptions,
+}
+
+/// The path the original of `path` is kept at with `SaveOptions::backup`.
+pub fn backup_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = path.as_ref();
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+pub fn safe_read_comment_header_from_path<P: AsRef<Path>>(
+    path: P,
+) -> Result<CommentHeader, VorbisBatchError> {
+    read_path(path.as_ref())
+}
+
+pub fn read_comment_header_from_path<P: AsRef<Path>>(path: P) -> CommentHeader {
+    safe_read_comment_header_from_path(path).unwrap()
+}
+
+/// Replace the comment header of the file at `path`. The new file is written
+/// next to it and renamed over it once complete, so a crash at any point
+/// leaves either the old file or the new one.
+pub fn safe_replace_comment_header_in_path<P: AsRef<Path>>(
+    path: P,
+    new_header: CommentHeader,
+) -> Result<(), VorbisBatchError> {
+    safe_replace_comment_header_in_path_with_options(path, new_header, &SaveOptions::default())?;
+    Ok(())
+}
+
+/// Returns whether the file was rewritten, which it isn't when
+/// `skip_unchanged` finds nothing to change.
+pub fn safe_replace_comment_header_in_path_with_options<P: AsRef<Path>>(
+    path: P,
+    new_header: CommentHeader,
+    options: &SaveOptions,
+) -> Result<bool, VorbisBatchError> {
+    let path = path.as_ref();
+    let io_err = |err| VorbisBatchError::Io(path.to_path_buf(), err);
+    if options.skip_unchanged {
+        let read_err = |err| VorbisBatchError::Read(path.to_path_buf(), err);
+        let f_in = File::open(path).map_err(io_err)?;
+        let old_packet = safe_read_comment_packet(BufReader::new(f_in)).map_err(read_err)?;
+        let unchanged = is_unchanged(new_header.clone(), &old_packet, &options.replace)
+            .map_err(|err| read_err(err.into()))?;
+        if unchanged {
+            return Ok(false);
+        }
+    }
+    let original = fs::metadata(path).map_err(io_err)?;
+    let staged = stage_with_options(path, new_header, &options.replace)?;
+    let saved = copy_metadata(&original, &staged, options)
+        .and_then(|_| save_backup(path, options))
+        .and_then(|_| fs::rename(&staged, path))
+        .map_err(io_err);
+    if let Err(err) = saved {
+        let _ = fs::remove_file(&staged);
+        return Err(err);
+    }
+    sync_parent(path).map_err(io_err)?;
+    Ok(true)
+}
+
+/// The time goes first, the permissions may make the file read-only.
+fn copy_metadata(original: &Metadata, staged: &Path, options: &SaveOptions) -> io::Result<()> {
+    if options.preserve_modified {
+        let modified = original.modified()?;
+        File::options()
+            .write(true)
+            .open(staged)?
+            .set_modified(modified)?;
+    }
+    if options.preserve_permissions {
+        fs::set_permissions(staged, original.permissions())?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let current = fs::metadata(staged)?;
+            if (current.uid(), current.gid()) != (original.uid(), original.gid()) {
+                match std::os::unix::fs::chown(staged, Some(original.uid()), Some(original.gid())) {
+                    Err(err) if err.kind() != io::ErrorKind::PermissionDenied => return Err(err),
+                    _ => {}
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn save_backup(path: &Path, options: &SaveOptions) -> io::Result<()> {
+    if !options.backup {
+        return Ok(());
+    }
+    let backup = backup_path(path);
+    match fs::remove_file(&backup) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+        _ => {}
+    }
+    // A link keeps the original's inode and costs no copy, where supported
+    if fs::hard_link(path, &backup).is_err() {
+        fs::copy(path, &backup)?;
+    }
+    Ok(())
+}
+
+/// Make the rename durable, on platforms where directories can be synced.
+fn sync_parent(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        let parent = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+        File::open(parent)?.sync_all()?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+pub fn replace_comment_header_in_path<P: AsRef<Path>>(path: P, new_header: CommentHeader) {
+    safe_replace_comment_header_in_path(path, new_header).unwrap()
+}
+
+/// Write `f_in` with its new header to a file created at `path`.
+pub fn safe_write_comment_header_to_path<T: Read + Seek, P: AsRef<Path>>(
+    f_in: T,
+    path: P,
+    new_header: CommentHeader,
+) -> Result<ReplaceReport, VorbisBatchError> {
+    let path = path.as_ref();
+    let io_err = |err| VorbisBatchError::Io(path.to_path_buf(), err);
+    let f_out = File::create(path).map_err(io_err)?;
+    let report = safe_replace_comment_header_into(f_in, BufWriter::new(&f_out), new_header)
+        .map_err(|err| VorbisBatchError::Replace(path.to_path_buf(), err))?;
+    f_out.sync_all().map_err(io_err)?;
+    Ok(report)
+}
+
+pub fn write_comment_header_to_path<T: Read + Seek, P: AsRef<Path>>(
+    f_in: T,
+    path: P,
+    new_header: CommentHeader,
+) -> ReplaceReport {
+    safe_write_comment_header_to_path(f_in, path, new_header).unwrap()
+}
@@ -0,0 +1,100 @@
+// Find and replace across tag values
+
+use crate::batch::{read_path, safe_replace_comment_headers, VorbisBatchError};
+use crate::CommentHeader;
+use std::path::Path;
+
+/// Which tags a find and replace applies to. Tag names are case insensitive.
+#[derive(Clone, Debug)]
+pub enum FieldFilter {
+    All,
+    Only(Vec<String>),
+    Except(Vec<String>),
+}
+
+impl FieldFilter {
+    pub fn matches(&self, tag: &str) -> bool {
+        match self {
+            FieldFilter::All => true,
+            FieldFilter::Only(tags) => tags.iter().any(|t| t.eq_ignore_ascii_case(tag)),
+            FieldFilter::Except(tags) => !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Pattern {
+    /// Replace every occurrence of the exact text.
+    Plain(String),
+    /// Replace every match, the replacement may refer to capture groups.
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+impl Pattern {
+    fn apply(&self, value: &str, replacement: &str) -> Option<String> {
+        match self {
+            Pattern::Plain(text) => {
+                if text.is_empty() || !value.contains(text.as_str()) {
+                    None
+                } else {
+                    Some(value.replace(text.as_str(), replacement))
+                }
+            }
+            #[cfg(feature = "regex")]
+            Pattern::Regex(regex) => {
+                if regex.is_match(value) {
+                    Some(regex.replace_all(value, replacement).into_owned())
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// Replace `pattern` in the values of the tags selected by `filter`.
+/// Returns the number of values that changed.
+pub fn find_replace(
+    header: &mut CommentHeader,
+    pattern: &Pattern,
+    replacement: &str,
+    filter: &FieldFilter,
+) -> usize {
+    let mut changed = 0;
+    for (key, value) in header.comments.values_mut() {
+        if !filter.matches(key) {
+            continue;
+        }
+        if let Some(new_value) = pattern.apply(value, replacement) {
+            if new_value != *value {
+                *value = new_value;
+                changed += 1;
+            }
+        }
+    }
+    changed
+}
+
+/// Run `find_replace` on every file, rewriting only the files that changed.
+/// Returns the total number of values that changed.
+pub fn find_replace_files<P: AsRef<Path>>(
+    files: &[P],
+    pattern: &Pattern,
+    replacement: &str,
+    filter: &FieldFilter,
+) -> Result<usize, VorbisBatchError> {
+    let mut changed = 0;
+    let mut jobs = vec![];
+    for path in files.iter() {
+        let path = path.as_ref();
+        let mut header = read_path(path)?;
+        let count = find_replace(&mut header, pattern, replacement, filter);
+        if count > 0 {
+            changed += count;
+            jobs.push((path, header));
+        }
+    }
+    safe_replace_comment_headers(jobs)?;
+    Ok(changed)
+}
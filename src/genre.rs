@@ -0,0 +1,240 @@
+// Multiple GENRE values, legacy "Rock; Indie" values and ID3v1 genre numbers
+
+use crate::keys;
+use crate::{CommentHeader, VorbisComments};
+
+/// ID3v1 genres, including the Winamp extensions, by number.
+const ID3V1_GENRES: [&str; 148] = [
+    "Blues",
+    "Classic Rock",
+    "Country",
+    "Dance",
+    "Disco",
+    "Funk",
+    "Grunge",
+    "Hip-Hop",
+    "Jazz",
+    "Metal",
+    "New Age",
+    "Oldies",
+    "Other",
+    "Pop",
+    "R&B",
+    "Rap",
+    "Reggae",
+    "Rock",
+    "Techno",
+    "Industrial",
+    "Alternative",
+    "Ska",
+    "Death Metal",
+    "Pranks",
+    "Soundtrack",
+    "Euro-Techno",
+    "Ambient",
+    "Trip-Hop",
+    "Vocal",
+    "Jazz+Funk",
+    "Fusion",
+    "Trance",
+    "Classical",
+    "Instrumental",
+    "Acid",
+    "House",
+    "Game",
+    "Sound Clip",
+    "Gospel",
+    "Noise",
+    "AlternRock",
+    "Bass",
+    "Soul",
+    "Punk",
+    "Space",
+    "Meditative",
+    "Instrumental Pop",
+    "Instrumental Rock",
+    "Ethnic",
+    "Gothic",
+    "Darkwave",
+    "Techno-Industrial",
+    "Electronic",
+    "Pop-Folk",
+    "Eurodance",
+    "Dream",
+    "Southern Rock",
+    "Comedy",
+    "Cult",
+    "Gangsta",
+    "Top 40",
+    "Christian Rap",
+    "Pop/Funk",
+    "Jungle",
+    "Native American",
+    "Cabaret",
+    "New Wave",
+    "Psychadelic",
+    "Rave",
+    "Showtunes",
+    "Trailer",
+    "Lo-Fi",
+    "Tribal",
+    "Acid Punk",
+    "Acid Jazz",
+    "Polka",
+    "Retro",
+    "Musical",
+    "Rock & Roll",
+    "Hard Rock",
+    "Folk",
+    "Folk-Rock",
+    "National Folk",
+    "Swing",
+    "Fast Fusion",
+    "Bebob",
+    "Latin",
+    "Revival",
+    "Celtic",
+    "Bluegrass",
+    "Avantgarde",
+    "Gothic Rock",
+    "Progressive Rock",
+    "Psychedelic Rock",
+    "Symphonic Rock",
+    "Slow Rock",
+    "Big Band",
+    "Chorus",
+    "Easy Listening",
+    "Acoustic",
+    "Humour",
+    "Speech",
+    "Chanson",
+    "Opera",
+    "Chamber Music",
+    "Sonata",
+    "Symphony",
+    "Booty Bass",
+    "Primus",
+    "Porn Groove",
+    "Satire",
+    "Slow Jam",
+    "Club",
+    "Tango",
+    "Samba",
+    "Folklore",
+    "Ballad",
+    "Power Ballad",
+    "Rhythmic Soul",
+    "Freestyle",
+    "Duet",
+    "Punk Rock",
+    "Drum Solo",
+    "A capella",
+    "Euro-House",
+    "Dance Hall",
+    "Goa",
+    "Drum & Bass",
+    "Club-House",
+    "Hardcore",
+    "Terror",
+    "Indie",
+    "BritPop",
+    "Afro-Punk",
+    "Polsk Punk",
+    "Beat",
+    "Christian Gangsta Rap",
+    "Heavy Metal",
+    "Black Metal",
+    "Crossover",
+    "Contemporary Christian",
+    "Christian Rock",
+    "Merengue",
+    "Salsa",
+    "Thrash Metal",
+    "Anime",
+    "JPop",
+    "Synthpop",
+];
+
+pub fn id3v1_genre(number: u8) -> Option<&'static str> {
+    ID3V1_GENRES.get(number as usize).cloned()
+}
+
+/// The genre name for "17" or "(17)", `None` for anything else.
+fn numeric_genre(value: &str) -> Option<&'static str> {
+    let value = value.trim();
+    let digits = value
+        .strip_prefix('(')
+        .and_then(|rest| rest.strip_suffix(')'))
+        .unwrap_or(value);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    id3v1_genre(digits.parse::<u8>().ok()?)
+}
+
+/// Split a legacy "Rock; Indie" value, dropping empty parts.
+pub fn split_genres(value: &str) -> Vec<String> {
+    value
+        .split(';')
+        .map(|genre| genre.trim())
+        .filter(|genre| !genre.is_empty())
+        .map(|genre| genre.to_string())
+        .collect()
+}
+
+/// Keep the first spelling of genres that only differ in case.
+fn dedup_genres(genres: Vec<String>) -> Vec<String> {
+    let mut unique: Vec<String> = vec![];
+    for genre in genres {
+        if !unique
+            .iter()
+            .any(|seen| seen.to_lowercase() == genre.to_lowercase())
+        {
+            unique.push(genre);
+        }
+    }
+    unique
+}
+
+pub trait VorbisGenres {
+    /// All genres, with legacy values split and duplicates removed.
+    fn genres(&self) -> Vec<String>;
+    /// Replace the GENRE tags with one entry per genre.
+    fn set_genres(&mut self, genres: &[&str]);
+    /// Rewrite GENRE as split, deduplicated entries, turning ID3v1 genre
+    /// numbers into names when `map_id3v1` is set.
+    fn normalize_genres(&mut self, map_id3v1: bool);
+}
+
+impl VorbisGenres for CommentHeader {
+    fn genres(&self) -> Vec<String> {
+        let genres = self
+            .get_tag_multi(keys::GENRE)
+            .iter()
+            .flat_map(|value| split_genres(value))
+            .collect();
+        dedup_genres(genres)
+    }
+
+    fn set_genres(&mut self, genres: &[&str]) {
+        self.clear_tag(keys::GENRE);
+        self.add_tag_multi(keys::GENRE, genres);
+    }
+
+    fn normalize_genres(&mut self, map_id3v1: bool) {
+        let genres = self
+            .genres()
+            .into_iter()
+            .map(|genre| match numeric_genre(&genre) {
+                Some(name) if map_id3v1 => name.to_string(),
+                _ => genre,
+            })
+            .collect();
+        let genres = dedup_genres(genres);
+        let genres = genres
+            .iter()
+            .map(|genre| genre.as_str())
+            .collect::<Vec<&str>>();
+        self.set_genres(&genres);
+    }
+}
@@ -0,0 +1,68 @@
+// Granule positions of the audio pages, kept from the input or recomputed
+
+use lewton::audio::get_decoded_sample_count;
+use lewton::header::{
+    read_header_ident, read_header_setup, HeaderReadError, IdentHeader, SetupHeader,
+};
+
+/// How the granule position of each audio page is written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GranuleMode {
+    /// Give each page the granule position the input had on the page its
+    /// last packet ended on. Packets written again can end up on other
+    /// pages than in the input, so a page may get the position of samples
+    /// that are only on the next one. Pages copied as they are keep theirs.
+    #[default]
+    Packet,
+    /// Keep the granule position of every audio page exactly, by copying the
+    /// audio pages as with `ReplaceOptions::copy_pages`.
+    Preserve,
+    /// Compute the granule position of every page from the block sizes of
+    /// the packets ending on it, counting from zero, for whatever pages are
+    /// written. The last page keeps the position of the input when it is
+    /// lower, since it tells how many samples to drop at the end.
+    Recompute,
+}
+
+/// Counts the samples of a stream, packet by packet.
+pub(crate) struct GranuleCounter {
+    ident: Option<IdentHeader>,
+    setup: Option<SetupHeader>,
+    packets: u64,
+    position: u64,
+}
+
+impl GranuleCounter {
+    pub(crate) fn new() -> GranuleCounter {
+        GranuleCounter {
+            ident: None,
+            setup: None,
+            packets: 0,
+            position: 0,
+        }
+    }
+
+    /// The granule position at the end of `packet`, the next packet of the
+    /// stream starting with the three headers.
+    pub(crate) fn next(&mut self, packet: &[u8]) -> Result<u64, HeaderReadError> {
+        self.packets += 1;
+        match (&self.ident, &self.setup) {
+            (None, _) => self.ident = Some(read_header_ident(packet)?),
+            (Some(ident), None) if self.packets == 3 => {
+                let blocksizes = (ident.blocksize_0, ident.blocksize_1);
+                self.setup = Some(read_header_setup(packet, ident.audio_channels, blocksizes)?);
+            }
+            (Some(_), None) => {}
+            (Some(ident), Some(setup)) => {
+                // The first audio packet only primes the decoder, and
+                // packets that don't decode give no samples
+                if let Ok(samples) = get_decoded_sample_count(ident, setup, packet) {
+                    if self.packets > 4 {
+                        self.position += samples as u64;
+                    }
+                }
+            }
+        }
+        Ok(self.position)
+    }
+}
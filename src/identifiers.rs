@@ -0,0 +1,147 @@
+// Release identifiers: ISRC, BARCODE (EAN/UPC) and CATALOGNUMBER
+
+use crate::keys;
+use crate::{CommentHeader, VorbisComments};
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum VorbisIdentifierError {
+    #[error("invalid ISRC: {0}")]
+    InvalidIsrc(String),
+    #[error("invalid EAN/UPC barcode: {0}")]
+    InvalidBarcode(String),
+    #[error("invalid catalog number: {0}")]
+    InvalidCatalogNumber(String),
+}
+
+/// The ISRC in its canonical form, 12 uppercase characters without
+/// hyphens: country code, registrant code, year and designation code.
+pub fn normalize_isrc(value: &str) -> Option<String> {
+    let isrc: String = value
+        .trim()
+        .chars()
+        .filter(|c| *c != '-')
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    let bytes = isrc.as_bytes();
+    let valid = bytes.len() == 12
+        && bytes[0..2].iter().all(|b| b.is_ascii_uppercase())
+        && bytes[2..5].iter().all(|b| b.is_ascii_alphanumeric())
+        && bytes[5..12].iter().all(|b| b.is_ascii_digit());
+    if valid {
+        Some(isrc)
+    } else {
+        None
+    }
+}
+
+/// Check an EAN-8, UPC-A (12 digits) or EAN-13 barcode, including its
+/// check digit.
+pub fn is_valid_barcode(value: &str) -> bool {
+    let digits = value.trim().as_bytes();
+    if ![8, 12, 13].contains(&digits.len()) || !digits.iter().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+    // Weights alternate 3 and 1 from the right, the check digit having 1
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(index, digit)| {
+            let digit = (digit - b'0') as u32;
+            if index % 2 == 1 {
+                digit * 3
+            } else {
+                digit
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// Catalog numbers are free-form, but must be a non-empty single line.
+fn is_valid_catalog_number(value: &str) -> bool {
+    !value.trim().is_empty() && !value.chars().any(|c| c.is_control())
+}
+
+pub trait VorbisIdentifiers {
+    /// The ISRC in canonical form, `None` if missing or malformed.
+    fn isrc(&self) -> Option<String>;
+    /// Validate and write the ISRC in canonical form.
+    fn set_isrc(&mut self, isrc: &str) -> Result<(), VorbisIdentifierError>;
+    /// The barcode, `None` if missing or malformed.
+    fn barcode(&self) -> Option<String>;
+    fn set_barcode(&mut self, barcode: &str) -> Result<(), VorbisIdentifierError>;
+    fn catalog_number(&self) -> Option<String>;
+    fn set_catalog_number(&mut self, catalog_number: &str) -> Result<(), VorbisIdentifierError>;
+    /// Every malformed ISRC, BARCODE and CATALOGNUMBER value, for ingest
+    /// pipelines that reject such files.
+    fn invalid_identifiers(&self) -> Vec<VorbisIdentifierError>;
+}
+
+impl VorbisIdentifiers for CommentHeader {
+    fn isrc(&self) -> Option<String> {
+        normalize_isrc(&self.get_tag_single(keys::ISRC)?)
+    }
+
+    fn set_isrc(&mut self, isrc: &str) -> Result<(), VorbisIdentifierError> {
+        let isrc = normalize_isrc(isrc)
+            .ok_or_else(|| VorbisIdentifierError::InvalidIsrc(isrc.to_string()))?;
+        self.clear_tag(keys::ISRC);
+        self.add_tag_single(keys::ISRC, &isrc);
+        Ok(())
+    }
+
+    fn barcode(&self) -> Option<String> {
+        self.get_tag_single(keys::BARCODE)
+            .map(|barcode| barcode.trim().to_string())
+            .filter(|barcode| is_valid_barcode(barcode))
+    }
+
+    fn set_barcode(&mut self, barcode: &str) -> Result<(), VorbisIdentifierError> {
+        if !is_valid_barcode(barcode) {
+            return Err(VorbisIdentifierError::InvalidBarcode(barcode.to_string()));
+        }
+        self.clear_tag(keys::BARCODE);
+        self.add_tag_single(keys::BARCODE, barcode.trim());
+        Ok(())
+    }
+
+    fn catalog_number(&self) -> Option<String> {
+        self.get_tag_single(keys::CATALOGNUMBER)
+            .filter(|catalog_number| is_valid_catalog_number(catalog_number))
+            .map(|catalog_number| catalog_number.trim().to_string())
+    }
+
+    fn set_catalog_number(&mut self, catalog_number: &str) -> Result<(), VorbisIdentifierError> {
+        if !is_valid_catalog_number(catalog_number) {
+            return Err(VorbisIdentifierError::InvalidCatalogNumber(
+                catalog_number.to_string(),
+            ));
+        }
+        self.clear_tag(keys::CATALOGNUMBER);
+        self.add_tag_single(keys::CATALOGNUMBER, catalog_number.trim());
+        Ok(())
+    }
+
+    fn invalid_identifiers(&self) -> Vec<VorbisIdentifierError> {
+        let mut invalid = vec![];
+        for isrc in self.get_tag_multi(keys::ISRC) {
+            if normalize_isrc(&isrc).is_none() {
+                invalid.push(VorbisIdentifierError::InvalidIsrc(isrc));
+            }
+        }
+        for barcode in self.get_tag_multi(keys::BARCODE) {
+            if !is_valid_barcode(&barcode) {
+                invalid.push(VorbisIdentifierError::InvalidBarcode(barcode));
+            }
+        }
+        for catalog_number in self.get_tag_multi(keys::CATALOGNUMBER) {
+            if !is_valid_catalog_number(&catalog_number) {
+                invalid.push(VorbisIdentifierError::InvalidCatalogNumber(catalog_number));
+            }
+        }
+        invalid
+    }
+}
@@ -0,0 +1,81 @@
+// Iterating over the entries of a comment header
+
+use crate::store::TagStore;
+use std::slice;
+
+/// Iterator over `(key, value)` pairs in header order, returned by
+/// `VorbisComments::iter`.
+#[derive(Clone, Debug)]
+pub struct TagIter<'a> {
+    inner: slice::Iter<'a, Option<(String, String)>>,
+    remaining: usize,
+}
+
+impl<'a> TagIter<'a> {
+    pub(crate) fn new(store: &'a TagStore) -> TagIter<'a> {
+        TagIter {
+            inner: store.slots().iter(),
+            remaining: store.len(),
+        }
+    }
+}
+
+fn as_strs(entry: &(String, String)) -> (&str, &str) {
+    (entry.0.as_str(), entry.1.as_str())
+}
+
+impl<'a> Iterator for TagIter<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.inner.by_ref().flatten().next()?;
+        self.remaining -= 1;
+        Some(as_strs(entry))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl DoubleEndedIterator for TagIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let entry = self.inner.by_ref().flatten().next_back()?;
+        self.remaining -= 1;
+        Some(as_strs(entry))
+    }
+}
+
+impl ExactSizeIterator for TagIter<'_> {}
+
+/// Iterator over the values of one tag, returned by
+/// `VorbisComments::get_tag_refs`.
+#[derive(Clone, Debug)]
+pub struct TagRefs<'a> {
+    slots: &'a [Option<(String, String)>],
+    positions: slice::Iter<'a, usize>,
+}
+
+impl<'a> TagRefs<'a> {
+    pub(crate) fn new(store: &'a TagStore, tag: &str) -> TagRefs<'a> {
+        TagRefs {
+            slots: store.slots(),
+            positions: store.positions(tag).iter(),
+        }
+    }
+}
+
+impl<'a> Iterator for TagRefs<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let position = self.positions.next()?;
+        self.slots[*position]
+            .as_ref()
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.positions.size_hint()
+    }
+}
@@ -0,0 +1,235 @@
+//! Standard tag names, see <https://xiph.org/vorbis/doc/v-comment.html>
+//!
+//! Tag names are case insensitive, these are spelled the way the spec lists them.
+
+use crate::{CommentHeader, VorbisComments};
+
+pub const TITLE: &str = "TITLE";
+pub const VERSION: &str = "VERSION";
+pub const ALBUM: &str = "ALBUM";
+pub const TRACKNUMBER: &str = "TRACKNUMBER";
+pub const TRACKTOTAL: &str = "TRACKTOTAL";
+pub const DISCNUMBER: &str = "DISCNUMBER";
+pub const DISCTOTAL: &str = "DISCTOTAL";
+pub const TOTALTRACKS: &str = "TOTALTRACKS";
+pub const TOTALDISCS: &str = "TOTALDISCS";
+pub const ARTIST: &str = "ARTIST";
+pub const ALBUMARTIST: &str = "ALBUMARTIST";
+pub const PERFORMER: &str = "PERFORMER";
+pub const COMPOSER: &str = "COMPOSER";
+pub const COPYRIGHT: &str = "COPYRIGHT";
+pub const LICENSE: &str = "LICENSE";
+pub const ORGANIZATION: &str = "ORGANIZATION";
+pub const DESCRIPTION: &str = "DESCRIPTION";
+pub const COMMENT: &str = "COMMENT";
+pub const GENRE: &str = "GENRE";
+pub const DATE: &str = "DATE";
+pub const ORIGINALDATE: &str = "ORIGINALDATE";
+pub const LOCATION: &str = "LOCATION";
+pub const CONTACT: &str = "CONTACT";
+pub const ISRC: &str = "ISRC";
+pub const BARCODE: &str = "BARCODE";
+pub const CATALOGNUMBER: &str = "CATALOGNUMBER";
+pub const ENCODER: &str = "ENCODER";
+pub const ENCODERSETTINGS: &str = "ENCODERSETTINGS";
+pub const ENCODEDBY: &str = "ENCODEDBY";
+pub const LYRICS: &str = "LYRICS";
+pub const UNSYNCEDLYRICS: &str = "UNSYNCEDLYRICS";
+pub const ARTISTSORT: &str = "ARTISTSORT";
+pub const ALBUMARTISTSORT: &str = "ALBUMARTISTSORT";
+pub const ALBUMSORT: &str = "ALBUMSORT";
+pub const TITLESORT: &str = "TITLESORT";
+pub const BPM: &str = "BPM";
+pub const INITIALKEY: &str = "INITIALKEY";
+pub const COMPILATION: &str = "COMPILATION";
+pub const CUESHEET: &str = "CUESHEET";
+pub const WORK: &str = "WORK";
+pub const MOVEMENT: &str = "MOVEMENT";
+pub const MOVEMENTNAME: &str = "MOVEMENTNAME";
+pub const MOVEMENTTOTAL: &str = "MOVEMENTTOTAL";
+pub const LANGUAGE: &str = "LANGUAGE";
+pub const SPEAKER: &str = "SPEAKER";
+pub const TRANSCRIPT: &str = "TRANSCRIPT";
+pub const SEGMENT: &str = "SEGMENT";
+pub const PADDING: &str = "PADDING";
+pub const RATING: &str = "RATING";
+pub const FMPS_RATING: &str = "FMPS_RATING";
+pub const REPLAYGAIN_TRACK_GAIN: &str = "REPLAYGAIN_TRACK_GAIN";
+pub const REPLAYGAIN_TRACK_PEAK: &str = "REPLAYGAIN_TRACK_PEAK";
+pub const REPLAYGAIN_ALBUM_GAIN: &str = "REPLAYGAIN_ALBUM_GAIN";
+pub const REPLAYGAIN_ALBUM_PEAK: &str = "REPLAYGAIN_ALBUM_PEAK";
+pub const METADATA_BLOCK_PICTURE: &str = "METADATA_BLOCK_PICTURE";
+
+macro_rules! tag_keys {
+    ($($variant:ident, $key:ident;)*) => {
+        /// A tag name, either one of the well-known ones above or any other.
+        #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum TagKey {
+            $($variant,)*
+            Other(String),
+        }
+
+        impl TagKey {
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(TagKey::$variant => $key,)*
+                    TagKey::Other(name) => name,
+                }
+            }
+        }
+
+        impl From<&str> for TagKey {
+            /// Well-known names, in any case, give their variant.
+            fn from(name: &str) -> TagKey {
+                $(
+                    if name.eq_ignore_ascii_case($key) {
+                        return TagKey::$variant;
+                    }
+                )*
+                TagKey::Other(name.to_string())
+            }
+        }
+    };
+}
+
+tag_keys! {
+    Title, TITLE;
+    Version, VERSION;
+    Album, ALBUM;
+    TrackNumber, TRACKNUMBER;
+    TrackTotal, TRACKTOTAL;
+    DiscNumber, DISCNUMBER;
+    DiscTotal, DISCTOTAL;
+    TotalTracks, TOTALTRACKS;
+    TotalDiscs, TOTALDISCS;
+    Artist, ARTIST;
+    AlbumArtist, ALBUMARTIST;
+    Performer, PERFORMER;
+    Composer, COMPOSER;
+    Copyright, COPYRIGHT;
+    License, LICENSE;
+    Organization, ORGANIZATION;
+    Description, DESCRIPTION;
+    Comment, COMMENT;
+    Genre, GENRE;
+    Date, DATE;
+    OriginalDate, ORIGINALDATE;
+    Location, LOCATION;
+    Contact, CONTACT;
+    Isrc, ISRC;
+    Barcode, BARCODE;
+    CatalogNumber, CATALOGNUMBER;
+    Encoder, ENCODER;
+    EncoderSettings, ENCODERSETTINGS;
+    EncodedBy, ENCODEDBY;
+    Lyrics, LYRICS;
+    UnsyncedLyrics, UNSYNCEDLYRICS;
+    ArtistSort, ARTISTSORT;
+    AlbumArtistSort, ALBUMARTISTSORT;
+    AlbumSort, ALBUMSORT;
+    TitleSort, TITLESORT;
+    Bpm, BPM;
+    InitialKey, INITIALKEY;
+    Compilation, COMPILATION;
+    CueSheet, CUESHEET;
+    Work, WORK;
+    Movement, MOVEMENT;
+    MovementName, MOVEMENTNAME;
+    MovementTotal, MOVEMENTTOTAL;
+    Language, LANGUAGE;
+    Speaker, SPEAKER;
+    Transcript, TRANSCRIPT;
+    Segment, SEGMENT;
+    Padding, PADDING;
+    Rating, RATING;
+    FmpsRating, FMPS_RATING;
+    ReplayGainTrackGain, REPLAYGAIN_TRACK_GAIN;
+    ReplayGainTrackPeak, REPLAYGAIN_TRACK_PEAK;
+    ReplayGainAlbumGain, REPLAYGAIN_ALBUM_GAIN;
+    ReplayGainAlbumPeak, REPLAYGAIN_ALBUM_PEAK;
+    MetadataBlockPicture, METADATA_BLOCK_PICTURE;
+}
+
+impl AsRef<str> for TagKey {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl std::fmt::Display for TagKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+macro_rules! standard_tags {
+    (
+        $($get:ident, $set:ident, $key:ident;)*
+        @totals
+        $($total_get:ident, $total_set:ident, $total_key:ident, $alias:ident;)*
+    ) => {
+        /// Getters and setters for the standard tags. Getters return the first
+        /// value, setters replace all existing values. The totals are also
+        /// read from their alias, TOTALTRACKS or TOTALDISCS, which their
+        /// setters remove.
+        pub trait VorbisStandardTags {
+            $(
+                fn $get(&self) -> Option<String>;
+                fn $set(&mut self, value: &str);
+            )*
+            $(
+                fn $total_get(&self) -> Option<String>;
+                fn $total_set(&mut self, value: &str);
+            )*
+        }
+
+        impl VorbisStandardTags for CommentHeader {
+            $(
+                fn $get(&self) -> Option<String> {
+                    self.get_tag_single($key)
+                }
+
+                fn $set(&mut self, value: &str) {
+                    self.set_tag_single($key, value);
+                }
+            )*
+            $(
+                fn $total_get(&self) -> Option<String> {
+                    self.get_tag_single($total_key)
+                        .or_else(|| self.get_tag_single($alias))
+                }
+
+                fn $total_set(&mut self, value: &str) {
+                    self.clear_tag($alias);
+                    self.set_tag_single($total_key, value);
+                }
+            )*
+        }
+    };
+}
+
+standard_tags! {
+    title, set_title, TITLE;
+    version, set_version, VERSION;
+    album, set_album, ALBUM;
+    track_number, set_track_number, TRACKNUMBER;
+    disc_number, set_disc_number, DISCNUMBER;
+    artist, set_artist, ARTIST;
+    album_artist, set_album_artist, ALBUMARTIST;
+    performer, set_performer, PERFORMER;
+    composer, set_composer, COMPOSER;
+    copyright, set_copyright, COPYRIGHT;
+    license, set_license, LICENSE;
+    organization, set_organization, ORGANIZATION;
+    description, set_description, DESCRIPTION;
+    comment, set_comment, COMMENT;
+    genre, set_genre, GENRE;
+    date, set_date, DATE;
+    location, set_location, LOCATION;
+    contact, set_contact, CONTACT;
+    encoder, set_encoder, ENCODER;
+    @totals
+    track_total, set_track_total, TRACKTOTAL, TOTALTRACKS;
+    disc_total, set_disc_total, DISCTOTAL, TOTALDISCS;
+}
@@ -0,0 +1,86 @@
+// Tags with a language suffix, such as DESCRIPTION-ENG or COMMENT-FRA
+
+use crate::{CommentHeader, VorbisComments};
+
+/// The language of `key` if it is `base` or `base-<LANGUAGE>`, `Some(None)`
+/// for the unsuffixed `base`.
+pub(crate) fn tag_language(key: &str, base: &str) -> Option<Option<String>> {
+    let upper = key.to_uppercase();
+    let rest = upper.strip_prefix(&base.to_uppercase())?;
+    if rest.is_empty() {
+        Some(None)
+    } else {
+        let language = rest
+            .strip_prefix('-')
+            .filter(|language| !language.is_empty())?;
+        Some(Some(language.to_lowercase()))
+    }
+}
+
+pub trait VorbisLanguageTags {
+    /// All values of `tag` and `tag-<LANGUAGE>`, in tag order, with the
+    /// lowercase language, `None` for the unsuffixed tag.
+    fn get_localized<K: AsRef<str>>(&self, tag: K) -> Vec<(Option<String>, String)>;
+    /// The first value in the given language, or of the unsuffixed tag for
+    /// `None`.
+    fn get_localized_single<K: AsRef<str>>(&self, tag: K, language: Option<&str>)
+        -> Option<String>;
+    /// The distinct languages `tag` has values in, in order of appearance.
+    fn tag_languages<K: AsRef<str>>(&self, tag: K) -> Vec<String>;
+    /// Replace the values in the given language, written as `tag` or
+    /// `tag-<LANGUAGE>`.
+    fn set_localized<K: AsRef<str>>(&mut self, tag: K, language: Option<&str>, value: &str);
+    /// Remove the values in the given language only.
+    fn clear_localized<K: AsRef<str>>(&mut self, tag: K, language: Option<&str>);
+}
+
+impl VorbisLanguageTags for CommentHeader {
+    fn get_localized<K: AsRef<str>>(&self, tag: K) -> Vec<(Option<String>, String)> {
+        let tag = tag.as_ref();
+        self.iter()
+            .filter_map(|(key, value)| {
+                tag_language(key, tag).map(|language| (language, value.to_string()))
+            })
+            .collect()
+    }
+
+    fn get_localized_single<K: AsRef<str>>(
+        &self,
+        tag: K,
+        language: Option<&str>,
+    ) -> Option<String> {
+        let language = language.map(|language| language.to_lowercase());
+        self.get_localized(tag)
+            .into_iter()
+            .find(|entry| entry.0 == language)
+            .map(|entry| entry.1)
+    }
+
+    fn tag_languages<K: AsRef<str>>(&self, tag: K) -> Vec<String> {
+        let mut languages: Vec<String> = vec![];
+        for (language, _) in self.get_localized(tag) {
+            if let Some(language) = language {
+                if !languages.contains(&language) {
+                    languages.push(language);
+                }
+            }
+        }
+        languages
+    }
+
+    fn set_localized<K: AsRef<str>>(&mut self, tag: K, language: Option<&str>, value: &str) {
+        let tag = tag.as_ref();
+        self.clear_localized(tag, language);
+        let key = match language {
+            Some(language) => format!("{}-{}", tag, language),
+            None => tag.to_string(),
+        };
+        self.add_tag_single(key, value);
+    }
+
+    fn clear_localized<K: AsRef<str>>(&mut self, tag: K, language: Option<&str>) {
+        let tag = tag.as_ref();
+        let language = language.map(|language| language.to_lowercase());
+        self.retain(|key, _| tag_language(key, tag) != Some(language.clone()));
+    }
+}
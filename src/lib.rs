@@ -5,15 +5,186 @@ extern crate lewton;
 extern crate ogg;
 
 use lewton::header::HeaderReadError;
-//use lewton::header::CommentHeader;
 use ogg::writing::PacketWriteEndInfo;
 use ogg::{OggReadError, Packet, PacketReader, PacketWriter};
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryInto;
-use std::io::{self, Cursor, Read, Seek};
+use std::io::{self, Cursor, Read, Seek, Write};
+use std::path::PathBuf;
 
+use glob::glob_matches;
+use granule::GranuleCounter;
+use page::{salvage_packets, PageError, PageReadError};
+use pagination::PageLayout;
+use parse::parse_comment_header;
+use stats::Counted;
+use store::TagStore;
 use thiserror::Error;
+use verify::Recorded;
 
-pub type CommentHeader = lewton::header::CommentHeader;
+mod album;
+mod aliases;
+mod base64;
+mod batch;
+mod builder;
+mod cache;
+mod cancel;
+mod chapters;
+mod classical;
+mod compilation;
+mod conformance;
+mod copy;
+mod crc;
+mod credits;
+mod cuesheet;
+#[cfg(feature = "chrono")]
+mod dates;
+mod diff;
+mod duration;
+mod encoding;
+mod entry;
+mod files;
+mod find_replace;
+mod genre;
+mod glob;
+mod granule;
+mod identifiers;
+mod iter;
+mod language;
+mod limits;
+mod lyrics;
+mod merge;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod multivalue;
+mod musical;
+mod numbering;
+mod options;
+mod page;
+mod pagination;
+mod parse;
+mod picture;
+mod playlist;
+mod privacy;
+mod progress;
+mod provenance;
+mod rating;
+mod recovery;
+mod replaygain;
+mod roundtrip;
+mod sanitize;
+mod scan;
+mod schema;
+mod sort;
+mod spill;
+mod spoken;
+mod stats;
+mod store;
+mod surgical;
+mod tags;
+mod throttle;
+mod transform;
+#[cfg(feature = "nfc")]
+mod unicode;
+mod update;
+mod verify;
+
+#[cfg(feature = "differential")]
+pub mod differential;
+pub mod keys;
+
+pub use album::{renumber_tracks, AlbumInconsistency, AlbumSession};
+pub use aliases::{KeyAliases, VorbisAliases};
+pub use batch::{
+    safe_replace_comment_headers, safe_replace_comment_headers_throttled, VorbisBatchError,
+};
+pub use builder::{is_valid_tag_name, CommentHeaderBuilder, VorbisBuildError};
+#[cfg(feature = "sqlite")]
+pub use cache::SqliteCache;
+pub use cache::{CachedHeader, FileCache, MemoryCache, ScanCache};
+pub use cancel::CancelToken;
+pub use chapters::{format_timestamp, parse_timestamp, Chapter, VorbisChapters};
+pub use classical::{roman_numeral, VorbisClassical};
+pub use compilation::VorbisCompilation;
+pub use conformance::{check_conformance, ConformanceIssue};
+pub use copy::{copy_comment_header, copy_comment_header_from, CopyOptions};
+pub use crc::{ogg_crc32, OggCrc32};
+pub use credits::{Credit, Performer, VorbisCredits};
+pub use cuesheet::{
+    format_cue_time, parse_cue_time, CueIndex, CueSheet, CueTrack, VorbisCueSheet,
+    VorbisCueSheetError, CUE_FRAMES_PER_SECOND,
+};
+#[cfg(feature = "chrono")]
+pub use dates::{TagDate, VorbisDates};
+pub use diff::{diff, TagChange, TagDiff};
+pub use duration::{read_duration, safe_read_duration};
+pub use encoding::{LegacyEncoding, VorbisRecode};
+pub use entry::TagEntry;
+pub use files::{
+    backup_path, read_comment_header_from_path, replace_comment_header_in_path,
+    safe_read_comment_header_from_path, safe_replace_comment_header_in_path,
+    safe_replace_comment_header_in_path_with_options, safe_write_comment_header_to_path,
+    write_comment_header_to_path, SaveOptions,
+};
+pub use find_replace::{find_replace, find_replace_files, FieldFilter, Pattern};
+pub use genre::{id3v1_genre, split_genres, VorbisGenres};
+pub use granule::GranuleMode;
+pub use identifiers::{is_valid_barcode, normalize_isrc, VorbisIdentifierError, VorbisIdentifiers};
+pub use iter::{TagIter, TagRefs};
+pub use keys::{TagKey, VorbisStandardTags};
+pub use language::VorbisLanguageTags;
+pub use limits::ParseLimits;
+pub use lyrics::{Lyrics, VorbisLyrics};
+pub use merge::{MergeStrategy, VorbisMerge};
+#[cfg(feature = "mmap")]
+pub use mmap::{
+    safe_read_comment_header_mapped, safe_replace_comment_header_mapped_into, MappedFile,
+};
+pub use multivalue::{split_value, VorbisMultiValue};
+pub use musical::{Mode, MusicalKey, Note, VorbisMusical};
+pub use numbering::{Position, TotalConvention, VorbisNumbering};
+pub use options::ReplaceOptionsBuilder;
+pub use pagination::Pagination;
+pub use parse::{CommentHeaderRef, DecodeReport, LazyCommentHeader};
+pub use picture::{
+    probe_image, ImageInfo, Picture, PictureType, VorbisPictureError, VorbisPictures, PICTURE_TAG,
+};
+pub use playlist::{m3u8_entry, xspf_playlist, xspf_track, M3U8_HEADER};
+pub use privacy::{
+    safe_strip_all_tags, strip_all_tags, StripOptions, VorbisPrivacy, ENCODER_TAGS, GENERIC_VENDOR,
+};
+pub use progress::safe_replace_comment_header_with_progress;
+pub use provenance::{Provenance, Source, PROVENANCE_TAG};
+pub use rating::{normalize_rating, RatingFormat, VorbisRating};
+pub use recovery::{
+    safe_read_comment_header_recovering, safe_read_comment_packet_recovering, RecoveryReport,
+};
+pub use replaygain::{format_gain, parse_gain, ReplayGain, VorbisReplayGain};
+pub use roundtrip::{roundtrip_check, RoundTripOutcome};
+pub use sanitize::{SanitizeOptions, SanitizeReport, VorbisSanitize};
+pub use scan::{header_fingerprint, ScanEntry, Scanner, FINGERPRINT_LEN};
+pub use schema::{SchemaViolation, TagSchema};
+pub use sort::{SortField, SortedValue, VorbisSortTags};
+pub use spill::{safe_replace_comment_header_spilled, ReplaceOutput, SpillFile};
+pub use spoken::{
+    is_valid_language_tag, Segment, Transcript, VorbisLanguageError, VorbisSpokenWord,
+};
+pub use stats::{RewriteStats, RewriteWarning};
+pub use surgical::{
+    patch_comment_header_in_place, plan_replace, safe_patch_comment_header_file,
+    safe_patch_comment_header_in_place, safe_plan_replace, safe_surgical_replace_comment_header,
+    shift_page_sequences, surgical_replace_comment_header, ReplacePlan, VorbisSurgicalError,
+};
+pub use tags::VorbisTags;
+pub use throttle::Throttle;
+pub use transform::{TagTransaction, Transform, ValueChange};
+#[cfg(feature = "nfc")]
+pub use unicode::{safe_read_comment_header_nfc, to_nfc, VorbisUnicode};
+pub use update::{read_file_comments, update_file_comments, write_file_comments, FileSnapshot};
+pub use verify::{safe_compare_audio, safe_verify_file, verify_file, PageIssue};
+
+/// The name the tag container has always had in this crate.
+pub type CommentHeader = VorbisTags;
 
 #[derive(Error, Debug)]
 #[error("failed to make vorbis comment")]
@@ -25,24 +196,126 @@ pub enum VorbisReadCommentError {
     FailedReadOggFile(#[from] OggReadError),
     #[error("failed to read vorbis header")]
     FailedReadHeader(#[from] HeaderReadError),
+    #[error("file ended before the comment header")]
+    Truncated,
+    /// The vendor string is longer than `ParseLimits::max_vendor_len`.
+    #[error("vendor string of {0} bytes is over the limit")]
+    VendorTooLong(usize),
+    #[error("{0} comments are over the limit")]
+    TooManyComments(usize),
+    #[error("comment of {0} bytes is over the limit")]
+    CommentTooLong(usize),
+    /// The comment header packet is, or would grow, past
+    /// `ParseLimits::max_header_size`.
+    #[error("comment header of {0} bytes is over the limit")]
+    HeaderTooLarge(usize),
 }
 
 #[derive(Error, Debug)]
 pub enum VorbisReplaceCommentError {
     #[error("failed to write vorbis file")]
     FailedReadOggFile(#[from] io::Error),
+    #[error("output does not conform to the Vorbis Ogg mapping: {0:?}")]
+    NonConformant(Vec<ConformanceIssue>),
+    /// Packet `0` of the output is not the one of the input, see
+    /// `ReplaceOptions::verify_audio`.
+    #[error("packet {0} of the output differs from the input")]
+    AudioMismatch(u64),
+    #[error("failed to read the comments of the input")]
+    ReadComments(#[from] VorbisReadCommentError),
+    /// The input has a malformed or corrupt page. Nothing is returned rather
+    /// than a file missing everything after it.
+    #[error("failed to read the input stream")]
+    ReadStream(#[from] OggReadError),
+    /// The `CancelToken` of the options was cancelled.
+    #[error("the rewrite was cancelled")]
+    Cancelled,
+    /// The input can't be copied page by page, see `ReplaceOptions::copy_pages`.
+    #[error("failed to copy the pages of the input")]
+    CopyPages(#[from] VorbisSurgicalError),
 }
 
 //type VorbisComments = CommentHeader;
+/// Tag names can be given as strings or as `TagKey`s.
 pub trait VorbisComments {
-    fn from(vendor: String, comment_list: Vec<(String, String)>) -> CommentHeader;
-    fn new() -> CommentHeader;
+    fn from(vendor: String, comment_list: Vec<(String, String)>) -> Self;
+    fn new() -> Self;
     fn get_tag_names(&self) -> Vec<String>;
-    fn get_tag_single(&self, tag: &str) -> Option<String>;
-    fn get_tag_multi(&self, tag: &str) -> Vec<String>;
-    fn clear_tag(&mut self, tag: &str);
-    fn add_tag_single(&mut self, tag: &str, value: &str);
-    fn add_tag_multi(&mut self, tag: &str, values: &Vec<&str>);
+    fn get_tag_single<K: AsRef<str>>(&self, tag: K) -> Option<String>;
+    fn get_tag_multi<K: AsRef<str>>(&self, tag: K) -> Vec<String>;
+    /// The values of the tag, borrowed from the header.
+    fn get_tag_refs<K: AsRef<str>>(&self, tag: K) -> TagRefs<'_>;
+    /// The first value of the tag, borrowed from the header.
+    fn get_tag_single_ref<K: AsRef<str>>(&self, tag: K) -> Option<&str>;
+    fn clear_tag<K: AsRef<str>>(&mut self, tag: K);
+    fn add_tag_single<K: AsRef<str>>(&mut self, tag: K, value: &str);
+    fn add_tag_multi<K: AsRef<str>>(&mut self, tag: K, values: &[&str]);
+    /// Add a value, keeping the key as written instead of lowercasing it.
+    /// Lookups still ignore case.
+    fn add_tag_verbatim<K: AsRef<str>>(&mut self, tag: K, value: &str);
+    /// Replace all values of the tag with a single one, keeping the key as written.
+    fn set_tag_verbatim<K: AsRef<str>>(&mut self, tag: K, value: &str);
+    /// The distinct tag names as stored, in order of first appearance. Names
+    /// that only differ in case are listed once, as first spelled.
+    fn get_tag_names_verbatim(&self) -> Vec<String>;
+    /// Replace all values of the tag with a single one.
+    fn set_tag_single<K: AsRef<str>>(&mut self, tag: K, value: &str);
+    /// Replace all values of the tag.
+    fn set_tag_multi<K: AsRef<str>>(&mut self, tag: K, values: &[&str]);
+    /// Remove the entries of the tag with exactly this value, returning how
+    /// many were removed.
+    fn remove_tag_value<K: AsRef<str>>(&mut self, tag: K, value: &str) -> usize;
+    /// Remove the entries of the tag whose value matches the predicate,
+    /// returning how many were removed.
+    fn remove_tag_values_where<K: AsRef<str>, F: FnMut(&str) -> bool>(
+        &mut self,
+        tag: K,
+        predicate: F,
+    ) -> usize;
+    /// Give the entries of `old_tag` the name `new_tag`, keeping them where
+    /// they are. Returns how many entries were renamed.
+    fn rename_tag<K: AsRef<str>, N: AsRef<str>>(&mut self, old_tag: K, new_tag: N) -> usize;
+    /// Replace each value for which `map(key, value)` returns a new one.
+    /// Returns how many values changed.
+    fn map_values<F: FnMut(&str, &str) -> Option<String>>(&mut self, map: F) -> usize;
+    /// Replace each key for which `map(key)` returns a new one, keeping the
+    /// entries where they are. Returns how many keys changed.
+    fn map_keys<F: FnMut(&str) -> Option<String>>(&mut self, map: F) -> usize;
+    /// The values of the tag, for conditional edits such as
+    /// `header.entry("genre").or_insert("Unknown")`.
+    fn entry<K: AsRef<str>>(&mut self, tag: K) -> TagEntry<'_>;
+    /// All entries as `(key, value)` pairs, in header order.
+    fn iter(&self) -> TagIter<'_>;
+    /// Add every `(key, value)` pair, as `add_tag_single` would.
+    fn extend_tags<I: IntoIterator<Item = (String, String)>>(&mut self, tags: I);
+    /// The values of every tag, by lowercase tag name.
+    fn to_map(&self) -> BTreeMap<String, Vec<String>>;
+    /// A header with no vendor and the tags of `map`, sorted by name since
+    /// a `HashMap` has no order.
+    fn from_map(map: HashMap<String, Vec<String>>) -> Self;
+    /// Keep only the entries for which `keep(key, value)` returns true.
+    fn retain<F: FnMut(&str, &str) -> bool>(&mut self, keep: F);
+    /// Keep only the tags matching one of the names, which may contain `*`
+    /// and `?` wildcards, e.g. "MUSICBRAINZ_*". Returns how many entries
+    /// were removed.
+    fn filter_keys(&mut self, keep: &[&str]) -> usize;
+    /// Remove the tags matching one of the names, which may contain
+    /// wildcards as for `filter_keys`. Returns how many entries were removed.
+    fn remove_keys(&mut self, drop: &[&str]) -> usize;
+    /// Order the entries by lowercase tag name, keeping the values of each
+    /// tag in their order.
+    fn sort_tags(&mut self);
+    fn contains_tag<K: AsRef<str>>(&self, tag: K) -> bool;
+    /// Number of entries, counting every value of multi-value tags.
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool;
+    /// Size in bytes of the packet `make_comment_header` would produce.
+    fn encoded_size(&self) -> usize;
+    /// One "KEY=value" line per entry, like `vorbiscomment -l` prints.
+    fn to_pretty_string(&self) -> String;
+    /// Whether both headers have the same vendor and the same entries, in
+    /// any order and with tag names in any case. Values must match exactly.
+    fn eq_ignore_order_and_case(&self, other: &Self) -> bool;
     fn get_vendor(&self) -> String;
     fn set_vendor(&mut self, vend: &str);
 }
@@ -51,64 +324,227 @@ impl VorbisComments for CommentHeader {
     fn from(vendor: String, comment_list: Vec<(String, String)>) -> CommentHeader {
         CommentHeader {
             vendor,
-            comment_list,
+            comments: TagStore::from_vec(comment_list),
         }
     }
 
     fn new() -> CommentHeader {
         CommentHeader {
             vendor: "".to_string(),
-            comment_list: Vec::new(),
+            comments: TagStore::default(),
         }
     }
 
     fn get_tag_names(&self) -> Vec<String> {
-        let mut names = self
-            .comment_list
-            .iter()
-            .map(|comment| comment.0.to_lowercase())
-            .collect::<Vec<String>>();
+        let mut names = self.comments.names().cloned().collect::<Vec<String>>();
         names.sort_unstable();
-        names.dedup();
         names
     }
 
-    fn get_tag_single(&self, tag: &str) -> Option<String> {
-        let tags = self.get_tag_multi(tag);
-        let result = if tags.len() > 0 {
-            Some(tags[0].to_string())
-        } else {
-            None
-        };
-        result
+    fn get_tag_single<K: AsRef<str>>(&self, tag: K) -> Option<String> {
+        self.get_tag_single_ref(tag).map(|value| value.to_string())
     }
 
-    fn get_tag_multi(&self, tag: &str) -> Vec<String> {
-        self.comment_list
-            .clone()
-            .iter()
-            .filter(|comment| comment.0.to_lowercase() == tag.to_string().to_lowercase())
-            .map(|comment| comment.1.clone())
+    fn get_tag_multi<K: AsRef<str>>(&self, tag: K) -> Vec<String> {
+        self.get_tag_refs(tag)
+            .map(|value| value.to_string())
             .collect::<Vec<String>>()
     }
 
-    fn clear_tag(&mut self, tag: &str) {
-        self.comment_list
-            .retain(|comment| comment.0.to_lowercase() != tag.to_string().to_lowercase());
+    fn get_tag_refs<K: AsRef<str>>(&self, tag: K) -> TagRefs<'_> {
+        TagRefs::new(&self.comments, tag.as_ref())
     }
 
-    fn add_tag_single(&mut self, tag: &str, value: &str) {
-        self.comment_list
-            .push((tag.to_string().to_lowercase(), value.to_string()));
+    fn get_tag_single_ref<K: AsRef<str>>(&self, tag: K) -> Option<&str> {
+        self.get_tag_refs(tag).next()
     }
 
-    fn add_tag_multi(&mut self, tag: &str, values: &Vec<&str>) {
+    fn clear_tag<K: AsRef<str>>(&mut self, tag: K) {
+        self.comments.remove_tag(tag.as_ref());
+    }
+
+    fn add_tag_single<K: AsRef<str>>(&mut self, tag: K, value: &str) {
+        self.comments
+            .push(tag.as_ref().to_lowercase(), value.to_string());
+    }
+
+    fn add_tag_multi<K: AsRef<str>>(&mut self, tag: K, values: &[&str]) {
+        let tag = tag.as_ref();
         for value in values.iter() {
-            self.comment_list
-                .push((tag.to_string().to_lowercase(), value.to_string()));
+            self.add_tag_single(tag, value);
         }
     }
 
+    fn add_tag_verbatim<K: AsRef<str>>(&mut self, tag: K, value: &str) {
+        self.comments
+            .push(tag.as_ref().to_string(), value.to_string());
+    }
+
+    fn set_tag_verbatim<K: AsRef<str>>(&mut self, tag: K, value: &str) {
+        let tag = tag.as_ref();
+        self.clear_tag(tag);
+        self.add_tag_verbatim(tag, value);
+    }
+
+    fn get_tag_names_verbatim(&self) -> Vec<String> {
+        self.comments
+            .first_keys()
+            .into_iter()
+            .map(|key| key.to_string())
+            .collect()
+    }
+
+    fn set_tag_single<K: AsRef<str>>(&mut self, tag: K, value: &str) {
+        let tag = tag.as_ref();
+        self.clear_tag(tag);
+        self.add_tag_single(tag, value);
+    }
+
+    fn set_tag_multi<K: AsRef<str>>(&mut self, tag: K, values: &[&str]) {
+        let tag = tag.as_ref();
+        self.clear_tag(tag);
+        self.add_tag_multi(tag, values);
+    }
+
+    fn remove_tag_value<K: AsRef<str>>(&mut self, tag: K, value: &str) -> usize {
+        self.remove_tag_values_where(tag, |candidate| candidate == value)
+    }
+
+    fn remove_tag_values_where<K: AsRef<str>, F: FnMut(&str) -> bool>(
+        &mut self,
+        tag: K,
+        mut predicate: F,
+    ) -> usize {
+        self.comments
+            .retain_tag(tag.as_ref(), |value| !predicate(value))
+    }
+
+    fn rename_tag<K: AsRef<str>, N: AsRef<str>>(&mut self, old_tag: K, new_tag: N) -> usize {
+        let old_tag = old_tag.as_ref().to_lowercase();
+        let new_tag = new_tag.as_ref().to_lowercase();
+        if !self.comments.contains(&old_tag) {
+            return 0;
+        }
+        self.comments.rename_keys(|key| {
+            if key.to_lowercase() == old_tag {
+                Some(new_tag.clone())
+            } else {
+                None
+            }
+        })
+    }
+
+    fn map_values<F: FnMut(&str, &str) -> Option<String>>(&mut self, mut map: F) -> usize {
+        let mut changed = 0;
+        for (key, value) in self.comments.values_mut() {
+            if let Some(new_value) = map(key, value) {
+                if new_value != *value {
+                    *value = new_value;
+                    changed += 1;
+                }
+            }
+        }
+        changed
+    }
+
+    fn map_keys<F: FnMut(&str) -> Option<String>>(&mut self, mut map: F) -> usize {
+        self.comments
+            .rename_keys(|key| map(key).filter(|new_key| new_key != key))
+    }
+
+    fn entry<K: AsRef<str>>(&mut self, tag: K) -> TagEntry<'_> {
+        TagEntry::new(self, tag.as_ref())
+    }
+
+    fn iter(&self) -> TagIter<'_> {
+        TagIter::new(&self.comments)
+    }
+
+    fn extend_tags<I: IntoIterator<Item = (String, String)>>(&mut self, tags: I) {
+        for (key, value) in tags {
+            self.add_tag_single(&key, &value);
+        }
+    }
+
+    fn to_map(&self) -> BTreeMap<String, Vec<String>> {
+        self.comments
+            .names()
+            .map(|name| (name.clone(), self.get_tag_multi(name)))
+            .collect()
+    }
+
+    fn from_map(map: HashMap<String, Vec<String>>) -> CommentHeader {
+        let mut tags = map.into_iter().collect::<Vec<(String, Vec<String>)>>();
+        tags.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut header = CommentHeader::new();
+        header.extend_tags(
+            tags.into_iter().flat_map(|(key, values)| {
+                values.into_iter().map(move |value| (key.clone(), value))
+            }),
+        );
+        header
+    }
+
+    fn retain<F: FnMut(&str, &str) -> bool>(&mut self, keep: F) {
+        self.comments.retain(keep);
+    }
+
+    fn filter_keys(&mut self, keep: &[&str]) -> usize {
+        self.comments
+            .retain(|key, _| keep.iter().any(|pattern| glob_matches(pattern, key)))
+    }
+
+    fn remove_keys(&mut self, drop: &[&str]) -> usize {
+        self.comments
+            .retain(|key, _| !drop.iter().any(|pattern| glob_matches(pattern, key)))
+    }
+
+    fn sort_tags(&mut self) {
+        let mut entries = std::mem::take(&mut self.comments).into_vec();
+        entries.sort_by_cached_key(|(key, _)| key.to_lowercase());
+        self.comments = TagStore::from_vec(entries);
+    }
+
+    fn contains_tag<K: AsRef<str>>(&self, tag: K) -> bool {
+        self.comments.contains(tag.as_ref())
+    }
+
+    fn len(&self) -> usize {
+        self.comments.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.comments.len() == 0
+    }
+
+    fn encoded_size(&self) -> usize {
+        // Signature, vendor length, comment count and framing bit
+        let fixed = 7 + 4 + 4 + 1;
+        let comments: usize = self
+            .iter()
+            .map(|(key, value)| 4 + key.len() + 1 + value.len())
+            .sum();
+        fixed + self.vendor.len() + comments
+    }
+
+    fn to_pretty_string(&self) -> String {
+        self.iter()
+            .map(|(key, value)| format!("{}={}\n", key, value))
+            .collect()
+    }
+
+    fn eq_ignore_order_and_case(&self, other: &CommentHeader) -> bool {
+        fn sorted(header: &CommentHeader) -> Vec<(String, &str)> {
+            let mut entries = header
+                .iter()
+                .map(|(key, value)| (key.to_lowercase(), value))
+                .collect::<Vec<(String, &str)>>();
+            entries.sort_unstable();
+            entries
+        }
+        self.vendor == other.vendor && self.len() == other.len() && sorted(self) == sorted(other)
+    }
+
     fn get_vendor(&self) -> String {
         self.vendor.to_string()
     }
@@ -143,7 +579,6 @@ pub fn safe_make_comment_header(header: &CommentHeader) -> Result<Vec<u8>, Vorbi
 
     //write number of comments
     let comment_nbr: u32 = header
-        .comment_list
         .len()
         .try_into()
         .map_err(|_| VorbisMakeCommentError())?;
@@ -151,13 +586,12 @@ pub fn safe_make_comment_header(header: &CommentHeader) -> Result<Vec<u8>, Vorbi
 
     let mut commentstrings: Vec<String> = vec![];
     //write each comment
-    for comment in header.comment_list.iter() {
+    for comment in header.iter() {
         commentstrings.push(format!("{}={}", comment.0, comment.1));
         //let commenstrings.last().as_bytes();
         let comment_len: u32 = commentstrings
             .last()
-            .ok_or_else(|| VorbisMakeCommentError())?
-            .as_bytes()
+            .ok_or(VorbisMakeCommentError())?
             .len()
             .try_into()
             .map_err(|_| VorbisMakeCommentError())?;
@@ -165,7 +599,7 @@ pub fn safe_make_comment_header(header: &CommentHeader) -> Result<Vec<u8>, Vorbi
         new_packet.extend(
             commentstrings
                 .last()
-                .ok_or_else(|| VorbisMakeCommentError())?
+                .ok_or(VorbisMakeCommentError())?
                 .as_bytes()
                 .iter()
                 .cloned(),
@@ -180,85 +614,730 @@ pub fn make_comment_header(header: &CommentHeader) -> Vec<u8> {
     safe_make_comment_header(header).unwrap()
 }
 
-pub fn safe_read_comment_header<T: Read + Seek>(
+/// How tag names are cased when a header is written. Lookups ignore case
+/// either way, but many taggers conventionally write uppercase names.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CasePolicy {
+    /// Write names as they are stored.
+    #[default]
+    Preserve,
+    Uppercase,
+    Lowercase,
+}
+
+impl CasePolicy {
+    pub fn apply(&self, key: &str) -> String {
+        match self {
+            CasePolicy::Preserve => key.to_string(),
+            CasePolicy::Uppercase => key.to_uppercase(),
+            CasePolicy::Lowercase => key.to_lowercase(),
+        }
+    }
+
+    fn apply_to(&self, header: &CommentHeader) -> CommentHeader {
+        let mut header = header.clone();
+        if *self != CasePolicy::Preserve {
+            header.comments.rename_keys(|key| Some(self.apply(key)));
+        }
+        header
+    }
+}
+
+/// `safe_make_comment_header` with the tag names cased according to `policy`.
+pub fn safe_make_comment_header_with_case(
+    header: &CommentHeader,
+    policy: CasePolicy,
+) -> Result<Vec<u8>, VorbisMakeCommentError> {
+    safe_make_comment_header(&policy.apply_to(header))
+}
+
+impl From<PageReadError> for VorbisReadCommentError {
+    fn from(err: PageReadError) -> VorbisReadCommentError {
+        let err = match err {
+            PageReadError::Io(err) => OggReadError::ReadError(err),
+            PageReadError::Page(PageError::Truncated) => return VorbisReadCommentError::Truncated,
+            PageReadError::Page(PageError::NoCapturePattern) => OggReadError::NoCapturePatternFound,
+            PageReadError::Page(PageError::BadVersion(version)) => {
+                OggReadError::InvalidStreamStructVer(version)
+            }
+        };
+        VorbisReadCommentError::FailedReadOggFile(err)
+    }
+}
+
+/// Read the comment header packet of the first Vorbis stream, for parsing
+/// with `CommentHeaderRef::parse`. Pages are read one at a time and reading
+/// stops right after the page ending the comment header, so the audio pages
+/// are never read. Streams of other codecs starting before it are skipped.
+pub fn safe_read_comment_packet<T: Read>(f_in: T) -> Result<Vec<u8>, VorbisReadCommentError> {
+    safe_read_comment_packet_with_limits(f_in, &ParseLimits::default())
+}
+
+/// `safe_read_comment_packet`, failing with `HeaderTooLarge` as soon as the
+/// packet grows past `limits.max_header_size`. The other limits are left to
+/// the parsing.
+pub fn safe_read_comment_packet_with_limits<T: Read>(
+    mut f_in: T,
+    limits: &ParseLimits,
+) -> Result<Vec<u8>, VorbisReadCommentError> {
+    let mut serial = None;
+    let mut packets: Vec<Vec<u8>> = vec![vec![]];
+    loop {
+        let page = match page::read_page(&mut f_in, true)? {
+            Some((page, _)) => page,
+            None => return Err(VorbisReadCommentError::Truncated),
+        };
+        let computed = page.compute_checksum();
+        if page.checksum != computed {
+            let err = OggReadError::HashMismatch(page.checksum, computed);
+            return Err(VorbisReadCommentError::FailedReadOggFile(err));
+        }
+        match serial {
+            None if page.is_first() && page.body.starts_with(b"\x01vorbis") => {
+                serial = Some(page.serial)
+            }
+            // The beginning of another stream
+            None if page.is_first() => continue,
+            None => return Err(HeaderReadError::NotVorbisHeader.into()),
+            Some(serial) if serial != page.serial => continue,
+            Some(_) => {}
+        }
+        let mut pos = 0;
+        for lacing in page.lacing.iter() {
+            let len = *lacing as usize;
+            packets
+                .last_mut()
+                .unwrap()
+                .extend(&page.body[pos..pos + len]);
+            pos += len;
+            if len < 255 {
+                packets.push(vec![]);
+            }
+        }
+        // The identification header too, or a first packet that never ends
+        // would be buffered without bound
+        for packet in packets.iter().take(2) {
+            limits.check_size(packet.len())?;
+        }
+        if packets.len() > 2 {
+            break;
+        }
+    }
+    Ok(packets.swap_remove(1))
+}
+
+/// Read the comment header of the first Vorbis stream, see
+/// `safe_read_comment_packet`.
+pub fn safe_read_comment_header<T: Read>(f_in: T) -> Result<CommentHeader, VorbisReadCommentError> {
+    let packet = safe_read_comment_packet(f_in)?;
+    #[cfg(feature = "differential")]
+    differential::check(&packet);
+    let comment_hdr = parse_comment_header(&packet)?;
+    Ok(comment_hdr)
+}
+
+/// `safe_read_comment_header` for files from untrusted sources, checking
+/// the comment header against `limits` before parsing it.
+pub fn safe_read_comment_header_with_limits<T: Read>(
     f_in: T,
+    limits: &ParseLimits,
 ) -> Result<CommentHeader, VorbisReadCommentError> {
-    let mut reader = PacketReader::new(f_in);
+    let packet = safe_read_comment_packet_with_limits(f_in, limits)?;
+    limits.check_packet(&packet)?;
+    Ok(parse_comment_header(&packet)?)
+}
 
-    let packet: Packet = reader.read_packet_expected()?;
-    let stream_serial = packet.stream_serial();
+/// `safe_read_comment_header` decoding text that isn't valid UTF-8 with
+/// replacement characters, see `CommentHeaderRef::parse_lossy`, so damaged
+/// tags can still be read and fixed.
+pub fn safe_read_comment_header_lossy<T: Read>(
+    f_in: T,
+) -> Result<(CommentHeader, DecodeReport), VorbisReadCommentError> {
+    let packet = safe_read_comment_packet(f_in)?;
+    let (header, report) = CommentHeaderRef::parse_lossy(&packet)?;
+    Ok((header.to_owned(), report))
+}
+
+/// `safe_read_comment_header_lossy` decoding text that isn't valid UTF-8
+/// with `encoding` instead, see `CommentHeaderRef::parse_with_fallback`.
+pub fn safe_read_comment_header_with_fallback<T: Read>(
+    f_in: T,
+    encoding: LegacyEncoding,
+) -> Result<(CommentHeader, DecodeReport), VorbisReadCommentError> {
+    let packet = safe_read_comment_packet(f_in)?;
+    let (header, report) = CommentHeaderRef::parse_with_fallback(&packet, encoding)?;
+    Ok((header.to_owned(), report))
+}
+
+pub fn read_comment_header<T: Read>(f_in: T) -> CommentHeader {
+    safe_read_comment_header(f_in).unwrap()
+}
+
+/// Options for `safe_replace_comment_header_with_options`, built field by
+/// field or with `ReplaceOptions::builder`.
+#[derive(Clone, Debug, Default)]
+pub struct ReplaceOptions {
+    /// Set the end-of-stream flag on the last page written when the input
+    /// ends without one, so the output is a properly terminated stream.
+    pub mark_end_of_stream: bool,
+    /// When the input ends in the middle of a page, as downloads cut off do,
+    /// keep the packets of that page that are complete instead of leaving
+    /// the whole page out. They go on a page of their own with a granule
+    /// position counted from the audio, and `ReplaceReport::stats` tells
+    /// how many were kept. Has no effect when `raw_copy` transfers the pages
+    /// without parsing them.
+    pub salvage_truncated_page: bool,
+    /// Validate the output with `check_conformance`, failing with
+    /// `NonConformant` if any issue is found.
+    pub check_conformance: bool,
+    /// Read the output back and check with `safe_compare_audio` that every
+    /// packet but the comment header is byte for byte the one of the input,
+    /// in the same order, failing with `AudioMismatch` otherwise. As with
+    /// `check_conformance` the output is collected before any of it is
+    /// written, and the functions taking inputs that can't seek keep a copy
+    /// of the input to compare with.
+    pub verify_audio: bool,
+    /// What to do with the space freed when the new comment header is
+    /// smaller than the old one.
+    pub shrink: ShrinkStrategy,
+    /// Casing of the tag names written.
+    pub case_policy: CasePolicy,
+    /// Keep the vendor string of the file when the new header's vendor is
+    /// empty, as it is for a header made with `VorbisComments::new`.
+    pub preserve_vendor: bool,
+    /// Write `GENERIC_VENDOR` as the vendor and drop the `ENCODER_TAGS`,
+    /// see `VorbisPrivacy::anonymize_encoder`.
+    pub anonymize_encoder: bool,
+    /// Bytes to reserve in the comment header with a PADDING tag, so later
+    /// edits that grow the tags by up to that much can still be written with
+    /// `safe_patch_comment_header_in_place`. PADDING tags of the new header
+    /// are dropped first, and any space freed by a smaller header goes in
+    /// the same tag unless `shrink` is `Compact`.
+    pub reserve_padding: usize,
+    /// Copy every page other than the comment and setup header pages as it
+    /// is instead of writing the packets again, so the audio keeps its
+    /// exact page layout. Only the sequence numbers of the later pages change
+    /// when the headers need another number of pages. This needs the headers
+    /// laid out as `safe_surgical_replace_comment_header` requires.
+    pub copy_pages: bool,
+    /// As `copy_pages`, but when the later pages need no new sequence
+    /// numbers everything after the header pages is transferred with
+    /// `io::copy` without being parsed, so the time taken no longer depends
+    /// on the length of the audio. `ReplaceReport::truncated` is then always
+    /// false and `mark_end_of_stream` has no effect.
+    pub raw_copy: bool,
+    /// How the granule positions of the audio pages are written. `Preserve`
+    /// copies the pages as `copy_pages` does, and `Recompute` turns off the
+    /// `io::copy` of `raw_copy`.
+    pub granule_mode: GranuleMode,
+    /// Where pages end when the packets are written again.
+    pub pagination: Pagination,
+    /// Sort the tags with `VorbisComments::sort_tags` before writing, so the
+    /// same tags give the same bytes whatever order they were added in. The
+    /// rest of the output only depends on the input and the other options,
+    /// so an input and a set of tags always give the same file.
+    pub deterministic: bool,
+    /// Output size in bytes past which `safe_replace_comment_header_spilled`
+    /// moves the output to a temporary file. Never when `None`.
+    pub spill_threshold: Option<usize>,
+    /// Directory for those temporary files, the system one when `None`.
+    pub spill_dir: Option<PathBuf>,
+    /// Checked between packets, or pages when copying them, failing the
+    /// rewrite with `Cancelled` once cancelled. Staging and spill files are
+    /// removed as for any other error.
+    pub cancel: Option<CancelToken>,
+    /// Convert the values written to Unicode NFC.
+    #[cfg(feature = "nfc")]
+    pub normalize_nfc: bool,
+}
+
+/// Handling of a comment header that got smaller.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ShrinkStrategy {
+    /// Write the smaller header, making the file smaller.
+    #[default]
+    Compact,
+    /// Pad the packet after the framing bit with zero bytes up to its old
+    /// size, so the page layout stays the same.
+    PadPacket,
+    /// Fill the difference with a PADDING tag, which also gets shrunk on
+    /// the next rewrite to make room for new tags. PADDING tags already in
+    /// the new header are dropped first. Differences too small for a tag
+    /// are padded as with `PadPacket`.
+    PaddingTag,
+}
+
+/// Summary of a comment header replacement.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReplaceReport {
+    /// The input ended before a page with the end-of-stream flag was read.
+    pub truncated: bool,
+    /// The strategy applied if the new comment header was smaller.
+    pub shrink: Option<ShrinkStrategy>,
+    /// Bytes of padding added to the comment header.
+    pub slack: usize,
+    /// The new comment header packet is byte for byte the one in the input.
+    pub unchanged: bool,
+    pub stats: RewriteStats,
+}
 
-    let mut packet: Packet = reader.read_packet_expected()?;
-    //println!("{:?}",packet.data);
-    while packet.stream_serial() != stream_serial {
-        packet = reader.read_packet_expected()?;
-        //println!("{:?}",packet.data);
+/// Bytes a PADDING entry takes besides its value: the length, the name and "=".
+const PADDING_ENTRY_SIZE: usize = 4 + keys::PADDING.len() + 1;
+
+/// Make the comment packet replacing one of `old_len` bytes, returning it
+/// with the strategy applied and the padding added. A non-zero `reserve`
+/// adds a PADDING tag of at least that many bytes.
+pub(crate) fn fit_comment_packet(
+    new_header: &CommentHeader,
+    old_len: usize,
+    strategy: ShrinkStrategy,
+    reserve: usize,
+) -> (Vec<u8>, Option<ShrinkStrategy>, usize) {
+    if strategy == ShrinkStrategy::PaddingTag || reserve > 0 {
+        let mut unpadded = new_header.clone();
+        unpadded.clear_tag(keys::PADDING);
+        let data = make_comment_header(&unpadded);
+        let reserved = if reserve > 0 {
+            reserve.max(PADDING_ENTRY_SIZE)
+        } else {
+            0
+        };
+        let shrunk = strategy != ShrinkStrategy::Compact && data.len() + reserved < old_len;
+        let target = if shrunk {
+            old_len
+        } else {
+            data.len() + reserved
+        };
+        let slack = target - data.len();
+        let applied = shrunk.then_some(ShrinkStrategy::PaddingTag);
+        if slack == 0 {
+            return (data, None, 0);
+        }
+        if slack >= PADDING_ENTRY_SIZE {
+            unpadded.add_tag_single(keys::PADDING, &" ".repeat(slack - PADDING_ENTRY_SIZE));
+            return (make_comment_header(&unpadded), applied, slack);
+        }
+        let mut data = data;
+        data.resize(target, 0);
+        return (data, Some(ShrinkStrategy::PadPacket), slack);
+    }
+    let mut data = make_comment_header(new_header);
+    if data.len() >= old_len {
+        return (data, None, 0);
+    }
+    match strategy {
+        ShrinkStrategy::PadPacket => {
+            let slack = old_len - data.len();
+            data.resize(old_len, 0);
+            (data, Some(strategy), slack)
+        }
+        _ => (data, Some(strategy), 0),
     }
-    let comment_hdr = lewton::header::read_header_comment(&packet.data)?; //println!("{:?}", comment_hdr);
-    Ok(comment_hdr)
 }
 
-pub fn read_comment_header<T: Read + Seek>(f_in: T) -> CommentHeader {
-    return safe_read_comment_header(f_in).unwrap();
+fn end_info(packet: &Packet, ends_page: bool) -> PacketWriteEndInfo {
+    if packet.last_in_stream() {
+        PacketWriteEndInfo::EndStream
+    } else if ends_page {
+        PacketWriteEndInfo::EndPage
+    } else {
+        PacketWriteEndInfo::NormalPacket
+    }
 }
 
 pub fn safe_replace_comment_header<T: Read + Seek>(
     f_in: T,
     new_header: CommentHeader,
 ) -> Result<Cursor<Vec<u8>>, VorbisReplaceCommentError> {
-    let new_comment_data = make_comment_header(&new_header);
+    let (f_out, _report) =
+        safe_replace_comment_header_with_options(f_in, new_header, &ReplaceOptions::default())?;
+    Ok(f_out)
+}
+
+pub fn safe_replace_comment_header_with_options<T: Read + Seek>(
+    mut f_in: T,
+    new_header: CommentHeader,
+    options: &ReplaceOptions,
+) -> Result<(Cursor<Vec<u8>>, ReplaceReport), VorbisReplaceCommentError> {
+    let start = f_in.stream_position()?;
+    let mut f_out = Cursor::new(vec![]);
+    let report = write_replaced(
+        &mut f_in,
+        &mut f_out,
+        &prepare_header(new_header, options),
+        options,
+    )?;
+    if options.check_conformance {
+        let issues = check_conformance(f_out.get_ref());
+        if !issues.is_empty() {
+            return Err(VorbisReplaceCommentError::NonConformant(issues));
+        }
+    }
+    if options.verify_audio {
+        f_in.seek(io::SeekFrom::Start(start))?;
+        check_audio(f_in, f_out.get_ref())?;
+    }
+    f_out.seek(std::io::SeekFrom::Start(0))?;
+    Ok((f_out, report))
+}
+
+pub fn safe_replace_comment_header_into<T: Read + Seek, W: Write>(
+    f_in: T,
+    f_out: W,
+    new_header: CommentHeader,
+) -> Result<ReplaceReport, VorbisReplaceCommentError> {
+    safe_replace_comment_header_into_with_options(
+        f_in,
+        f_out,
+        new_header,
+        &ReplaceOptions::default(),
+    )
+}
+
+/// Write the file with its new header to `f_out` as it is produced, instead
+/// of collecting it in memory. With `check_conformance` or `verify_audio` the
+/// output has to be checked before any of it is written, so it is still
+/// collected first.
+pub fn safe_replace_comment_header_into_with_options<T: Read + Seek, W: Write>(
+    f_in: T,
+    mut f_out: W,
+    new_header: CommentHeader,
+    options: &ReplaceOptions,
+) -> Result<ReplaceReport, VorbisReplaceCommentError> {
+    if options.check_conformance || options.verify_audio {
+        let (buffer, report) = safe_replace_comment_header_with_options(f_in, new_header, options)?;
+        f_out.write_all(buffer.get_ref())?;
+        f_out.flush()?;
+        return Ok(report);
+    }
+    let report = write_replaced(
+        f_in,
+        &mut f_out,
+        &prepare_header(new_header, options),
+        options,
+    )?;
+    f_out.flush()?;
+    Ok(report)
+}
+
+pub fn replace_comment_header_into<T: Read + Seek, W: Write>(
+    f_in: T,
+    f_out: W,
+    new_header: CommentHeader,
+) -> ReplaceReport {
+    safe_replace_comment_header_into(f_in, f_out, new_header).unwrap()
+}
+
+/// `safe_replace_comment_header_into_with_options` for inputs that can't
+/// seek, such as pipes, sockets and archive entries. The pages are copied as
+/// with `ReplaceOptions::copy_pages`, so the headers must be laid out as it
+/// requires.
+pub fn safe_replace_comment_header_streaming<T: Read, W: Write>(
+    f_in: T,
+    f_out: W,
+    new_header: CommentHeader,
+    options: &ReplaceOptions,
+) -> Result<ReplaceReport, VorbisReplaceCommentError> {
+    stream_replaced(
+        f_in,
+        f_out,
+        |_| prepare_header(new_header, options),
+        options,
+    )
+}
+
+/// Stream `f_in` to `f_out` with its comment header edited by `edit`,
+/// reading the input once. Pages are written as soon as they are read, so
+/// this works as a filter between stdin and stdout or in a request handler,
+/// see `safe_replace_comment_header_streaming` for the requirements.
+pub fn safe_transform_comment_header<T, W, F>(
+    f_in: T,
+    f_out: W,
+    edit: F,
+    options: &ReplaceOptions,
+) -> Result<ReplaceReport, VorbisReplaceCommentError>
+where
+    T: Read,
+    W: Write,
+    F: FnOnce(&mut CommentHeader),
+{
+    let make_header = |old_header: &CommentHeader| {
+        let mut header = old_header.clone();
+        edit(&mut header);
+        prepare_header(header, options)
+    };
+    stream_replaced(f_in, f_out, make_header, options)
+}
+
+pub fn transform_comment_header<T: Read, W: Write, F: FnOnce(&mut CommentHeader)>(
+    f_in: T,
+    f_out: W,
+    edit: F,
+) -> ReplaceReport {
+    safe_transform_comment_header(f_in, f_out, edit, &ReplaceOptions::default()).unwrap()
+}
+
+/// Copy the pages of `f_in` to `f_out` with a new comment header, checking
+/// the whole output before writing any of it with `check_conformance` or
+/// `verify_audio`.
+fn stream_replaced<T, W, H>(
+    f_in: T,
+    mut f_out: W,
+    make_header: H,
+    options: &ReplaceOptions,
+) -> Result<ReplaceReport, VorbisReplaceCommentError>
+where
+    T: Read,
+    W: Write,
+    H: FnOnce(&CommentHeader) -> CommentHeader,
+{
+    if options.check_conformance || options.verify_audio {
+        let mut f_in = Recorded::new(f_in);
+        let mut buffer = vec![];
+        let report = surgical::write_copied_pages(&mut f_in, &mut buffer, make_header, options)?;
+        if options.check_conformance {
+            let issues = check_conformance(&buffer);
+            if !issues.is_empty() {
+                return Err(VorbisReplaceCommentError::NonConformant(issues));
+            }
+        }
+        if options.verify_audio {
+            check_audio(Cursor::new(&f_in.data), &buffer)?;
+        }
+        f_out.write_all(&buffer)?;
+        f_out.flush()?;
+        return Ok(report);
+    }
+    surgical::write_copied_pages(f_in, f_out, make_header, options)
+}
+
+/// Fail with `AudioMismatch` unless `output` has the packets of `f_in`.
+fn check_audio<T: Read + Seek>(f_in: T, output: &[u8]) -> Result<(), VorbisReplaceCommentError> {
+    match safe_compare_audio(f_in, Cursor::new(output))? {
+        Some(index) => Err(VorbisReplaceCommentError::AudioMismatch(index)),
+        None => Ok(()),
+    }
+}
 
-    let f_out_ram: Vec<u8> = vec![];
-    let mut f_out = Cursor::new(f_out_ram);
+/// The header as it will be written, with the options applied.
+fn prepare_header(new_header: CommentHeader, options: &ReplaceOptions) -> CommentHeader {
+    let mut new_header = options.case_policy.apply_to(&new_header);
+    if options.anonymize_encoder {
+        new_header.anonymize_encoder();
+    }
+    if options.deterministic {
+        new_header.sort_tags();
+    }
+    #[cfg(feature = "nfc")]
+    if options.normalize_nfc {
+        new_header.normalize_nfc();
+    }
+    new_header
+}
 
-    let mut reader = PacketReader::new(f_in);
+/// The comment packet replacing `old_header`, a packet of `old_len` bytes.
+pub(crate) fn fit_replacement(
+    new_header: &CommentHeader,
+    old_header: CommentHeader,
+    old_len: usize,
+    options: &ReplaceOptions,
+) -> (Vec<u8>, Option<ShrinkStrategy>, usize) {
+    let reserve = options.reserve_padding;
+    if options.preserve_vendor && new_header.vendor.is_empty() {
+        let mut new_header = new_header.clone();
+        new_header.vendor = old_header.vendor;
+        fit_comment_packet(&new_header, old_len, options.shrink, reserve)
+    } else {
+        fit_comment_packet(new_header, old_len, options.shrink, reserve)
+    }
+}
+
+/// Whether replacing the comment header packet `old_packet` with
+/// `new_header` would write the same bytes again.
+pub(crate) fn is_unchanged(
+    new_header: CommentHeader,
+    old_packet: &[u8],
+    options: &ReplaceOptions,
+) -> Result<bool, HeaderReadError> {
+    let old_header = parse_comment_header(old_packet)?;
+    let new_header = prepare_header(new_header, options);
+    let (packet, _, _) = fit_replacement(&new_header, old_header, old_packet.len(), options);
+    Ok(packet == old_packet)
+}
+
+/// Copy the packets of `f_in` to `f_out`, replacing the comment header.
+fn write_replaced<T: Read + Seek, W: Write>(
+    f_in: T,
+    f_out: W,
+    new_header: &CommentHeader,
+    options: &ReplaceOptions,
+) -> Result<ReplaceReport, VorbisReplaceCommentError> {
+    if options.copy_pages || options.raw_copy || options.granule_mode == GranuleMode::Preserve {
+        return surgical::write_copied_pages(f_in, f_out, |_| new_header.clone(), options);
+    }
+    let mut f_in = Counted::new(f_in);
+    if options.salvage_truncated_page {
+        f_in.keep_partial_page();
+    }
+    let mut f_out = Counted::new(f_out);
+    let mut reader = PacketReader::new(&mut f_in);
     let mut writer = PacketWriter::new(&mut f_out);
+    let mut warnings = vec![];
 
     let mut header_done = false;
+    let mut end_of_stream = false;
+    let mut shrink = None;
+    let mut slack = 0;
+    let mut unchanged = false;
+    let mut serial = None;
+    // Also counted to give salvaged packets their granule position
+    let recompute = options.granule_mode == GranuleMode::Recompute;
+    let mut counter = (recompute || options.salvage_truncated_page).then(GranuleCounter::new);
+    let mut cut_short = false;
+    // Packets are written one behind the reader with their granule position
+    // and whether they end a page, so that the last one can still be given
+    // the right end info once the input runs out.
+    let mut layout = PageLayout::new(&options.pagination);
+    let mut pending: Option<(Packet, u64, bool)> = None;
     loop {
-        let rp = reader.read_packet();
-        match rp {
-            Ok(r) => {
-                match r {
-                    Some(mut packet) => {
-                        let inf = if packet.last_in_stream() {
-                            PacketWriteEndInfo::EndStream
-                        } else if packet.last_in_page() {
-                            PacketWriteEndInfo::EndPage
-                        } else {
-                            PacketWriteEndInfo::NormalPacket
-                        };
-                        if !header_done {
-                            let comment_hdr = lewton::header::read_header_comment(&packet.data);
-                            match comment_hdr {
-                                Ok(_hdr) => {
-                                    // This is the packet to replace
-                                    packet.data = new_comment_data.clone();
-                                    header_done = true;
-                                }
-                                Err(_error) => {}
-                            }
-                        }
-                        let lastpacket = packet.last_in_stream() && packet.last_in_page();
-                        let stream_serial = packet.stream_serial();
-                        let absgp_page = packet.absgp_page();
-                        writer.write_packet(packet.data, stream_serial, inf, absgp_page)?;
-                        if lastpacket {
-                            break;
-                        }
-                    }
-                    // End of stream
-                    None => break,
+        if cancel::is_cancelled(&options.cancel) {
+            return Err(VorbisReplaceCommentError::Cancelled);
+        }
+        let rp = match reader.read_packet() {
+            Ok(r) => r,
+            // The last page was cut short, treat it like the end of the file
+            Err(OggReadError::ReadError(ref error))
+                if error.kind() == io::ErrorKind::UnexpectedEof =>
+            {
+                cut_short = true;
+                None
+            }
+            Err(error) => return Err(error.into()),
+        };
+        let mut packet = match rp {
+            Some(packet) => packet,
+            // End of file
+            None => break,
+        };
+        if let Some((previous, granule, ends_page)) = pending.take() {
+            let inf = end_info(&previous, ends_page);
+            let stream_serial = previous.stream_serial();
+            writer.write_packet(previous.data, stream_serial, inf, granule)?;
+        }
+        if !header_done {
+            let comment_hdr = parse_comment_header(&packet.data);
+            match comment_hdr {
+                Ok(old_header) => {
+                    // This is the packet to replace
+                    let fitted =
+                        fit_replacement(new_header, old_header, packet.data.len(), options);
+                    unchanged = fitted.0 == packet.data;
+                    packet.data = fitted.0;
+                    shrink = fitted.1;
+                    slack = fitted.2;
+                    header_done = true;
+                }
+                Err(_error) => {}
+            }
+        }
+        let lastpacket = packet.last_in_stream() && packet.last_in_page();
+        let in_stream = *serial.get_or_insert(packet.stream_serial()) == packet.stream_serial();
+        let mut granule = packet.absgp_page();
+        if let Some(counter) = counter.as_mut() {
+            if in_stream {
+                let position = counter
+                    .next(&packet.data)
+                    .map_err(VorbisReadCommentError::from)?;
+                if recompute {
+                    granule = if packet.last_in_stream() {
+                        position.min(granule)
+                    } else {
+                        position
+                    };
                 }
             }
-            Err(error) => {
-                println!("Error reading packet: {:?}", error);
-                break;
+        }
+        let ends_page = if in_stream {
+            layout.ends_page(packet.data.len(), packet.last_in_page())
+        } else {
+            packet.last_in_page()
+        };
+        pending = Some((packet, granule, ends_page));
+        if lastpacket {
+            end_of_stream = true;
+            break;
+        }
+    }
+    drop(reader);
+    let mut last = pending.map(|(packet, granule, _)| {
+        let last_in_stream = packet.last_in_stream();
+        (packet.stream_serial(), packet.data, granule, last_in_stream)
+    });
+    if cut_short {
+        let salvaged = match (serial, counter.as_mut()) {
+            (Some(serial), Some(counter)) if options.salvage_truncated_page => {
+                salvage_packets(f_in.partial_page())
+                    .filter(|salvaged| salvaged.serial == serial && !salvaged.packets.is_empty())
+                    .map(|salvaged| (serial, salvaged.packets, counter))
+            }
+            _ => None,
+        };
+        match salvaged {
+            Some((serial, packets, counter)) => {
+                warnings.push(RewriteWarning::PartialPageSalvaged {
+                    packets: packets.len(),
+                });
+                // The packets go on a page of their own
+                if let Some((stream_serial, data, granule, _)) = last.take() {
+                    writer.write_packet(
+                        data,
+                        stream_serial,
+                        PacketWriteEndInfo::EndPage,
+                        granule,
+                    )?;
+                }
+                for data in packets {
+                    let granule = counter.next(&data).map_err(VorbisReadCommentError::from)?;
+                    if let Some((stream_serial, data, granule, _)) =
+                        last.replace((serial, data, granule, false))
+                    {
+                        writer.write_packet(
+                            data,
+                            stream_serial,
+                            PacketWriteEndInfo::NormalPacket,
+                            granule,
+                        )?;
+                    }
+                }
             }
+            None => warnings.push(RewriteWarning::PartialPageDropped),
         }
     }
-    f_out.seek(std::io::SeekFrom::Start(0))?;
-    Ok(f_out)
+    if let Some((stream_serial, data, granule, last_in_stream)) = last {
+        let inf = if last_in_stream {
+            PacketWriteEndInfo::EndStream
+        } else if options.mark_end_of_stream {
+            warnings.push(RewriteWarning::EndOfStreamMarked);
+            PacketWriteEndInfo::EndStream
+        } else {
+            // Always finish the page, or the packet would never be written
+            PacketWriteEndInfo::EndPage
+        };
+        writer.write_packet(data, stream_serial, inf, granule)?;
+    }
+    drop(writer);
+    let mut report = ReplaceReport {
+        truncated: !end_of_stream,
+        shrink,
+        slack,
+        unchanged,
+        stats: RewriteStats {
+            warnings,
+            ..Default::default()
+        },
+    };
+    report.stats.count(&f_in, &f_out);
+    Ok(report)
 }
 
 pub fn replace_comment_header<T: Read + Seek>(
@@ -0,0 +1,82 @@
+// Bounds on what a comment header may claim, for untrusted input
+
+use crate::VorbisReadCommentError;
+
+/// Limits checked before a comment header is parsed, failing with a
+/// dedicated `VorbisReadCommentError` variant. `None` sets no limit.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// Bytes of the vendor string.
+    pub max_vendor_len: Option<usize>,
+    pub max_comments: Option<usize>,
+    /// Bytes of a single "KEY=value" comment.
+    pub max_comment_len: Option<usize>,
+    /// Bytes of the whole packet. Also checked as the packet is read, on the
+    /// identification header before it as well, so a file can't make either
+    /// be buffered past the limit.
+    pub max_header_size: Option<usize>,
+}
+
+fn over(len: usize, limit: Option<usize>) -> bool {
+    limit.is_some_and(|limit| len > limit)
+}
+
+impl ParseLimits {
+    /// Limits no tagger comes close to, leaving room for embedded pictures:
+    /// a 1 KiB vendor string, 10000 comments, 16 MiB per comment and 64 MiB
+    /// in total.
+    pub fn untrusted() -> ParseLimits {
+        ParseLimits {
+            max_vendor_len: Some(1 << 10),
+            max_comments: Some(10_000),
+            max_comment_len: Some(16 << 20),
+            max_header_size: Some(64 << 20),
+        }
+    }
+
+    pub(crate) fn check_size(&self, size: usize) -> Result<(), VorbisReadCommentError> {
+        if over(size, self.max_header_size) {
+            return Err(VorbisReadCommentError::HeaderTooLarge(size));
+        }
+        Ok(())
+    }
+
+    /// Check the lengths and count the packet claims. A packet too short for
+    /// what it claims is left for the parser to reject.
+    pub(crate) fn check_packet(&self, packet: &[u8]) -> Result<(), VorbisReadCommentError> {
+        self.check_size(packet.len())?;
+        let u32_at = |pos: usize| {
+            packet
+                .get(pos..pos.checked_add(4)?)
+                .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+        };
+        // After the packet type and "vorbis"
+        let vendor_len = match u32_at(7) {
+            Some(len) => len,
+            None => return Ok(()),
+        };
+        if over(vendor_len, self.max_vendor_len) {
+            return Err(VorbisReadCommentError::VendorTooLong(vendor_len));
+        }
+        let mut pos = vendor_len.saturating_add(11);
+        let count = match u32_at(pos) {
+            Some(count) => count,
+            None => return Ok(()),
+        };
+        if over(count, self.max_comments) {
+            return Err(VorbisReadCommentError::TooManyComments(count));
+        }
+        pos += 4;
+        for _ in 0..count {
+            let len = match u32_at(pos) {
+                Some(len) => len,
+                None => return Ok(()),
+            };
+            if over(len, self.max_comment_len) {
+                return Err(VorbisReadCommentError::CommentTooLong(len));
+            }
+            pos = pos.saturating_add(len).saturating_add(4);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+// Lyrics in LYRICS and UNSYNCEDLYRICS tags, optionally with a language
+// suffix such as LYRICS-ENG
+
+use crate::keys;
+use crate::language::tag_language;
+use crate::{CommentHeader, VorbisComments};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lyrics {
+    /// Lowercase language code from the tag name suffix, if any.
+    pub language: Option<String>,
+    pub text: String,
+}
+
+/// The language of a lyrics tag name, `Some(None)` for an unsuffixed one.
+fn lyrics_language(key: &str) -> Option<Option<String>> {
+    tag_language(key, keys::UNSYNCEDLYRICS).or_else(|| tag_language(key, keys::LYRICS))
+}
+
+pub trait VorbisLyrics {
+    /// All lyrics, in tag order.
+    fn lyrics(&self) -> Vec<Lyrics>;
+    /// Replace the lyrics in the given language (or without a language),
+    /// written as `LYRICS` or `LYRICS-<LANGUAGE>`.
+    fn set_lyrics(&mut self, language: Option<&str>, text: &str);
+}
+
+impl VorbisLyrics for CommentHeader {
+    fn lyrics(&self) -> Vec<Lyrics> {
+        self.iter()
+            .filter_map(|(key, value)| {
+                lyrics_language(key).map(|language| Lyrics {
+                    language,
+                    text: value.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    fn set_lyrics(&mut self, language: Option<&str>, text: &str) {
+        let language = language.map(|language| language.to_lowercase());
+        self.retain(|key, _| lyrics_language(key) != Some(language.clone()));
+        let key = match &language {
+            Some(language) => format!("{}-{}", keys::LYRICS, language),
+            None => keys::LYRICS.to_string(),
+        };
+        self.add_tag_single(&key, text);
+    }
+}
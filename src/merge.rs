@@ -0,0 +1,61 @@
+// Combining the tags of two headers
+
+use crate::{CommentHeader, VorbisComments};
+
+/// What `merge` does with a tag that the other header has.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Replace the existing values with the other header's.
+    Overwrite,
+    /// Add the other header's values after the existing ones, skipping
+    /// values the tag already has.
+    Append,
+    /// Only take the tag if this header doesn't have it yet.
+    KeepExisting,
+}
+
+pub trait VorbisMerge {
+    /// Take the tags of `other` using `strategy` for every tag. The vendor
+    /// is left alone.
+    fn merge(&mut self, other: &CommentHeader, strategy: MergeStrategy);
+    /// `merge` with the strategy chosen per tag, given its lowercase name.
+    fn merge_with<F: FnMut(&str) -> MergeStrategy>(&mut self, other: &CommentHeader, strategy: F);
+}
+
+impl VorbisMerge for CommentHeader {
+    fn merge(&mut self, other: &CommentHeader, strategy: MergeStrategy) {
+        self.merge_with(other, |_| strategy);
+    }
+
+    fn merge_with<F: FnMut(&str) -> MergeStrategy>(
+        &mut self,
+        other: &CommentHeader,
+        mut strategy: F,
+    ) {
+        for tag in other.get_tag_names() {
+            let values = other.get_tag_refs(&tag);
+            match strategy(&tag) {
+                MergeStrategy::Overwrite => {
+                    self.clear_tag(&tag);
+                    for value in values {
+                        self.add_tag_single(&tag, value);
+                    }
+                }
+                MergeStrategy::Append => {
+                    for value in values {
+                        if !self.get_tag_refs(&tag).any(|existing| existing == value) {
+                            self.add_tag_single(&tag, value);
+                        }
+                    }
+                }
+                MergeStrategy::KeepExisting => {
+                    if !self.contains_tag(&tag) {
+                        for value in values {
+                            self.add_tag_single(&tag, value);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+// Memory mapped input files, enabled by the `mmap` feature
+
+use crate::batch::VorbisBatchError;
+use crate::{
+    safe_read_comment_header, safe_replace_comment_header_into_with_options, CommentHeader,
+    ReplaceOptions, ReplaceReport,
+};
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{self, Cursor, Write};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// A file mapped into memory, read as a byte slice.
+///
+/// The mapping sees changes made to the file while it is open, and the
+/// process may be killed if the file is truncated meanwhile, so only map
+/// files no one else writes to.
+pub struct MappedFile {
+    path: PathBuf,
+    map: Mmap,
+}
+
+impl MappedFile {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<MappedFile> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        // The risks are those of the file changing, as documented above
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(MappedFile {
+            path: path.to_path_buf(),
+            map,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// A reader over the mapping, for the functions taking `Read + Seek`.
+    pub fn reader(&self) -> Cursor<&[u8]> {
+        Cursor::new(&self.map)
+    }
+}
+
+impl Deref for MappedFile {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.map
+    }
+}
+
+fn open(path: &Path) -> Result<MappedFile, VorbisBatchError> {
+    MappedFile::open(path).map_err(|err| VorbisBatchError::Io(path.to_path_buf(), err))
+}
+
+/// `safe_read_comment_header` on the mapped file at `path`.
+pub fn safe_read_comment_header_mapped<P: AsRef<Path>>(
+    path: P,
+) -> Result<CommentHeader, VorbisBatchError> {
+    let path = path.as_ref();
+    let mapped = open(path)?;
+    safe_read_comment_header(mapped.reader())
+        .map_err(|err| VorbisBatchError::Read(path.to_path_buf(), err))
+}
+
+/// `safe_replace_comment_header_into_with_options` reading the mapped file
+/// at `path`.
+pub fn safe_replace_comment_header_mapped_into<P: AsRef<Path>, W: Write>(
+    path: P,
+    f_out: W,
+    new_header: CommentHeader,
+    options: &ReplaceOptions,
+) -> Result<ReplaceReport, VorbisBatchError> {
+    let path = path.as_ref();
+    let mapped = open(path)?;
+    safe_replace_comment_header_into_with_options(mapped.reader(), f_out, new_header, options)
+        .map_err(|err| VorbisBatchError::Replace(path.to_path_buf(), err))
+}
@@ -0,0 +1,62 @@
+// Converting between legacy joined values and repeated entries
+
+use crate::{CommentHeader, VorbisComments};
+
+/// Split `value` at each of the separators, trimming the parts and dropping
+/// empty ones.
+pub fn split_value(value: &str, separators: &[&str]) -> Vec<String> {
+    let mut parts = vec![value.to_string()];
+    for separator in separators.iter().filter(|separator| !separator.is_empty()) {
+        parts = parts
+            .iter()
+            .flat_map(|part| part.split(separator))
+            .map(|part| part.to_string())
+            .collect();
+    }
+    parts
+        .iter()
+        .map(|part| part.trim())
+        .filter(|part| !part.is_empty())
+        .map(|part| part.to_string())
+        .collect()
+}
+
+pub trait VorbisMultiValue {
+    /// Turn values such as "A; B / C" into one entry per part, e.g. with the
+    /// separators `[";", "/"]`. Returns how many entries were added.
+    fn split_multivalue<K: AsRef<str>>(&mut self, tag: K, separators: &[&str]) -> usize;
+    /// Join all values of the tag into a single entry, for players that only
+    /// read the first one.
+    fn join_multivalue<K: AsRef<str>>(&mut self, tag: K, separator: &str);
+}
+
+impl VorbisMultiValue for CommentHeader {
+    fn split_multivalue<K: AsRef<str>>(&mut self, tag: K, separators: &[&str]) -> usize {
+        let tag = tag.as_ref();
+        let before = self.get_tag_refs(tag).count();
+        let values = self
+            .get_tag_refs(tag)
+            .flat_map(|value| split_value(value, separators))
+            .collect::<Vec<String>>();
+        if values.len() == before {
+            return 0;
+        }
+        let values = values
+            .iter()
+            .map(|value| value.as_str())
+            .collect::<Vec<&str>>();
+        self.set_tag_multi(tag, &values);
+        values.len().saturating_sub(before)
+    }
+
+    fn join_multivalue<K: AsRef<str>>(&mut self, tag: K, separator: &str) {
+        let tag = tag.as_ref();
+        if self.get_tag_refs(tag).count() > 1 {
+            let joined = self
+                .get_tag_refs(tag)
+                .collect::<Vec<&str>>()
+                .join(separator);
+            self.set_tag_single(tag, &joined);
+        }
+    }
+}
@@ -0,0 +1,192 @@
+// BPM and INITIALKEY, with Camelot wheel notation for keys
+
+use crate::keys;
+use crate::{CommentHeader, VorbisComments};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Note {
+    C,
+    CSharp,
+    D,
+    DSharp,
+    E,
+    F,
+    FSharp,
+    G,
+    GSharp,
+    A,
+    ASharp,
+    B,
+}
+
+const NOTES: [Note; 12] = [
+    Note::C,
+    Note::CSharp,
+    Note::D,
+    Note::DSharp,
+    Note::E,
+    Note::F,
+    Note::FSharp,
+    Note::G,
+    Note::GSharp,
+    Note::A,
+    Note::ASharp,
+    Note::B,
+];
+
+impl Note {
+    /// Semitones above C.
+    pub fn pitch_class(&self) -> u8 {
+        NOTES.iter().position(|note| note == self).unwrap() as u8
+    }
+
+    pub fn from_pitch_class(pitch_class: u8) -> Note {
+        NOTES[pitch_class as usize % 12]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Major,
+    Minor,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MusicalKey {
+    pub note: Note,
+    pub mode: Mode,
+}
+
+const MAJOR_NAMES: [&str; 12] = [
+    "C", "Db", "D", "Eb", "E", "F", "F#", "G", "Ab", "A", "Bb", "B",
+];
+const MINOR_NAMES: [&str; 12] = [
+    "Cm", "C#m", "Dm", "Ebm", "Em", "Fm", "F#m", "Gm", "G#m", "Am", "Bbm", "Bm",
+];
+
+impl MusicalKey {
+    /// Parse standard notation ("C", "F#m", "Bbm", "A minor", "Ebmaj") or
+    /// Camelot notation ("8A", "12B").
+    pub fn parse(value: &str) -> Option<MusicalKey> {
+        let value = value.trim();
+        if let Some(key) = MusicalKey::from_camelot(value) {
+            return Some(key);
+        }
+        let mut chars = value.chars();
+        let base = match chars.next()?.to_ascii_uppercase() {
+            'C' => 0,
+            'D' => 2,
+            'E' => 4,
+            'F' => 5,
+            'G' => 7,
+            'A' => 9,
+            'B' => 11,
+            _ => return None,
+        };
+        let mut rest = chars.as_str();
+        let mut pitch_class = base;
+        if let Some(after) = rest.strip_prefix(['#', '♯']) {
+            pitch_class += 1;
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix(['b', '♭']) {
+            pitch_class += 11;
+            rest = after;
+        }
+        let mode = match rest.trim().to_lowercase().as_str() {
+            "" | "maj" | "major" => Mode::Major,
+            "m" | "min" | "minor" => Mode::Minor,
+            _ => return None,
+        };
+        Some(MusicalKey {
+            note: Note::from_pitch_class(pitch_class),
+            mode,
+        })
+    }
+
+    /// Standard notation, such as "Am" or "Eb".
+    pub fn to_tag_value(&self) -> String {
+        let pitch_class = self.note.pitch_class() as usize;
+        match self.mode {
+            Mode::Major => MAJOR_NAMES[pitch_class],
+            Mode::Minor => MINOR_NAMES[pitch_class],
+        }
+        .to_string()
+    }
+
+    /// Camelot wheel position, 1 to 12, and letter, 'A' for minor and 'B'
+    /// for major keys.
+    pub fn camelot(&self) -> (u8, char) {
+        let pitch_class = self.note.pitch_class();
+        let (offset, letter) = match self.mode {
+            Mode::Major => (8, 'B'),
+            Mode::Minor => (5, 'A'),
+        };
+        // Each step on the wheel is a fifth, seven semitones
+        let number = (7 * pitch_class + offset) % 12;
+        (if number == 0 { 12 } else { number }, letter)
+    }
+
+    pub fn to_camelot(&self) -> String {
+        let (number, letter) = self.camelot();
+        format!("{}{}", number, letter)
+    }
+
+    pub fn from_camelot(value: &str) -> Option<MusicalKey> {
+        let value = value.trim();
+        let last = value.chars().last()?;
+        let number = value[..value.len() - last.len_utf8()].parse::<u8>().ok()?;
+        if !(1..=12).contains(&number) {
+            return None;
+        }
+        let (offset, mode) = match last.to_ascii_uppercase() {
+            'B' => (8, Mode::Major),
+            'A' => (5, Mode::Minor),
+            _ => return None,
+        };
+        // 7 is its own inverse modulo 12
+        let pitch_class = 7 * ((number + 12 - offset) % 12) % 12;
+        Some(MusicalKey {
+            note: Note::from_pitch_class(pitch_class),
+            mode,
+        })
+    }
+}
+
+pub trait VorbisMusical {
+    fn bpm(&self) -> Option<f64>;
+    /// Write BPM rounded to two decimals, or remove it when `None` or not a
+    /// finite positive number, which `bpm` wouldn't read back.
+    fn set_bpm(&mut self, bpm: Option<f64>);
+    /// INITIALKEY, in standard or Camelot notation.
+    fn initial_key(&self) -> Option<MusicalKey>;
+    /// Write INITIALKEY in standard notation, or remove it when `None`.
+    fn set_initial_key(&mut self, key: Option<MusicalKey>);
+}
+
+impl VorbisMusical for CommentHeader {
+    fn bpm(&self) -> Option<f64> {
+        self.get_tag_single(keys::BPM)?
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|bpm| bpm.is_finite() && *bpm > 0.0)
+    }
+
+    fn set_bpm(&mut self, bpm: Option<f64>) {
+        self.clear_tag(keys::BPM);
+        if let Some(bpm) = bpm.filter(|bpm| bpm.is_finite() && *bpm > 0.0) {
+            self.add_tag_single(keys::BPM, &format!("{}", (bpm * 100.0).round() / 100.0));
+        }
+    }
+
+    fn initial_key(&self) -> Option<MusicalKey> {
+        MusicalKey::parse(&self.get_tag_single(keys::INITIALKEY)?)
+    }
+
+    fn set_initial_key(&mut self, key: Option<MusicalKey>) {
+        self.clear_tag(keys::INITIALKEY);
+        if let Some(key) = key {
+            self.add_tag_single(keys::INITIALKEY, &key.to_tag_value());
+        }
+    }
+}
@@ -0,0 +1,168 @@
+// Track and disc numbers, including the non-standard "3/12" form
+
+use crate::keys;
+use crate::{CommentHeader, VorbisComments};
+
+/// A track or disc number with the optional total it is out of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Position {
+    pub number: u32,
+    pub total: Option<u32>,
+}
+
+impl Position {
+    /// Parse "3" or "3/12", allowing surrounding whitespace and zero padding.
+    pub fn parse(value: &str) -> Option<Position> {
+        let mut parts = value.splitn(2, '/');
+        let number = parts.next()?.trim().parse::<u32>().ok()?;
+        let total = match parts.next() {
+            Some(total) => Some(total.trim().parse::<u32>().ok()?),
+            None => None,
+        };
+        Some(Position { number, total })
+    }
+}
+
+/// The number in `number_key`, with the total from "n/total" or else from
+/// the first of `total_keys` holding a number.
+pub(crate) fn read_position(
+    header: &CommentHeader,
+    number_key: &str,
+    total_keys: &[&str],
+) -> Option<Position> {
+    let mut position = Position::parse(&header.get_tag_single(number_key)?)?;
+    if position.total.is_none() {
+        position.total = total_keys.iter().find_map(|total_key| {
+            header
+                .get_tag_single(total_key)
+                .and_then(|total| total.trim().parse::<u32>().ok())
+        });
+    }
+    Some(position)
+}
+
+/// The first value of `preferred`, or else of `other`, written to
+/// `preferred` alone.
+fn reconcile_total(header: &mut CommentHeader, preferred: &str, other: &str) {
+    let total = header
+        .get_tag_single(preferred)
+        .or_else(|| header.get_tag_single(other));
+    header.clear_tag(other);
+    if let Some(total) = total {
+        header.set_tag_single(preferred, &total);
+    }
+}
+
+/// The two spellings used for track and disc totals.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TotalConvention {
+    /// TRACKTOTAL and DISCTOTAL, as most taggers write them.
+    #[default]
+    TrackTotal,
+    /// TOTALTRACKS and TOTALDISCS.
+    TotalTracks,
+}
+
+impl TotalConvention {
+    /// The track and disc total tags of the convention.
+    pub fn keys(&self) -> (&'static str, &'static str) {
+        match self {
+            TotalConvention::TrackTotal => (keys::TRACKTOTAL, keys::DISCTOTAL),
+            TotalConvention::TotalTracks => (keys::TOTALTRACKS, keys::TOTALDISCS),
+        }
+    }
+}
+
+/// Write the number to `number_key` and the total to the first of
+/// `total_keys`, removing the values of all of them first so no stale total
+/// is read back.
+pub(crate) fn write_position(
+    header: &mut CommentHeader,
+    number_key: &str,
+    total_keys: &[&str],
+    position: Option<Position>,
+) {
+    write_position_padded(header, number_key, total_keys, position, 0);
+}
+
+/// `write_position` with the number zero-padded to `width` digits.
+pub(crate) fn write_position_padded(
+    header: &mut CommentHeader,
+    number_key: &str,
+    total_keys: &[&str],
+    position: Option<Position>,
+    width: usize,
+) {
+    header.clear_tag(number_key);
+    for total_key in total_keys {
+        header.clear_tag(total_key);
+    }
+    if let Some(position) = position {
+        let number = format!("{:0width$}", position.number, width = width);
+        header.add_tag_single(number_key, &number);
+        if let Some(total) = position.total {
+            header.add_tag_single(total_keys[0], &total.to_string());
+        }
+    }
+}
+
+pub trait VorbisNumbering {
+    /// TRACKNUMBER, with the total taken from "n/total", TRACKTOTAL or
+    /// TOTALTRACKS.
+    fn track(&self) -> Option<Position>;
+    /// Write TRACKNUMBER and TRACKTOTAL as separate plain numbers, or remove
+    /// both when `None`. TOTALTRACKS is removed either way.
+    fn set_track(&mut self, position: Option<Position>);
+    /// DISCNUMBER, with the total taken from "n/total", DISCTOTAL or
+    /// TOTALDISCS.
+    fn disc(&self) -> Option<Position>;
+    /// Write DISCNUMBER and DISCTOTAL as separate plain numbers, or remove
+    /// both when `None`. TOTALDISCS is removed either way.
+    fn set_disc(&mut self, position: Option<Position>);
+    /// Keep a single track total and a single disc total, in the tags of
+    /// `convention`. The value already in those tags wins over the one in
+    /// the tags of the other convention.
+    fn normalize_totals(&mut self, convention: TotalConvention);
+}
+
+impl VorbisNumbering for CommentHeader {
+    fn track(&self) -> Option<Position> {
+        read_position(
+            self,
+            keys::TRACKNUMBER,
+            &[keys::TRACKTOTAL, keys::TOTALTRACKS],
+        )
+    }
+
+    fn set_track(&mut self, position: Option<Position>) {
+        write_position(
+            self,
+            keys::TRACKNUMBER,
+            &[keys::TRACKTOTAL, keys::TOTALTRACKS],
+            position,
+        );
+    }
+
+    fn disc(&self) -> Option<Position> {
+        read_position(self, keys::DISCNUMBER, &[keys::DISCTOTAL, keys::TOTALDISCS])
+    }
+
+    fn set_disc(&mut self, position: Option<Position>) {
+        write_position(
+            self,
+            keys::DISCNUMBER,
+            &[keys::DISCTOTAL, keys::TOTALDISCS],
+            position,
+        );
+    }
+
+    fn normalize_totals(&mut self, convention: TotalConvention) {
+        let (track_total, disc_total) = convention.keys();
+        let (other_track, other_disc) = match convention {
+            TotalConvention::TrackTotal => TotalConvention::TotalTracks.keys(),
+            TotalConvention::TotalTracks => TotalConvention::TrackTotal.keys(),
+        };
+        reconcile_total(self, track_total, other_track);
+        reconcile_total(self, disc_total, other_disc);
+    }
+}
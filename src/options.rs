@@ -0,0 +1,138 @@
+// Chained construction of the rewrite options, and running a rewrite with them
+
+use crate::{
+    safe_replace_comment_header_into_with_options, CancelToken, CasePolicy, CommentHeader,
+    GranuleMode, Pagination, ReplaceOptions, ReplaceReport, ShrinkStrategy,
+    VorbisReplaceCommentError,
+};
+use std::io::{Read, Seek, Write};
+use std::path::Path;
+
+/// Chained construction of `ReplaceOptions`, each method setting the field
+/// of the same name:
+/// `ReplaceOptions::builder().preserve_vendor(true).reserve_padding(1024).build()`.
+#[derive(Clone, Debug, Default)]
+pub struct ReplaceOptionsBuilder {
+    options: ReplaceOptions,
+}
+
+impl ReplaceOptionsBuilder {
+    pub fn new() -> ReplaceOptionsBuilder {
+        ReplaceOptionsBuilder::default()
+    }
+
+    pub fn mark_end_of_stream(mut self, mark: bool) -> ReplaceOptionsBuilder {
+        self.options.mark_end_of_stream = mark;
+        self
+    }
+
+    pub fn salvage_truncated_page(mut self, salvage: bool) -> ReplaceOptionsBuilder {
+        self.options.salvage_truncated_page = salvage;
+        self
+    }
+
+    pub fn check_conformance(mut self, check: bool) -> ReplaceOptionsBuilder {
+        self.options.check_conformance = check;
+        self
+    }
+
+    pub fn verify_audio(mut self, verify: bool) -> ReplaceOptionsBuilder {
+        self.options.verify_audio = verify;
+        self
+    }
+
+    pub fn shrink(mut self, strategy: ShrinkStrategy) -> ReplaceOptionsBuilder {
+        self.options.shrink = strategy;
+        self
+    }
+
+    pub fn case_policy(mut self, policy: CasePolicy) -> ReplaceOptionsBuilder {
+        self.options.case_policy = policy;
+        self
+    }
+
+    pub fn preserve_vendor(mut self, preserve: bool) -> ReplaceOptionsBuilder {
+        self.options.preserve_vendor = preserve;
+        self
+    }
+
+    pub fn anonymize_encoder(mut self, anonymize: bool) -> ReplaceOptionsBuilder {
+        self.options.anonymize_encoder = anonymize;
+        self
+    }
+
+    pub fn reserve_padding(mut self, bytes: usize) -> ReplaceOptionsBuilder {
+        self.options.reserve_padding = bytes;
+        self
+    }
+
+    pub fn copy_pages(mut self, copy: bool) -> ReplaceOptionsBuilder {
+        self.options.copy_pages = copy;
+        self
+    }
+
+    pub fn raw_copy(mut self, copy: bool) -> ReplaceOptionsBuilder {
+        self.options.raw_copy = copy;
+        self
+    }
+
+    pub fn granule_mode(mut self, mode: GranuleMode) -> ReplaceOptionsBuilder {
+        self.options.granule_mode = mode;
+        self
+    }
+
+    pub fn pagination(mut self, pagination: Pagination) -> ReplaceOptionsBuilder {
+        self.options.pagination = pagination;
+        self
+    }
+
+    pub fn deterministic(mut self, deterministic: bool) -> ReplaceOptionsBuilder {
+        self.options.deterministic = deterministic;
+        self
+    }
+
+    /// Spill the output to a temporary file past `bytes`, see
+    /// `safe_replace_comment_header_spilled`.
+    pub fn spill_threshold(mut self, bytes: usize) -> ReplaceOptionsBuilder {
+        self.options.spill_threshold = Some(bytes);
+        self
+    }
+
+    pub fn spill_dir<P: AsRef<Path>>(mut self, dir: P) -> ReplaceOptionsBuilder {
+        self.options.spill_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn cancel(mut self, token: &CancelToken) -> ReplaceOptionsBuilder {
+        self.options.cancel = Some(token.clone());
+        self
+    }
+
+    #[cfg(feature = "nfc")]
+    pub fn normalize_nfc(mut self, normalize: bool) -> ReplaceOptionsBuilder {
+        self.options.normalize_nfc = normalize;
+        self
+    }
+
+    pub fn build(self) -> ReplaceOptions {
+        self.options
+    }
+}
+
+impl ReplaceOptions {
+    pub fn builder() -> ReplaceOptionsBuilder {
+        ReplaceOptionsBuilder::new()
+    }
+
+    /// Write `f_in` to `f_out` with `new_header` as its comment header,
+    /// configured by these options alone, as
+    /// `safe_replace_comment_header_into_with_options` does.
+    pub fn replace<T: Read + Seek, W: Write>(
+        &self,
+        f_in: T,
+        f_out: W,
+        new_header: CommentHeader,
+    ) -> Result<ReplaceReport, VorbisReplaceCommentError> {
+        safe_replace_comment_header_into_with_options(f_in, f_out, new_header, self)
+    }
+}
@@ -0,0 +1,318 @@
+// Raw Ogg pages, for checks and edits that need the exact page layout
+
+use crate::crc::crc32_update;
+use std::io::{self, Read};
+
+pub(crate) const CAPTURE_PATTERN: &[u8; 4] = b"OggS";
+pub(crate) const HEADER_SIZE: usize = 27;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Page {
+    pub flags: u8,
+    pub granule_position: u64,
+    pub serial: u32,
+    pub sequence: u32,
+    pub checksum: u32,
+    pub lacing: Vec<u8>,
+    pub body: Vec<u8>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PageError {
+    /// Not enough data for the page the header describes.
+    Truncated,
+    NoCapturePattern,
+    BadVersion(u8),
+}
+
+impl Page {
+    pub const CONTINUED: u8 = 0x01;
+    pub const FIRST: u8 = 0x02;
+    pub const LAST: u8 = 0x04;
+
+    pub fn is_first(&self) -> bool {
+        self.flags & Page::FIRST != 0
+    }
+
+    pub fn is_last(&self) -> bool {
+        self.flags & Page::LAST != 0
+    }
+
+    /// Parse the page at the start of `data`, returning it and its size.
+    pub fn parse(data: &[u8]) -> Result<(Page, usize), PageError> {
+        if data.len() < HEADER_SIZE {
+            return Err(PageError::Truncated);
+        }
+        if &data[0..4] != CAPTURE_PATTERN {
+            return Err(PageError::NoCapturePattern);
+        }
+        if data[4] != 0 {
+            return Err(PageError::BadVersion(data[4]));
+        }
+        let segments = data[26] as usize;
+        let lacing = data
+            .get(HEADER_SIZE..HEADER_SIZE + segments)
+            .ok_or(PageError::Truncated)?
+            .to_vec();
+        let body_start = HEADER_SIZE + segments;
+        let body_len: usize = lacing.iter().map(|l| *l as usize).sum();
+        let body = data
+            .get(body_start..body_start + body_len)
+            .ok_or(PageError::Truncated)?
+            .to_vec();
+        let u32_at = |pos: usize| {
+            u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]])
+        };
+        let mut granule = [0u8; 8];
+        granule.copy_from_slice(&data[6..14]);
+        let page = Page {
+            flags: data[5],
+            granule_position: u64::from_le_bytes(granule),
+            serial: u32_at(14),
+            sequence: u32_at(18),
+            checksum: u32_at(22),
+            lacing,
+            body,
+        };
+        Ok((page, body_start + body_len))
+    }
+
+    fn header_bytes(&self, checksum: u32) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + self.lacing.len());
+        bytes.extend(CAPTURE_PATTERN.iter());
+        bytes.push(0);
+        bytes.push(self.flags);
+        bytes.extend(self.granule_position.to_le_bytes().iter());
+        bytes.extend(self.serial.to_le_bytes().iter());
+        bytes.extend(self.sequence.to_le_bytes().iter());
+        bytes.extend(checksum.to_le_bytes().iter());
+        bytes.push(self.lacing.len() as u8);
+        bytes.extend(self.lacing.iter());
+        bytes
+    }
+
+    pub fn size(&self) -> usize {
+        HEADER_SIZE + self.lacing.len() + self.body.len()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.header_bytes(self.checksum);
+        bytes.extend(self.body.iter());
+        bytes
+    }
+
+    /// The checksum the page should have, computed with the checksum field zeroed.
+    pub fn compute_checksum(&self) -> u32 {
+        let crc = crc32_update(0, &self.header_bytes(0));
+        crc32_update(crc, &self.body)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum PageReadError {
+    Io(io::Error),
+    Page(PageError),
+}
+
+impl From<io::Error> for PageReadError {
+    fn from(err: io::Error) -> PageReadError {
+        match err.kind() {
+            io::ErrorKind::UnexpectedEof => PageReadError::Page(PageError::Truncated),
+            _ => PageReadError::Io(err),
+        }
+    }
+}
+
+/// Read the page at the current position of `f`, `None` at the end of the
+/// file. With `scan`, bytes before the next capture pattern are skipped,
+/// returning the number skipped along with the page.
+pub(crate) fn read_page<R: Read>(
+    f: &mut R,
+    scan: bool,
+) -> Result<Option<(Page, u64)>, PageReadError> {
+    let mut data = vec![0; HEADER_SIZE];
+    if f.read(&mut data[..1])? == 0 {
+        return Ok(None);
+    }
+    f.read_exact(&mut data[1..4])?;
+    let mut skipped = 0;
+    while &data[0..4] != CAPTURE_PATTERN {
+        if !scan {
+            return Err(PageReadError::Page(PageError::NoCapturePattern));
+        }
+        data.copy_within(1..4, 0);
+        if f.read(&mut data[3..4])? == 0 {
+            return Err(PageReadError::Page(PageError::NoCapturePattern));
+        }
+        skipped += 1;
+    }
+    f.read_exact(&mut data[4..])?;
+    let segments = data[HEADER_SIZE - 1] as usize;
+    data.resize(HEADER_SIZE + segments, 0);
+    f.read_exact(&mut data[HEADER_SIZE..])?;
+    let body_len: usize = data[HEADER_SIZE..].iter().map(|l| *l as usize).sum();
+    data.resize(HEADER_SIZE + segments + body_len, 0);
+    f.read_exact(&mut data[HEADER_SIZE + segments..])?;
+    match Page::parse(&data) {
+        Ok((page, _)) => Ok(Some((page, skipped))),
+        Err(err) => Err(PageReadError::Page(err)),
+    }
+}
+
+/// Pages parsed from a buffer, each with its byte offset.
+pub(crate) struct ParsedPages {
+    pub pages: Vec<(usize, Page)>,
+    /// The first invalid page and its offset, parsing stops there.
+    pub error: Option<(usize, PageError)>,
+}
+
+/// Parse consecutive pages from the start of `data`.
+pub(crate) fn parse_pages(data: &[u8]) -> ParsedPages {
+    let mut pages = vec![];
+    let mut offset = 0;
+    while offset < data.len() {
+        match Page::parse(&data[offset..]) {
+            Ok((page, size)) => {
+                pages.push((offset, page));
+                offset += size;
+            }
+            Err(err) => {
+                return ParsedPages {
+                    pages,
+                    error: Some((offset, err)),
+                }
+            }
+        }
+    }
+    ParsedPages { pages, error: None }
+}
+
+/// The packets of a page cut short that are complete, with its serial and
+/// sequence number.
+pub(crate) struct SalvagedPackets {
+    pub serial: u32,
+    pub sequence: u32,
+    pub packets: Vec<Vec<u8>>,
+}
+
+/// The packets started and ended within what `data` has of a page. A
+/// packet continued from the previous page is left out.
+pub(crate) fn salvage_packets(data: &[u8]) -> Option<SalvagedPackets> {
+    if data.len() < HEADER_SIZE || &data[0..4] != CAPTURE_PATTERN || data[4] != 0 {
+        return None;
+    }
+    let u32_at =
+        |pos: usize| u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+    let segments = data[HEADER_SIZE - 1] as usize;
+    let lacing = &data[HEADER_SIZE..data.len().min(HEADER_SIZE + segments)];
+    let body = data.get(HEADER_SIZE + segments..).unwrap_or(&[]);
+    let mut packets = vec![];
+    let mut packet = vec![];
+    let mut continued = data[5] & Page::CONTINUED != 0;
+    let mut pos = 0;
+    for lacing in lacing.iter() {
+        let len = *lacing as usize;
+        let segment = match body.get(pos..pos + len) {
+            Some(segment) => segment,
+            None => break,
+        };
+        pos += len;
+        if !continued {
+            packet.extend(segment);
+        }
+        if len < 255 {
+            if !continued {
+                packets.push(std::mem::take(&mut packet));
+            }
+            continued = false;
+        }
+    }
+    Some(SalvagedPackets {
+        serial: u32_at(14),
+        sequence: u32_at(18),
+        packets,
+    })
+}
+
+/// A packet assembled from pages, with the index of the page it ends on.
+pub(crate) struct PagePacket {
+    pub data: Vec<u8>,
+    pub first_page: usize,
+    pub last_page: usize,
+}
+
+/// Split the pages of one logical stream into packets. A packet still
+/// unfinished at the last page is dropped.
+pub(crate) fn pages_to_packets<'a, I: Iterator<Item = &'a Page>>(pages: I) -> Vec<PagePacket> {
+    let mut packets = vec![];
+    let mut current: Option<PagePacket> = None;
+    for (index, page) in pages.enumerate() {
+        let mut pos = 0;
+        for lacing in page.lacing.iter() {
+            let len = *lacing as usize;
+            let packet = current.get_or_insert_with(|| PagePacket {
+                data: vec![],
+                first_page: index,
+                last_page: index,
+            });
+            packet.data.extend(&page.body[pos..pos + len]);
+            pos += len;
+            if len < 255 {
+                let mut packet = current.take().unwrap();
+                packet.last_page = index;
+                packets.push(packet);
+            }
+        }
+    }
+    packets
+}
+
+/// Lay packets out on pages of one stream, starting a new page for the first
+/// packet and ending the last page with the last packet. Pages where no
+/// packet ends get granule position -1, the others `granule_position`.
+pub(crate) fn paginate(
+    packets: &[&[u8]],
+    serial: u32,
+    first_sequence: u32,
+    granule_position: u64,
+) -> Vec<Page> {
+    let mut segments: Vec<(u8, &[u8], bool)> = vec![];
+    for packet in packets.iter() {
+        let mut chunks = packet.chunks(255).collect::<Vec<&[u8]>>();
+        if packet.len() % 255 == 0 {
+            chunks.push(&[]);
+        }
+        let count = chunks.len();
+        for (index, chunk) in chunks.into_iter().enumerate() {
+            segments.push((chunk.len() as u8, chunk, index + 1 == count));
+        }
+    }
+    let mut pages = vec![];
+    let mut continued = false;
+    for (index, page_segments) in segments.chunks(255).enumerate() {
+        let ends_packet = page_segments.iter().any(|segment| segment.2);
+        let mut page = Page {
+            flags: if continued { Page::CONTINUED } else { 0 },
+            granule_position: if ends_packet {
+                granule_position
+            } else {
+                u64::MAX
+            },
+            serial,
+            sequence: first_sequence.wrapping_add(index as u32),
+            checksum: 0,
+            lacing: page_segments.iter().map(|segment| segment.0).collect(),
+            body: page_segments
+                .iter()
+                .flat_map(|segment| segment.1.iter().cloned())
+                .collect(),
+        };
+        page.checksum = page.compute_checksum();
+        continued = !page_segments
+            .last()
+            .map(|segment| segment.2)
+            .unwrap_or(true);
+        pages.push(page);
+    }
+    pages
+}
@@ -0,0 +1,80 @@
+// Where the pages end when the packets are written again
+
+/// How the audio packets are laid out on pages when they are written again,
+/// for players that expect pages of about the size encoders make. Pages
+/// copied as they are with `ReplaceOptions::copy_pages` keep their layout.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pagination {
+    /// End a page once the packets ending on it add up to at least this
+    /// many bytes, instead of where the input ended its pages. libvorbis
+    /// aims for about 4096.
+    pub target_page_size: Option<usize>,
+    /// End a page once this many packets ended on it.
+    pub max_packets_per_page: Option<usize>,
+    /// End a page after the setup header, so the first audio packet starts
+    /// a page even if it didn't in the input.
+    pub flush_after_headers: bool,
+    /// End a page after the identification header as well as after the
+    /// setup header, so the first page holds the identification header
+    /// alone and the comment and setup headers end a page before the audio,
+    /// the layout players expect. This repairs inputs laid out otherwise,
+    /// which `copy_pages` refuses with `HeadersNotPageAligned`.
+    pub headers_on_own_pages: bool,
+}
+
+/// Follows the pages being filled, packet by packet.
+pub(crate) struct PageLayout<'a> {
+    pagination: &'a Pagination,
+    packets: u64,
+    page_bytes: usize,
+    page_packets: usize,
+    page_segments: usize,
+}
+
+impl<'a> PageLayout<'a> {
+    pub(crate) fn new(pagination: &'a Pagination) -> PageLayout<'a> {
+        PageLayout {
+            pagination,
+            packets: 0,
+            page_bytes: 0,
+            page_packets: 0,
+            page_segments: 0,
+        }
+    }
+
+    /// Whether to end the page after the next packet of the stream, of `len`
+    /// bytes, when the input ended its page there or not.
+    pub(crate) fn ends_page(&mut self, len: usize, input_ends_page: bool) -> bool {
+        self.packets += 1;
+        if self.packets <= 3 {
+            let flush = match self.packets {
+                1 => self.pagination.headers_on_own_pages,
+                3 => self.pagination.flush_after_headers || self.pagination.headers_on_own_pages,
+                _ => false,
+            };
+            return input_ends_page || flush;
+        }
+        // The writer ends pages itself when their 255 segments are used up
+        self.page_segments += len / 255 + 1;
+        if self.page_segments >= 255 {
+            self.page_segments %= 255;
+            self.page_bytes = 0;
+            self.page_packets = 0;
+        }
+        self.page_bytes += len;
+        self.page_packets += 1;
+        let ends = match self.pagination.target_page_size {
+            Some(target) => self.page_bytes >= target,
+            None => input_ends_page,
+        } || self
+            .pagination
+            .max_packets_per_page
+            .is_some_and(|max| self.page_packets >= max);
+        if ends {
+            self.page_bytes = 0;
+            self.page_packets = 0;
+            self.page_segments = 0;
+        }
+        ends
+    }
+}
@@ -0,0 +1,383 @@
+// Comment header parsing, following the Vorbis I spec section 5
+
+use crate::encoding::LegacyEncoding;
+use crate::store::TagStore;
+use crate::{CommentHeader, ParseLimits, VorbisReadCommentError};
+use lewton::header::HeaderReadError;
+use std::borrow::Cow;
+
+/// Cursor over a packet, running out of data is `EndOfPacket`.
+struct PacketCursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> PacketCursor<'a> {
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], HeaderReadError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .ok_or(HeaderReadError::EndOfPacket)?;
+        let bytes = self
+            .data
+            .get(self.pos..end)
+            .ok_or(HeaderReadError::EndOfPacket)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, HeaderReadError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u32(&mut self) -> Result<u32, HeaderReadError> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Check the packet type and signature, returning the vendor string.
+    fn read_preamble(&mut self) -> Result<&'a str, HeaderReadError> {
+        std::str::from_utf8(self.read_preamble_bytes()?)
+            .map_err(|_| HeaderReadError::Utf8DecodeError)
+    }
+
+    /// `read_preamble`, with the vendor string left undecoded.
+    fn read_preamble_bytes(&mut self) -> Result<&'a [u8], HeaderReadError> {
+        let packet_type = self.read_u8()?;
+        if packet_type & 1 == 0 {
+            return Err(HeaderReadError::HeaderIsAudio);
+        }
+        if self.read_bytes(6)? != b"vorbis" {
+            return Err(HeaderReadError::NotVorbisHeader);
+        }
+        if packet_type != 3 {
+            return Err(HeaderReadError::HeaderBadType(packet_type));
+        }
+        let vendor_len = self.read_u32()? as usize;
+        self.read_bytes(vendor_len)
+    }
+}
+
+/// What decoding a header with `CommentHeaderRef::parse_lossy` or
+/// `parse_with_fallback` found that wasn't valid UTF-8.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DecodeReport {
+    /// The vendor string wasn't valid UTF-8.
+    pub lossy_vendor: bool,
+    /// The keys of the comments that weren't valid UTF-8, as decoded, in
+    /// header order.
+    pub lossy_tags: Vec<String>,
+}
+
+/// Decode `bytes` as UTF-8, or else with `fallback` if given or with
+/// replacement characters, telling whether it wasn't valid UTF-8.
+fn decode_text(bytes: &[u8], fallback: Option<LegacyEncoding>) -> (Cow<'_, str>, bool) {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => (Cow::Borrowed(text), false),
+        Err(_) => match fallback {
+            Some(encoding) => (Cow::Owned(encoding.decode(bytes)), true),
+            None => (
+                Cow::Owned(String::from_utf8_lossy(bytes).into_owned()),
+                true,
+            ),
+        },
+    }
+}
+
+/// Whether two tag names match ignoring case, as the `TagStore` index does.
+fn same_name(a: &str, b: &str) -> bool {
+    if a.is_ascii() && b.is_ascii() {
+        a.eq_ignore_ascii_case(b)
+    } else {
+        a.to_lowercase() == b.to_lowercase()
+    }
+}
+
+/// A comment header borrowing its strings from the packet it was parsed
+/// from, for reading a few tags without allocating a `String` per comment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommentHeaderRef<'a> {
+    vendor: Cow<'a, str>,
+    comments: Vec<(Cow<'a, str>, Cow<'a, str>)>,
+}
+
+impl<'a> CommentHeaderRef<'a> {
+    /// Parse a comment header packet, as read by `safe_read_comment_packet`.
+    ///
+    /// Like lewton, comments that aren't valid UTF-8 or have no '=' are
+    /// skipped, while an invalid vendor string or a missing framing bit is an
+    /// error.
+    pub fn parse(packet: &'a [u8]) -> Result<CommentHeaderRef<'a>, HeaderReadError> {
+        let mut rdr = PacketCursor {
+            data: packet,
+            pos: 0,
+        };
+        let vendor = rdr.read_preamble()?;
+        let comment_count = rdr.read_u32()?;
+        let mut comments = vec![];
+        for _ in 0..comment_count {
+            let comment_len = rdr.read_u32()? as usize;
+            let comment = match std::str::from_utf8(rdr.read_bytes(comment_len)?) {
+                Ok(comment) => comment,
+                Err(_) => continue,
+            };
+            if let Some((key, value)) = comment.split_once('=') {
+                comments.push((Cow::Borrowed(key), Cow::Borrowed(value)));
+            }
+        }
+        if rdr.read_u8()? != 1 {
+            return Err(HeaderReadError::HeaderBadFormat);
+        }
+        Ok(CommentHeaderRef {
+            vendor: Cow::Borrowed(vendor),
+            comments,
+        })
+    }
+
+    /// `parse` for damaged files: the vendor string and comments that aren't
+    /// valid UTF-8 are decoded with U+FFFD replacement characters instead of
+    /// failing or being skipped, and listed in the report. Comments
+    /// without '=' are still skipped.
+    pub fn parse_lossy(
+        packet: &'a [u8],
+    ) -> Result<(CommentHeaderRef<'a>, DecodeReport), HeaderReadError> {
+        CommentHeaderRef::parse_recovering(packet, None)
+    }
+
+    /// `parse_lossy`, but text that isn't valid UTF-8 is decoded with
+    /// `encoding`, as written by old non-compliant taggers. The report lists
+    /// what was decoded that way.
+    pub fn parse_with_fallback(
+        packet: &'a [u8],
+        encoding: LegacyEncoding,
+    ) -> Result<(CommentHeaderRef<'a>, DecodeReport), HeaderReadError> {
+        CommentHeaderRef::parse_recovering(packet, Some(encoding))
+    }
+
+    fn parse_recovering(
+        packet: &'a [u8],
+        fallback: Option<LegacyEncoding>,
+    ) -> Result<(CommentHeaderRef<'a>, DecodeReport), HeaderReadError> {
+        let mut rdr = PacketCursor {
+            data: packet,
+            pos: 0,
+        };
+        let mut report = DecodeReport::default();
+        let (vendor, lossy) = decode_text(rdr.read_preamble_bytes()?, fallback);
+        report.lossy_vendor = lossy;
+        let comment_count = rdr.read_u32()?;
+        let mut comments = vec![];
+        for _ in 0..comment_count {
+            let comment_len = rdr.read_u32()? as usize;
+            let comment = rdr.read_bytes(comment_len)?;
+            let key_len = match comment.iter().position(|byte| *byte == b'=') {
+                Some(key_len) => key_len,
+                None => continue,
+            };
+            let (key, lossy_key) = decode_text(&comment[..key_len], fallback);
+            let (value, lossy_value) = decode_text(&comment[key_len + 1..], fallback);
+            if lossy_key || lossy_value {
+                report.lossy_tags.push(key.to_string());
+            }
+            comments.push((key, value));
+        }
+        if rdr.read_u8()? != 1 {
+            return Err(HeaderReadError::HeaderBadFormat);
+        }
+        Ok((CommentHeaderRef { vendor, comments }, report))
+    }
+
+    /// `parse`, checking the packet against `limits` first.
+    pub fn parse_with_limits(
+        packet: &'a [u8],
+        limits: &ParseLimits,
+    ) -> Result<CommentHeaderRef<'a>, VorbisReadCommentError> {
+        limits.check_packet(packet)?;
+        Ok(CommentHeaderRef::parse(packet)?)
+    }
+
+    pub fn vendor(&self) -> &str {
+        &self.vendor
+    }
+
+    /// Number of comments.
+    pub fn len(&self) -> usize {
+        self.comments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.comments.is_empty()
+    }
+
+    /// The comments as (key, value) pairs, in order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&str, &str)> {
+        self.comments
+            .iter()
+            .map(|(key, value)| (key.as_ref(), value.as_ref()))
+    }
+
+    /// The values of the tag, whose name is matched ignoring case.
+    pub fn get_all<'s, K: AsRef<str> + 's>(&'s self, tag: K) -> impl Iterator<Item = &'s str> {
+        self.iter()
+            .filter(move |(key, _)| same_name(key, tag.as_ref()))
+            .map(|(_, value)| value)
+    }
+
+    /// The first value of the tag.
+    pub fn get<K: AsRef<str>>(&self, tag: K) -> Option<&str> {
+        let tag = tag.as_ref();
+        self.iter()
+            .find(|(key, _)| same_name(key, tag))
+            .map(|(_, value)| value)
+    }
+
+    /// An owned copy, for editing.
+    pub fn to_owned(&self) -> CommentHeader {
+        let mut comments = TagStore::default();
+        for (key, value) in self.iter() {
+            comments.push(key.to_string(), value.to_string());
+        }
+        CommentHeader {
+            vendor: self.vendor.to_string(),
+            comments,
+        }
+    }
+}
+
+/// Where a comment sits in the packet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct CommentSpan {
+    start: usize,
+    key_len: usize,
+    len: usize,
+}
+
+/// A comment header whose values are only checked and decoded when looked
+/// up. Parsing just records where each comment is and reads its key, so a
+/// large picture or lyrics tag costs nothing unless it is asked for.
+///
+/// A comment whose value isn't valid UTF-8 is still listed by `keys`, but
+/// skipped by `get` and `get_all`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LazyCommentHeader<'a> {
+    packet: &'a [u8],
+    vendor: &'a str,
+    comments: Vec<CommentSpan>,
+}
+
+impl<'a> LazyCommentHeader<'a> {
+    /// Parse a comment header packet, with the same checks as
+    /// `CommentHeaderRef::parse` apart from the values.
+    pub fn parse(packet: &'a [u8]) -> Result<LazyCommentHeader<'a>, HeaderReadError> {
+        let mut rdr = PacketCursor {
+            data: packet,
+            pos: 0,
+        };
+        let vendor = rdr.read_preamble()?;
+        let comment_count = rdr.read_u32()?;
+        let mut comments = vec![];
+        for _ in 0..comment_count {
+            let len = rdr.read_u32()? as usize;
+            let start = rdr.pos;
+            let comment = rdr.read_bytes(len)?;
+            let key_len = match comment.iter().position(|byte| *byte == b'=') {
+                Some(key_len) => key_len,
+                None => continue,
+            };
+            if std::str::from_utf8(&comment[..key_len]).is_ok() {
+                comments.push(CommentSpan {
+                    start,
+                    key_len,
+                    len,
+                });
+            }
+        }
+        if rdr.read_u8()? != 1 {
+            return Err(HeaderReadError::HeaderBadFormat);
+        }
+        Ok(LazyCommentHeader {
+            packet,
+            vendor,
+            comments,
+        })
+    }
+
+    /// `parse`, checking the packet against `limits` first.
+    pub fn parse_with_limits(
+        packet: &'a [u8],
+        limits: &ParseLimits,
+    ) -> Result<LazyCommentHeader<'a>, VorbisReadCommentError> {
+        limits.check_packet(packet)?;
+        Ok(LazyCommentHeader::parse(packet)?)
+    }
+
+    pub fn vendor(&self) -> &'a str {
+        self.vendor
+    }
+
+    /// Number of comments.
+    pub fn len(&self) -> usize {
+        self.comments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.comments.is_empty()
+    }
+
+    fn key(&self, span: &CommentSpan) -> &'a str {
+        // Checked when parsed
+        std::str::from_utf8(&self.packet[span.start..span.start + span.key_len]).unwrap()
+    }
+
+    fn value(&self, span: &CommentSpan) -> Option<&'a str> {
+        let value = &self.packet[span.start + span.key_len + 1..span.start + span.len];
+        std::str::from_utf8(value).ok()
+    }
+
+    /// The key of every comment, in order.
+    pub fn keys(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.comments.iter().map(|span| self.key(span))
+    }
+
+    /// The values of the tag, decoded now.
+    pub fn get_all<K: AsRef<str>>(&self, tag: K) -> Vec<&'a str> {
+        let tag = tag.as_ref();
+        self.comments
+            .iter()
+            .filter(|span| same_name(self.key(span), tag))
+            .filter_map(|span| self.value(span))
+            .collect()
+    }
+
+    /// The first value of the tag, decoded now.
+    pub fn get<K: AsRef<str>>(&self, tag: K) -> Option<&'a str> {
+        let tag = tag.as_ref();
+        self.comments
+            .iter()
+            .filter(|span| same_name(self.key(span), tag))
+            .find_map(|span| self.value(span))
+    }
+
+    /// Decode every value.
+    pub fn decode(&self) -> CommentHeaderRef<'a> {
+        let comments = self
+            .comments
+            .iter()
+            .filter_map(|span| {
+                Some((
+                    Cow::Borrowed(self.key(span)),
+                    Cow::Borrowed(self.value(span)?),
+                ))
+            })
+            .collect();
+        CommentHeaderRef {
+            vendor: Cow::Borrowed(self.vendor),
+            comments,
+        }
+    }
+}
+
+/// Parse a comment header packet into an owned header.
+pub(crate) fn parse_comment_header(packet: &[u8]) -> Result<CommentHeader, HeaderReadError> {
+    Ok(CommentHeaderRef::parse(packet)?.to_owned())
+}
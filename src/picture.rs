@@ -0,0 +1,462 @@
+// Embedded pictures, stored as base64 FLAC picture blocks in METADATA_BLOCK_PICTURE
+
+use crate::base64;
+use crate::{CommentHeader, VorbisComments};
+use std::convert::TryInto;
+
+use thiserror::Error;
+
+/// Tag holding an embedded picture.
+pub const PICTURE_TAG: &str = "metadata_block_picture";
+
+#[derive(Error, Debug)]
+pub enum VorbisPictureError {
+    #[error("invalid base64 in picture tag")]
+    InvalidBase64,
+    #[error("picture block is truncated")]
+    TruncatedBlock,
+    #[error("picture text is not valid utf-8")]
+    InvalidText,
+    #[error("unrecognized image format")]
+    UnknownImageFormat,
+    /// The MIME type, description or data is over the 4 GiB a length field
+    /// of the block can tell.
+    #[error("picture field of {0} bytes does not fit in a picture block")]
+    FieldTooLong(usize),
+}
+
+/// Picture type, as defined by the FLAC picture block (and ID3v2 APIC).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PictureType {
+    Other,
+    FileIcon,
+    OtherFileIcon,
+    FrontCover,
+    BackCover,
+    LeafletPage,
+    Media,
+    LeadArtist,
+    Artist,
+    Conductor,
+    Band,
+    Composer,
+    Lyricist,
+    RecordingLocation,
+    DuringRecording,
+    DuringPerformance,
+    ScreenCapture,
+    BrightFish,
+    Illustration,
+    BandLogo,
+    PublisherLogo,
+    /// A code the FLAC picture block doesn't define, kept so it is written
+    /// back as it was read.
+    Unknown(u32),
+}
+
+const PICTURE_TYPES: [PictureType; 21] = [
+    PictureType::Other,
+    PictureType::FileIcon,
+    PictureType::OtherFileIcon,
+    PictureType::FrontCover,
+    PictureType::BackCover,
+    PictureType::LeafletPage,
+    PictureType::Media,
+    PictureType::LeadArtist,
+    PictureType::Artist,
+    PictureType::Conductor,
+    PictureType::Band,
+    PictureType::Composer,
+    PictureType::Lyricist,
+    PictureType::RecordingLocation,
+    PictureType::DuringRecording,
+    PictureType::DuringPerformance,
+    PictureType::ScreenCapture,
+    PictureType::BrightFish,
+    PictureType::Illustration,
+    PictureType::BandLogo,
+    PictureType::PublisherLogo,
+];
+
+impl PictureType {
+    /// Values the block doesn't define map to `Unknown`.
+    pub fn from_u32(value: u32) -> PictureType {
+        PICTURE_TYPES
+            .get(value as usize)
+            .copied()
+            .unwrap_or(PictureType::Unknown(value))
+    }
+
+    pub fn to_u32(self) -> u32 {
+        match self {
+            PictureType::Unknown(value) => value,
+            known => PICTURE_TYPES.iter().position(|t| *t == known).unwrap_or(0) as u32,
+        }
+    }
+}
+
+/// Format and dimensions sniffed from encoded image data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImageInfo {
+    pub mime_type: &'static str,
+    pub width: u32,
+    pub height: u32,
+    /// Bits per pixel.
+    pub depth: u32,
+    /// Number of colors for indexed images, 0 otherwise.
+    pub colors: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Picture {
+    pub picture_type: PictureType,
+    pub mime_type: String,
+    pub description: String,
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+    pub colors: u32,
+    pub data: Vec<u8>,
+}
+
+fn be_u16(data: &[u8], pos: usize) -> Option<u32> {
+    let bytes = data.get(pos..pos + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]) as u32)
+}
+
+fn be_u32(data: &[u8], pos: usize) -> Option<u32> {
+    let bytes = data.get(pos..pos + 4)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+fn le_u16(data: &[u8], pos: usize) -> Option<u32> {
+    let bytes = data.get(pos..pos + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]) as u32)
+}
+
+fn le_u24(data: &[u8], pos: usize) -> Option<u32> {
+    let bytes = data.get(pos..pos + 3)?;
+    Some(bytes[0] as u32 | (bytes[1] as u32) << 8 | (bytes[2] as u32) << 16)
+}
+
+fn le_u32(data: &[u8], pos: usize) -> Option<u32> {
+    let bytes = data.get(pos..pos + 4)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn probe_png(data: &[u8]) -> Option<ImageInfo> {
+    if data.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    let bit_depth = *data.get(24)? as u32;
+    let color_type = *data.get(25)?;
+    let channels = match color_type {
+        0 | 3 => 1,
+        2 => 3,
+        4 => 2,
+        6 => 4,
+        _ => return None,
+    };
+    let mut colors = 0;
+    if color_type == 3 {
+        // Indexed, count the palette entries
+        let mut pos = 8;
+        while let Some(len) = be_u32(data, pos) {
+            let kind = data.get(pos + 4..pos + 8)?;
+            if kind == b"PLTE" {
+                colors = len / 3;
+                break;
+            }
+            if kind == b"IDAT" || kind == b"IEND" {
+                break;
+            }
+            pos = pos.checked_add(12)?.checked_add(len as usize)?;
+        }
+    }
+    Some(ImageInfo {
+        mime_type: "image/png",
+        width: be_u32(data, 16)?,
+        height: be_u32(data, 20)?,
+        depth: bit_depth * channels,
+        colors,
+    })
+}
+
+fn probe_jpeg(data: &[u8]) -> Option<ImageInfo> {
+    let mut pos = 2;
+    loop {
+        if *data.get(pos)? != 0xff {
+            return None;
+        }
+        let marker = *data.get(pos + 1)?;
+        match marker {
+            // Fill byte
+            0xff => {
+                pos += 1;
+                continue;
+            }
+            // Markers without a length
+            0x01 | 0xd0..=0xd7 => {
+                pos += 2;
+                continue;
+            }
+            // Start of frame, except DHT, JPG and DAC
+            0xc0..=0xcf if marker != 0xc4 && marker != 0xc8 && marker != 0xcc => {
+                let precision = *data.get(pos + 4)? as u32;
+                let components = *data.get(pos + 9)? as u32;
+                return Some(ImageInfo {
+                    mime_type: "image/jpeg",
+                    width: be_u16(data, pos + 7)?,
+                    height: be_u16(data, pos + 5)?,
+                    depth: precision * components,
+                    colors: 0,
+                });
+            }
+            // Start of scan or end of image without a frame header
+            0xda | 0xd9 => return None,
+            _ => pos += 2 + be_u16(data, pos + 2)? as usize,
+        }
+    }
+}
+
+fn probe_gif(data: &[u8]) -> Option<ImageInfo> {
+    let packed = *data.get(10)?;
+    // Colors are only known from a global color table
+    let colors = if packed & 0x80 != 0 {
+        1 << ((packed & 0x07) + 1)
+    } else {
+        0
+    };
+    Some(ImageInfo {
+        mime_type: "image/gif",
+        width: le_u16(data, 6)?,
+        height: le_u16(data, 8)?,
+        // Palette entries are 24 bit RGB
+        depth: 24,
+        colors,
+    })
+}
+
+fn probe_webp(data: &[u8]) -> Option<ImageInfo> {
+    let chunk = data.get(12..16)?;
+    let body = 20;
+    let (width, height, alpha) = match chunk {
+        b"VP8 " => {
+            if data.get(body + 3..body + 6)? != [0x9d, 0x01, 0x2a] {
+                return None;
+            }
+            (
+                le_u16(data, body + 6)? & 0x3fff,
+                le_u16(data, body + 8)? & 0x3fff,
+                false,
+            )
+        }
+        b"VP8L" => {
+            if *data.get(body)? != 0x2f {
+                return None;
+            }
+            let bits = le_u32(data, body + 1)?;
+            (
+                (bits & 0x3fff) + 1,
+                ((bits >> 14) & 0x3fff) + 1,
+                (bits >> 28) & 1 != 0,
+            )
+        }
+        b"VP8X" => (
+            le_u24(data, body + 4)? + 1,
+            le_u24(data, body + 7)? + 1,
+            *data.get(body)? & 0x10 != 0,
+        ),
+        _ => return None,
+    };
+    Some(ImageInfo {
+        mime_type: "image/webp",
+        width,
+        height,
+        depth: if alpha { 32 } else { 24 },
+        colors: 0,
+    })
+}
+
+/// Sniff the format and dimensions of PNG, JPEG, GIF and WebP images.
+pub fn probe_image(data: &[u8]) -> Option<ImageInfo> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        probe_png(data)
+    } else if data.starts_with(&[0xff, 0xd8]) {
+        probe_jpeg(data)
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        probe_gif(data)
+    } else if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+        probe_webp(data)
+    } else {
+        None
+    }
+}
+
+impl Picture {
+    /// Make a picture with the given MIME type, leaving the dimensions unset.
+    pub fn new(picture_type: PictureType, mime_type: &str, data: Vec<u8>) -> Picture {
+        Picture {
+            picture_type,
+            mime_type: mime_type.to_string(),
+            description: "".to_string(),
+            width: 0,
+            height: 0,
+            depth: 0,
+            colors: 0,
+            data,
+        }
+    }
+
+    /// Make a picture from encoded image data, filling in the MIME type and
+    /// dimensions by sniffing the data.
+    pub fn from_image(
+        picture_type: PictureType,
+        data: Vec<u8>,
+    ) -> Result<Picture, VorbisPictureError> {
+        let info = probe_image(&data).ok_or(VorbisPictureError::UnknownImageFormat)?;
+        Ok(Picture {
+            picture_type,
+            mime_type: info.mime_type.to_string(),
+            description: "".to_string(),
+            width: info.width,
+            height: info.height,
+            depth: info.depth,
+            colors: info.colors,
+            data,
+        })
+    }
+
+    /// Serialize as a FLAC picture block, failing with `FieldTooLong` when a
+    /// length doesn't fit its 32-bit field.
+    pub fn safe_to_block(&self) -> Result<Vec<u8>, VorbisPictureError> {
+        let length = |len: usize| {
+            u32::try_from(len)
+                .map(u32::to_be_bytes)
+                .map_err(|_| VorbisPictureError::FieldTooLong(len))
+        };
+        let mut block: Vec<u8> = vec![];
+        block.extend(self.picture_type.to_u32().to_be_bytes().iter());
+        block.extend(length(self.mime_type.len())?.iter());
+        block.extend(self.mime_type.as_bytes().iter());
+        block.extend(length(self.description.len())?.iter());
+        block.extend(self.description.as_bytes().iter());
+        for value in [self.width, self.height, self.depth, self.colors].iter() {
+            block.extend(value.to_be_bytes().iter());
+        }
+        block.extend(length(self.data.len())?.iter());
+        block.extend(self.data.iter());
+        Ok(block)
+    }
+
+    pub fn to_block(&self) -> Vec<u8> {
+        self.safe_to_block().unwrap()
+    }
+
+    /// Parse a FLAC picture block.
+    pub fn from_block(block: &[u8]) -> Result<Picture, VorbisPictureError> {
+        let mut pos = 0;
+        let next_u32 = |pos: &mut usize| {
+            let value = be_u32(block, *pos).ok_or(VorbisPictureError::TruncatedBlock);
+            *pos += 4;
+            value
+        };
+        let next_bytes = |pos: &mut usize, len: usize| {
+            let end = pos
+                .checked_add(len)
+                .ok_or(VorbisPictureError::TruncatedBlock)?;
+            let bytes = block
+                .get(*pos..end)
+                .ok_or(VorbisPictureError::TruncatedBlock)?;
+            *pos = end;
+            Ok(bytes)
+        };
+        let picture_type = PictureType::from_u32(next_u32(&mut pos)?);
+        let mime_len = next_u32(&mut pos)? as usize;
+        let mime_type = next_bytes(&mut pos, mime_len)?;
+        let mime_type =
+            String::from_utf8(mime_type.to_vec()).map_err(|_| VorbisPictureError::InvalidText)?;
+        let desc_len = next_u32(&mut pos)? as usize;
+        let description = next_bytes(&mut pos, desc_len)?;
+        let description =
+            String::from_utf8(description.to_vec()).map_err(|_| VorbisPictureError::InvalidText)?;
+        let width = next_u32(&mut pos)?;
+        let height = next_u32(&mut pos)?;
+        let depth = next_u32(&mut pos)?;
+        let colors = next_u32(&mut pos)?;
+        let data_len = next_u32(&mut pos)? as usize;
+        let data = next_bytes(&mut pos, data_len)?.to_vec();
+        Ok(Picture {
+            picture_type,
+            mime_type,
+            description,
+            width,
+            height,
+            depth,
+            colors,
+            data,
+        })
+    }
+
+    /// Encode as the value of a METADATA_BLOCK_PICTURE tag.
+    pub fn to_tag_value(&self) -> String {
+        base64::encode(&self.to_block())
+    }
+
+    /// Decode the value of a METADATA_BLOCK_PICTURE tag.
+    pub fn from_tag_value(value: &str) -> Result<Picture, VorbisPictureError> {
+        let block = base64::decode(value).ok_or(VorbisPictureError::InvalidBase64)?;
+        Picture::from_block(&block)
+    }
+}
+
+pub trait VorbisPictures {
+    /// All pictures that could be decoded, in tag order.
+    fn get_pictures(&self) -> Vec<Picture>;
+    fn add_picture(&mut self, picture: &Picture);
+    fn clear_pictures(&mut self);
+    /// The first picture of type `FrontCover`.
+    fn get_front_cover(&self) -> Option<Picture>;
+    /// Replace any front cover with the given image. The dimensions are
+    /// filled in when the image format is recognized.
+    fn set_front_cover(&mut self, image_bytes: Vec<u8>, mime: &str);
+}
+
+impl VorbisPictures for CommentHeader {
+    fn get_pictures(&self) -> Vec<Picture> {
+        self.get_tag_multi(PICTURE_TAG)
+            .iter()
+            .filter_map(|value| Picture::from_tag_value(value).ok())
+            .collect()
+    }
+
+    fn add_picture(&mut self, picture: &Picture) {
+        self.add_tag_single(PICTURE_TAG, &picture.to_tag_value());
+    }
+
+    fn clear_pictures(&mut self) {
+        self.clear_tag(PICTURE_TAG);
+    }
+
+    fn get_front_cover(&self) -> Option<Picture> {
+        self.get_pictures()
+            .into_iter()
+            .find(|picture| picture.picture_type == PictureType::FrontCover)
+    }
+
+    fn set_front_cover(&mut self, image_bytes: Vec<u8>, mime: &str) {
+        self.retain(|key, value| {
+            key.to_lowercase() != PICTURE_TAG
+                || !matches!(Picture::from_tag_value(value),
+                    Ok(picture) if picture.picture_type == PictureType::FrontCover)
+        });
+        let mut picture = Picture::new(PictureType::FrontCover, mime, image_bytes);
+        if let Some(info) = probe_image(&picture.data) {
+            picture.width = info.width;
+            picture.height = info.height;
+            picture.depth = info.depth;
+            picture.colors = info.colors;
+        }
+        self.add_picture(&picture);
+    }
+}
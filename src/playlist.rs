@@ -0,0 +1,116 @@
+// Playlist entries (XSPF and extended M3U) from scanned files
+
+use crate::keys;
+use crate::{ScanEntry, VorbisComments};
+use std::time::Duration;
+
+/// First line of an extended M3U playlist.
+pub const M3U8_HEADER: &str = "#EXTM3U\n";
+
+fn xml_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// "file://" URI of the entry path, percent-encoding everything but
+/// unreserved characters and slashes.
+fn file_uri(entry: &ScanEntry) -> String {
+    let path = entry.path.to_string_lossy();
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            b'\\' => uri.push('/'),
+            byte => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// An XSPF `<track>` element with location, title, creator, album,
+/// track number and duration, for the tags that are present.
+pub fn xspf_track(entry: &ScanEntry, duration: Option<Duration>) -> String {
+    let header = &entry.header;
+    let mut track = String::from("<track>");
+    track.push_str(&format!(
+        "<location>{}</location>",
+        xml_escape(&file_uri(entry))
+    ));
+    let elements = [
+        ("title", keys::TITLE),
+        ("creator", keys::ARTIST),
+        ("album", keys::ALBUM),
+    ];
+    for (element, tag) in elements.iter() {
+        if let Some(value) = header.get_tag_single(tag) {
+            track.push_str(&format!("<{0}>{1}</{0}>", element, xml_escape(&value)));
+        }
+    }
+    let number = header
+        .get_tag_single(keys::TRACKNUMBER)
+        .and_then(|value| value.split('/').next()?.trim().parse::<u32>().ok());
+    if let Some(number) = number {
+        track.push_str(&format!("<trackNum>{}</trackNum>", number));
+    }
+    if let Some(duration) = duration {
+        track.push_str(&format!("<duration>{}</duration>", duration.as_millis()));
+    }
+    track.push_str("</track>");
+    track
+}
+
+/// A complete XSPF document around the given `<track>` elements.
+pub fn xspf_playlist(tracks: &[String]) -> String {
+    let mut playlist = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n<trackList>\n",
+    );
+    for track in tracks.iter() {
+        playlist.push_str(track);
+        playlist.push('\n');
+    }
+    playlist.push_str("</trackList>\n</playlist>\n");
+    playlist
+}
+
+/// An `#EXTINF` line followed by the path. The duration is in whole seconds,
+/// -1 when unknown, and the title is "ARTIST - TITLE" when both are present.
+pub fn m3u8_entry(entry: &ScanEntry, duration: Option<Duration>) -> String {
+    let seconds = duration
+        .map(|duration| duration.as_secs_f64().round() as i64)
+        .unwrap_or(-1);
+    let title = entry.header.get_tag_single(keys::TITLE);
+    let artist = entry.header.get_tag_single(keys::ARTIST);
+    let display = match (artist, title) {
+        (Some(artist), Some(title)) => format!("{} - {}", artist, title),
+        (None, Some(title)) => title,
+        _ => entry
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    };
+    // Line breaks would end the entry
+    let display = display.replace(['\r', '\n'], " ");
+    format!(
+        "#EXTINF:{},{}\n{}\n",
+        seconds,
+        display,
+        entry.path.to_string_lossy()
+    )
+}
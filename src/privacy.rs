@@ -0,0 +1,64 @@
+// Scrubbing personal metadata before sharing files
+
+use crate::keys;
+use crate::picture::PICTURE_TAG;
+use crate::{
+    safe_read_comment_header, safe_replace_comment_header_with_options, CommentHeader,
+    ReplaceOptions, VorbisComments, VorbisReplaceCommentError,
+};
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+/// Vendor string written in place of the real one by `anonymize_encoder`.
+pub const GENERIC_VENDOR: &str = "Vorbis";
+
+/// Tags naming the encoder, its settings or who ran it.
+pub const ENCODER_TAGS: [&str; 3] = [keys::ENCODER, keys::ENCODERSETTINGS, keys::ENCODEDBY];
+
+pub trait VorbisPrivacy {
+    /// Replace the vendor with `GENERIC_VENDOR` and remove the `ENCODER_TAGS`.
+    fn anonymize_encoder(&mut self);
+}
+
+impl VorbisPrivacy for CommentHeader {
+    fn anonymize_encoder(&mut self) {
+        self.set_vendor(GENERIC_VENDOR);
+        for tag in ENCODER_TAGS {
+            self.clear_tag(tag);
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct StripOptions {
+    /// Keep the embedded pictures, removing only the other tags.
+    pub keep_pictures: bool,
+    /// Also replace the vendor string with `GENERIC_VENDOR`.
+    pub anonymize_encoder: bool,
+}
+
+/// Rewrite the file with no tags. The vendor string, which names the encoder,
+/// is kept unless `anonymize_encoder` is set.
+pub fn safe_strip_all_tags<T: Read + Seek>(
+    mut f_in: T,
+    options: &StripOptions,
+) -> Result<Cursor<Vec<u8>>, VorbisReplaceCommentError> {
+    let mut header = CommentHeader::new();
+    if options.keep_pictures {
+        let old_header = safe_read_comment_header(&mut f_in)?;
+        for picture in old_header.get_tag_refs(PICTURE_TAG) {
+            header.add_tag_single(PICTURE_TAG, picture);
+        }
+        f_in.seek(SeekFrom::Start(0))?;
+    }
+    let replace = ReplaceOptions {
+        preserve_vendor: true,
+        anonymize_encoder: options.anonymize_encoder,
+        ..Default::default()
+    };
+    let (f_out, _) = safe_replace_comment_header_with_options(f_in, header, &replace)?;
+    Ok(f_out)
+}
+
+pub fn strip_all_tags<T: Read + Seek>(f_in: T, options: &StripOptions) -> Cursor<Vec<u8>> {
+    safe_strip_all_tags(f_in, options).unwrap()
+}
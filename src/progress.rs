@@ -0,0 +1,78 @@
+// Reporting how far a rewrite got, for progress bars
+
+use crate::{
+    safe_replace_comment_header_into_with_options, CommentHeader, ReplaceOptions, ReplaceReport,
+    VorbisReplaceCommentError,
+};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// Bytes read between two calls of the progress callback.
+const PROGRESS_STEP: u64 = 64 * 1024;
+
+/// Passes the position in the input to `progress` as it is read.
+struct ProgressReader<T, F> {
+    inner: T,
+    position: u64,
+    reported: u64,
+    total: u64,
+    progress: F,
+}
+
+impl<T, F: FnMut(u64, u64)> ProgressReader<T, F> {
+    fn report(&mut self) {
+        self.reported = self.position;
+        (self.progress)(self.position, self.total);
+    }
+}
+
+impl<T: Read, F: FnMut(u64, u64)> Read for ProgressReader<T, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.position += read as u64;
+        if self.position >= self.reported + PROGRESS_STEP || self.position == self.total {
+            self.report();
+        }
+        Ok(read)
+    }
+}
+
+impl<T: Seek, F> Seek for ProgressReader<T, F> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = self.inner.seek(pos)?;
+        Ok(self.position)
+    }
+}
+
+/// `safe_replace_comment_header_into_with_options`, calling
+/// `progress(bytes_read, total_bytes)` as the input is read, about every
+/// 64 KiB and once more at the end.
+pub fn safe_replace_comment_header_with_progress<T, W, F>(
+    mut f_in: T,
+    f_out: W,
+    new_header: CommentHeader,
+    options: &ReplaceOptions,
+    progress: F,
+) -> Result<ReplaceReport, VorbisReplaceCommentError>
+where
+    T: Read + Seek,
+    W: Write,
+    F: FnMut(u64, u64),
+{
+    let start = f_in.stream_position()?;
+    let total = f_in.seek(SeekFrom::End(0))?;
+    f_in.seek(SeekFrom::Start(start))?;
+    let mut reader = ProgressReader {
+        inner: f_in,
+        position: start,
+        reported: start,
+        total,
+        progress,
+    };
+    let report =
+        safe_replace_comment_header_into_with_options(&mut reader, f_out, new_header, options)?;
+    if reader.reported != reader.total {
+        reader.position = reader.total;
+        reader.report();
+    }
+    Ok(report)
+}
@@ -0,0 +1,131 @@
+// Recording which source each tag value came from
+
+use crate::{CommentHeader, VorbisComments};
+use std::path::PathBuf;
+
+/// Opt-in tag holding one "tag=source" value per tracked tag.
+pub const PROVENANCE_TAG: &str = "x-provenance";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Source {
+    File(PathBuf),
+    /// An external database, by name.
+    Database(String),
+    UserEdit,
+    Other(String),
+}
+
+impl Source {
+    /// `"file:<path>"`, `"db:<name>"`, `"user"` or `"other:<text>"`.
+    pub fn to_tag_value(&self) -> String {
+        match self {
+            Source::File(path) => format!("file:{}", path.display()),
+            Source::Database(name) => format!("db:{}", name),
+            Source::UserEdit => "user".to_string(),
+            Source::Other(text) => format!("other:{}", text),
+        }
+    }
+
+    pub fn from_tag_value(value: &str) -> Source {
+        if value == "user" {
+            Source::UserEdit
+        } else if let Some(path) = value.strip_prefix("file:") {
+            Source::File(PathBuf::from(path))
+        } else if let Some(name) = value.strip_prefix("db:") {
+            Source::Database(name.to_string())
+        } else {
+            Source::Other(value.strip_prefix("other:").unwrap_or(value).to_string())
+        }
+    }
+}
+
+/// The source of each tag, by lowercase tag name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Provenance {
+    entries: Vec<(String, Source)>,
+}
+
+impl Provenance {
+    pub fn new() -> Provenance {
+        Provenance::default()
+    }
+
+    pub fn entries(&self) -> &[(String, Source)] {
+        &self.entries
+    }
+
+    pub fn source(&self, tag: &str) -> Option<&Source> {
+        let tag = tag.to_lowercase();
+        self.entries
+            .iter()
+            .find(|entry| entry.0 == tag)
+            .map(|entry| &entry.1)
+    }
+
+    /// Record the source of `tag`, replacing any earlier one.
+    pub fn record(&mut self, tag: &str, source: Source) {
+        let tag = tag.to_lowercase();
+        match self.entries.iter_mut().find(|entry| entry.0 == tag) {
+            Some(entry) => entry.1 = source,
+            None => self.entries.push((tag, source)),
+        }
+    }
+
+    pub fn forget(&mut self, tag: &str) {
+        let tag = tag.to_lowercase();
+        self.entries.retain(|entry| entry.0 != tag);
+    }
+
+    /// Set every tag of `from` on `target`, replacing its values there, and
+    /// record `source` for them. With `fill_only`, tags `target` already has
+    /// are left alone.
+    pub fn merge(
+        &mut self,
+        target: &mut CommentHeader,
+        from: &CommentHeader,
+        source: Source,
+        fill_only: bool,
+    ) {
+        for tag in from.get_tag_names() {
+            if tag == PROVENANCE_TAG || (fill_only && target.get_tag_single(&tag).is_some()) {
+                continue;
+            }
+            let values = from.get_tag_multi(&tag);
+            target.clear_tag(&tag);
+            for value in values.iter() {
+                target.add_tag_single(&tag, value);
+            }
+            self.record(&tag, source.clone());
+        }
+    }
+
+    /// Read the sources stored in the X-PROVENANCE tag.
+    pub fn from_header(header: &CommentHeader) -> Provenance {
+        let mut provenance = Provenance::new();
+        for value in header.get_tag_multi(PROVENANCE_TAG) {
+            if let Some((tag, source)) = value.split_once('=') {
+                provenance.record(tag, Source::from_tag_value(source));
+            }
+        }
+        provenance
+    }
+
+    /// Store the sources in the X-PROVENANCE tag, replacing its values.
+    pub fn write_to(&self, header: &mut CommentHeader) {
+        header.clear_tag(PROVENANCE_TAG);
+        for (tag, source) in self.entries.iter() {
+            header.add_tag_single(
+                PROVENANCE_TAG,
+                &format!("{}={}", tag, source.to_tag_value()),
+            );
+        }
+    }
+
+    /// A report with one "tag: source" line per tag, for curators.
+    pub fn report(&self) -> String {
+        self.entries
+            .iter()
+            .map(|(tag, source)| format!("{}: {}\n", tag, source.to_tag_value()))
+            .collect()
+    }
+}
@@ -0,0 +1,88 @@
+// RATING and FMPS_RATING, normalized to 0.0 - 1.0
+
+use crate::keys;
+use crate::{CommentHeader, VorbisComments};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RatingFormat {
+    /// RATING from 0 to 5.
+    Stars,
+    /// RATING from 0 to 100. Values from 1 to 5, which would be read back
+    /// as stars, are written with a '%' suffix.
+    Percent,
+    /// FMPS_RATING from 0.0 to 1.0.
+    Fmps,
+}
+
+/// Normalize a RATING value. Values up to 5 are read as stars, larger
+/// ones and those with a '%' suffix as percent.
+pub fn normalize_rating(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let (value, percent) = match value.strip_suffix('%') {
+        Some(value) => (value.trim_end(), true),
+        None => (value, false),
+    };
+    let value = value.parse::<f64>().ok().filter(|v| v.is_finite())?;
+    if value < 0.0 {
+        None
+    } else if value <= 5.0 && !percent {
+        Some(value / 5.0)
+    } else {
+        Some((value / 100.0).min(1.0))
+    }
+}
+
+pub trait VorbisRating {
+    /// The rating from 0.0 to 1.0, from FMPS_RATING if present, else RATING.
+    fn rating(&self) -> Option<f64>;
+    /// Replace RATING and FMPS_RATING, writing the given representations.
+    /// RATING holds a single value, so only the first of `Stars` and
+    /// `Percent` is used.
+    fn set_rating(&mut self, rating: f64, formats: &[RatingFormat]);
+}
+
+impl VorbisRating for CommentHeader {
+    fn rating(&self) -> Option<f64> {
+        let fmps = self
+            .get_tag_single(keys::FMPS_RATING)
+            .and_then(|value| value.trim().parse::<f64>().ok())
+            .filter(|value| (0.0..=1.0).contains(value));
+        fmps.or_else(|| {
+            self.get_tag_single(keys::RATING)
+                .and_then(|value| normalize_rating(&value))
+        })
+    }
+
+    fn set_rating(&mut self, rating: f64, formats: &[RatingFormat]) {
+        let rating = if rating.is_finite() {
+            rating.clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        self.clear_tag(keys::RATING);
+        self.clear_tag(keys::FMPS_RATING);
+        let mut rating_written = false;
+        for format in formats.iter() {
+            match format {
+                RatingFormat::Stars | RatingFormat::Percent if rating_written => {}
+                RatingFormat::Stars => {
+                    self.add_tag_single(keys::RATING, &format!("{}", (rating * 5.0).round()));
+                    rating_written = true;
+                }
+                RatingFormat::Percent => {
+                    let percent = (rating * 100.0).round();
+                    let value = if percent > 0.0 && percent <= 5.0 {
+                        format!("{}%", percent)
+                    } else {
+                        format!("{}", percent)
+                    };
+                    self.add_tag_single(keys::RATING, &value);
+                    rating_written = true;
+                }
+                RatingFormat::Fmps => {
+                    self.add_tag_single(keys::FMPS_RATING, &format!("{:.6}", rating));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,168 @@
+// Reading the comments of damaged files by resynchronising on the next page
+
+use crate::page::{read_page, Page, PageReadError, CAPTURE_PATTERN};
+use crate::parse::parse_comment_header;
+use crate::{CommentHeader, VorbisReadCommentError};
+
+use lewton::header::HeaderReadError;
+use std::io::{self, Read};
+
+/// What reading with `safe_read_comment_header_recovering` had to skip.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RecoveryReport {
+    /// Pages dropped for a bad checksum or a malformed header.
+    pub dropped_pages: usize,
+    /// Bytes skipped looking for the next capture pattern, those of the
+    /// dropped pages included.
+    pub skipped_bytes: u64,
+    /// Packets of the Vorbis stream lost, in part or whole, with the pages
+    /// dropped or missing from the sequence numbers.
+    pub lost_packets: usize,
+}
+
+/// A reader keeping the bytes of the page being read, so they can be read
+/// again from just after its capture pattern when it turns out corrupt.
+struct Resync<R> {
+    inner: R,
+    pending: Vec<u8>,
+    pos: usize,
+    record: Vec<u8>,
+}
+
+impl<R: Read> Read for Resync<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = if self.pos < self.pending.len() {
+            let len = buf.len().min(self.pending.len() - self.pos);
+            buf[..len].copy_from_slice(&self.pending[self.pos..self.pos + len]);
+            self.pos += len;
+            len
+        } else {
+            self.inner.read(buf)?
+        };
+        self.record.extend(&buf[..read]);
+        Ok(read)
+    }
+}
+
+impl<R: Read> Resync<R> {
+    /// The next page with a valid checksum, skipping anything else.
+    fn next_page(&mut self, report: &mut RecoveryReport) -> Result<Option<Page>, PageReadError> {
+        loop {
+            self.record.clear();
+            match read_page(self, true) {
+                Ok(Some((page, _))) if page.checksum == page.compute_checksum() => {
+                    report.skipped_bytes += (self.record.len() - page.size()) as u64;
+                    return Ok(Some(page));
+                }
+                Ok(None) => return Ok(None),
+                Err(PageReadError::Io(err)) => return Err(PageReadError::Io(err)),
+                // A bad length may also have run the page over the ones
+                // after it, they are read again from its capture pattern on
+                Ok(Some(_)) | Err(PageReadError::Page(_)) => {}
+            }
+            let start = match self
+                .record
+                .windows(CAPTURE_PATTERN.len())
+                .position(|window| window == CAPTURE_PATTERN)
+            {
+                Some(start) => start,
+                // Only garbage up to the end of the file
+                None => {
+                    report.skipped_bytes += self.record.len() as u64;
+                    return Ok(None);
+                }
+            };
+            report.dropped_pages += 1;
+            report.skipped_bytes += start as u64 + 1;
+            let mut pending = self.record.split_off(start + 1);
+            pending.extend(&self.pending[self.pos..]);
+            self.pending = pending;
+            self.pos = 0;
+        }
+    }
+}
+
+/// `safe_read_comment_packet` for damaged files: pages with a bad checksum
+/// or that can't be parsed are dropped and reading resumes at the next
+/// capture pattern. Packets broken by a dropped page are discarded, and the
+/// comment header is recognised by its content rather than its position.
+pub fn safe_read_comment_packet_recovering<T: Read>(
+    f_in: T,
+) -> Result<(Vec<u8>, RecoveryReport), VorbisReadCommentError> {
+    let mut f_in = Resync {
+        inner: f_in,
+        pending: vec![],
+        pos: 0,
+        record: vec![],
+    };
+    let mut report = RecoveryReport::default();
+    let mut serial = None;
+    let mut next_sequence = 0;
+    let mut packet = vec![];
+    // Whether `packet` holds the start of a packet, not the remains of one
+    // broken by a lost page
+    let mut intact = true;
+    loop {
+        let page = match f_in.next_page(&mut report)? {
+            Some(page) => page,
+            None if serial.is_none() => return Err(HeaderReadError::NotVorbisHeader.into()),
+            None => return Err(VorbisReadCommentError::Truncated),
+        };
+        let starts_header = |kind: u8| {
+            page.flags & Page::CONTINUED == 0
+                && page.body.starts_with(&[kind])
+                && page.body[1..].starts_with(b"vorbis")
+        };
+        match serial {
+            // The identification header may be lost, the comment header
+            // still tells the stream
+            None if (page.is_first() && starts_header(1)) || starts_header(3) => {
+                serial = Some(page.serial);
+                next_sequence = page.sequence;
+            }
+            None => continue,
+            Some(serial) if serial != page.serial => continue,
+            Some(_) => {}
+        }
+        if page.sequence != next_sequence {
+            if !packet.is_empty() || page.flags & Page::CONTINUED != 0 {
+                report.lost_packets += 1;
+            }
+            packet.clear();
+            intact = page.flags & Page::CONTINUED == 0;
+        } else if page.flags & Page::CONTINUED == 0 && !packet.is_empty() {
+            report.lost_packets += 1;
+            packet.clear();
+            intact = true;
+        }
+        next_sequence = page.sequence.wrapping_add(1);
+        let mut pos = 0;
+        for lacing in page.lacing.iter() {
+            let len = *lacing as usize;
+            if intact {
+                packet.extend(&page.body[pos..pos + len]);
+            }
+            pos += len;
+            if len < 255 {
+                if packet.starts_with(b"\x03vorbis") {
+                    return Ok((packet, report));
+                }
+                // The setup header or audio, the comment header was lost
+                if packet.starts_with(b"\x05vorbis") || packet.first().is_some_and(|b| b & 1 == 0) {
+                    return Err(VorbisReadCommentError::Truncated);
+                }
+                packet.clear();
+                intact = true;
+            }
+        }
+    }
+}
+
+/// `safe_read_comment_header` for damaged files, see
+/// `safe_read_comment_packet_recovering`.
+pub fn safe_read_comment_header_recovering<T: Read>(
+    f_in: T,
+) -> Result<(CommentHeader, RecoveryReport), VorbisReadCommentError> {
+    let (packet, report) = safe_read_comment_packet_recovering(f_in)?;
+    Ok((parse_comment_header(&packet)?, report))
+}
@@ -0,0 +1,89 @@
+// ReplayGain tags
+
+use crate::keys::{
+    REPLAYGAIN_ALBUM_GAIN, REPLAYGAIN_ALBUM_PEAK, REPLAYGAIN_TRACK_GAIN, REPLAYGAIN_TRACK_PEAK,
+};
+use crate::{CommentHeader, VorbisComments};
+
+/// Gains are in dB, peaks are linear sample values where 1.0 is full scale.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ReplayGain {
+    pub track_gain: Option<f64>,
+    pub track_peak: Option<f64>,
+    pub album_gain: Option<f64>,
+    pub album_peak: Option<f64>,
+}
+
+/// Parse "-6.50 dB", "+1.2dB" or a bare number.
+pub fn parse_gain(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let bytes = value.as_bytes();
+    let number = if bytes.len() >= 2 && bytes[bytes.len() - 2..].eq_ignore_ascii_case(b"db") {
+        &value[..value.len() - 2]
+    } else {
+        value
+    };
+    number
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|gain| gain.is_finite())
+}
+
+/// Format a gain the way ReplayGain scanners write it, e.g. "+1.23 dB".
+pub fn format_gain(gain: f64) -> String {
+    format!("{:+.2} dB", gain)
+}
+
+fn parse_peak(value: &str) -> Option<f64> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|peak| peak.is_finite() && *peak >= 0.0)
+}
+
+fn format_peak(peak: f64) -> String {
+    format!("{:.6}", peak)
+}
+
+pub trait VorbisReplayGain {
+    /// Unparseable values are read as missing.
+    fn replay_gain(&self) -> ReplayGain;
+    /// Write all four tags, removing the ones that are `None`.
+    fn set_replay_gain(&mut self, gain: &ReplayGain);
+}
+
+impl VorbisReplayGain for CommentHeader {
+    fn replay_gain(&self) -> ReplayGain {
+        let gain = |tag| {
+            self.get_tag_single(tag)
+                .and_then(|value| parse_gain(&value))
+        };
+        let peak = |tag| {
+            self.get_tag_single(tag)
+                .and_then(|value| parse_peak(&value))
+        };
+        ReplayGain {
+            track_gain: gain(REPLAYGAIN_TRACK_GAIN),
+            track_peak: peak(REPLAYGAIN_TRACK_PEAK),
+            album_gain: gain(REPLAYGAIN_ALBUM_GAIN),
+            album_peak: peak(REPLAYGAIN_ALBUM_PEAK),
+        }
+    }
+
+    fn set_replay_gain(&mut self, gain: &ReplayGain) {
+        let values = [
+            (REPLAYGAIN_TRACK_GAIN, gain.track_gain.map(format_gain)),
+            (REPLAYGAIN_TRACK_PEAK, gain.track_peak.map(format_peak)),
+            (REPLAYGAIN_ALBUM_GAIN, gain.album_gain.map(format_gain)),
+            (REPLAYGAIN_ALBUM_PEAK, gain.album_peak.map(format_peak)),
+        ];
+        for (tag, value) in values.iter() {
+            self.clear_tag(tag);
+            if let Some(value) = value {
+                self.add_tag_single(tag, value);
+            }
+        }
+    }
+}
@@ -0,0 +1,56 @@
+// Parse, serialize and parse again, for fuzzers and ingestion gates
+
+use crate::parse::parse_comment_header;
+use crate::{safe_make_comment_header, CommentHeader};
+use lewton::header::HeaderReadError;
+
+#[derive(Debug, PartialEq)]
+pub enum RoundTripOutcome {
+    /// Serializing the parsed header gives back the input bytes.
+    Identical,
+    /// The re-parsed header is equal but the bytes differ, for instance
+    /// because the input had data after the framing bit.
+    Equivalent,
+    /// The input is not a comment header.
+    Unparseable(HeaderReadError),
+    /// The parsed header could not be serialized again.
+    Unserializable(CommentHeader),
+    /// The serialized header fails to parse, or parses differently.
+    Diverged {
+        parsed: CommentHeader,
+        reparsed: Result<CommentHeader, HeaderReadError>,
+    },
+}
+
+impl RoundTripOutcome {
+    /// Whether the packet survived the round trip, identically or not.
+    pub fn is_consistent(&self) -> bool {
+        matches!(
+            self,
+            RoundTripOutcome::Identical | RoundTripOutcome::Equivalent
+        )
+    }
+}
+
+/// Round trip a comment header packet through the parser and serializer.
+/// It never panics, whatever `bytes` holds.
+pub fn roundtrip_check(bytes: &[u8]) -> RoundTripOutcome {
+    let parsed = match parse_comment_header(bytes) {
+        Ok(parsed) => parsed,
+        Err(err) => return RoundTripOutcome::Unparseable(err),
+    };
+    let serialized = match safe_make_comment_header(&parsed) {
+        Ok(serialized) => serialized,
+        Err(_) => return RoundTripOutcome::Unserializable(parsed),
+    };
+    match parse_comment_header(&serialized) {
+        Ok(reparsed) if reparsed == parsed => {
+            if serialized == bytes {
+                RoundTripOutcome::Identical
+            } else {
+                RoundTripOutcome::Equivalent
+            }
+        }
+        reparsed => RoundTripOutcome::Diverged { parsed, reparsed },
+    }
+}
@@ -0,0 +1,83 @@
+// Cleaning up values written by careless taggers
+
+use crate::{CommentHeader, VorbisComments};
+use std::collections::HashSet;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SanitizeOptions {
+    /// Remove NULs and other control characters. Tabs and line breaks are
+    /// kept, as lyrics and cue sheets need them.
+    pub strip_control: bool,
+    /// Trim whitespace around values.
+    pub trim: bool,
+    /// Remove entries left with an empty value.
+    pub drop_empty: bool,
+    /// Remove entries with the same tag and value as an earlier one.
+    pub drop_duplicates: bool,
+}
+
+impl Default for SanitizeOptions {
+    /// Everything but `drop_duplicates`.
+    fn default() -> SanitizeOptions {
+        SanitizeOptions {
+            strip_control: true,
+            trim: true,
+            drop_empty: true,
+            drop_duplicates: false,
+        }
+    }
+}
+
+/// What `sanitize` changed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SanitizeReport {
+    /// Values changed by stripping or trimming.
+    pub cleaned: usize,
+    pub dropped_empty: usize,
+    pub dropped_duplicates: usize,
+}
+
+impl SanitizeReport {
+    pub fn is_empty(&self) -> bool {
+        *self == SanitizeReport::default()
+    }
+}
+
+fn is_stripped(c: char) -> bool {
+    c.is_control() && !matches!(c, '\t' | '\n' | '\r')
+}
+
+pub trait VorbisSanitize {
+    fn sanitize(&mut self, options: &SanitizeOptions) -> SanitizeReport;
+}
+
+impl VorbisSanitize for CommentHeader {
+    fn sanitize(&mut self, options: &SanitizeOptions) -> SanitizeReport {
+        let cleaned = self.map_values(|_, value| {
+            let mut value = value.to_string();
+            if options.strip_control {
+                value.retain(|c| !is_stripped(c));
+            }
+            if options.trim {
+                value = value.trim().to_string();
+            }
+            Some(value)
+        });
+        let mut report = SanitizeReport {
+            cleaned,
+            ..Default::default()
+        };
+        if options.drop_empty {
+            let before = self.len();
+            self.retain(|_, value| !value.is_empty());
+            report.dropped_empty = before - self.len();
+        }
+        if options.drop_duplicates {
+            let mut seen = HashSet::new();
+            let before = self.len();
+            self.retain(|key, value| seen.insert((key.to_lowercase(), value.to_string())));
+            report.dropped_duplicates = before - self.len();
+        }
+        report
+    }
+}
@@ -0,0 +1,168 @@
+// Reading the comment headers of many files, optionally through a cache
+
+use crate::batch::{read_path, VorbisBatchError};
+use crate::cache::{CachedHeader, ScanCache};
+use crate::crc::crc32_update;
+use crate::throttle::{Throttle, Throttler};
+use crate::CommentHeader;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Number of bytes at the start of a file covered by the fingerprint.
+pub const FINGERPRINT_LEN: u64 = 64 * 1024;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScanEntry {
+    pub path: PathBuf,
+    pub header: CommentHeader,
+    /// The header came from the cache rather than from the file.
+    pub from_cache: bool,
+}
+
+/// CRC32 of the first `FINGERPRINT_LEN` bytes of the file, which normally
+/// covers the comment header. It catches edits that keep size and mtime.
+pub fn header_fingerprint(path: &Path) -> io::Result<u32> {
+    let mut start = vec![];
+    File::open(path)?
+        .take(FINGERPRINT_LEN)
+        .read_to_end(&mut start)?;
+    Ok(crc32_update(0, &start))
+}
+
+pub(crate) fn file_size(path: &Path) -> u64 {
+    fs::metadata(path)
+        .map(|metadata| metadata.len())
+        .unwrap_or(0)
+}
+
+/// Reads comment headers, consulting the cache (if any) to skip files that
+/// haven't changed since they were last scanned.
+pub struct Scanner {
+    cache: Option<Box<dyn ScanCache>>,
+    verify_fingerprint: bool,
+    throttle: Throttle,
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        Scanner::new()
+    }
+}
+
+impl Scanner {
+    pub fn new() -> Scanner {
+        Scanner {
+            cache: None,
+            verify_fingerprint: true,
+            throttle: Throttle::default(),
+        }
+    }
+
+    pub fn with_cache(cache: Box<dyn ScanCache>) -> Scanner {
+        Scanner {
+            cache: Some(cache),
+            verify_fingerprint: true,
+            throttle: Throttle::default(),
+        }
+    }
+
+    /// Whether a cache entry must also match the header fingerprint, and not
+    /// only the file size and modification time. Enabled by default.
+    pub fn verify_fingerprint(&mut self, verify: bool) {
+        self.verify_fingerprint = verify;
+    }
+
+    /// Limits applied between the files of `scan`.
+    pub fn throttle(&mut self, throttle: Throttle) {
+        self.throttle = throttle;
+    }
+
+    pub fn scan_file(&mut self, path: &Path) -> Result<ScanEntry, VorbisBatchError> {
+        let cache = match self.cache.as_mut() {
+            Some(cache) => cache,
+            None => {
+                return Ok(ScanEntry {
+                    path: path.to_path_buf(),
+                    header: read_path(path)?,
+                    from_cache: false,
+                })
+            }
+        };
+        let io_err = |err| VorbisBatchError::Io(path.to_path_buf(), err);
+        let metadata = fs::metadata(path).map_err(io_err)?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .unwrap_or(Duration::ZERO);
+        let size = metadata.len();
+        let fingerprint = if self.verify_fingerprint {
+            header_fingerprint(path).map_err(io_err)?
+        } else {
+            0
+        };
+        if let Some(cached) = cache.get(path) {
+            if cached.size == size
+                && cached.modified == modified
+                && (!self.verify_fingerprint || cached.fingerprint == fingerprint)
+            {
+                return Ok(ScanEntry {
+                    path: path.to_path_buf(),
+                    header: cached.header,
+                    from_cache: true,
+                });
+            }
+        }
+        let header = read_path(path)?;
+        let fingerprint = if self.verify_fingerprint {
+            fingerprint
+        } else {
+            header_fingerprint(path).map_err(io_err)?
+        };
+        cache.insert(
+            path.to_path_buf(),
+            CachedHeader {
+                size,
+                modified,
+                fingerprint,
+                header: header.clone(),
+            },
+        );
+        Ok(ScanEntry {
+            path: path.to_path_buf(),
+            header,
+            from_cache: false,
+        })
+    }
+
+    /// Scan the files in order. A failure on one file doesn't stop the scan.
+    pub fn scan<P: AsRef<Path>>(
+        &mut self,
+        paths: &[P],
+    ) -> Vec<Result<ScanEntry, VorbisBatchError>> {
+        let mut throttler = Throttler::new(&self.throttle);
+        paths
+            .iter()
+            .map(|path| {
+                throttler.wait();
+                let entry = self.scan_file(path.as_ref());
+                throttler.file_done(file_size(path.as_ref()));
+                entry
+            })
+            .collect()
+    }
+
+    /// Write the cache to its storage.
+    pub fn flush(&mut self) -> io::Result<()> {
+        match self.cache.as_mut() {
+            Some(cache) => cache.flush(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn into_cache(self) -> Option<Box<dyn ScanCache>> {
+        self.cache
+    }
+}
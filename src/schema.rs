@@ -0,0 +1,101 @@
+// Declaring which tags a file must and may have
+
+use crate::glob::glob_matches;
+use crate::{CommentHeader, VorbisComments};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SchemaViolation {
+    /// A required tag has no value.
+    Missing(String),
+    /// The tag is neither required nor allowed.
+    NotAllowed(String),
+    /// The validator of the tag rejected this value.
+    InvalidValue { tag: String, value: String },
+}
+
+type Validator = Box<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// Rules a header is checked against with `validate`. Tag names are case
+/// insensitive and, except for required tags, may contain `*` and `?`
+/// wildcards.
+#[derive(Default)]
+pub struct TagSchema {
+    required: Vec<String>,
+    allowed: Option<Vec<String>>,
+    validators: Vec<(String, Validator)>,
+}
+
+impl TagSchema {
+    pub fn new() -> TagSchema {
+        TagSchema::default()
+    }
+
+    pub fn require(&mut self, tag: &str) -> &mut TagSchema {
+        self.required.push(tag.to_string());
+        self
+    }
+
+    /// Allow the tag. Once any tag is allowed, tags that are neither allowed
+    /// nor required are reported as `NotAllowed`.
+    pub fn allow(&mut self, tag: &str) -> &mut TagSchema {
+        self.allowed
+            .get_or_insert_with(Vec::new)
+            .push(tag.to_string());
+        self
+    }
+
+    /// Check every value of the matching tags with `validator`.
+    pub fn validator<F>(&mut self, tag: &str, validator: F) -> &mut TagSchema
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        self.validators.push((tag.to_string(), Box::new(validator)));
+        self
+    }
+
+    fn is_allowed(&self, key: &str) -> bool {
+        match &self.allowed {
+            None => true,
+            Some(allowed) => {
+                self.required
+                    .iter()
+                    .any(|tag| tag.eq_ignore_ascii_case(key))
+                    || allowed.iter().any(|pattern| glob_matches(pattern, key))
+            }
+        }
+    }
+
+    /// All violations, missing tags first and then in header order.
+    /// An empty list means the header conforms.
+    pub fn validate(&self, header: &CommentHeader) -> Vec<SchemaViolation> {
+        let mut violations = self
+            .required
+            .iter()
+            .filter(|tag| !header.contains_tag(tag))
+            .map(|tag| SchemaViolation::Missing(tag.to_string()))
+            .collect::<Vec<SchemaViolation>>();
+        let mut not_allowed: Vec<String> = vec![];
+        for (key, value) in header.iter() {
+            let key = key.to_lowercase();
+            if !self.is_allowed(&key) {
+                if !not_allowed.contains(&key) {
+                    not_allowed.push(key.clone());
+                    violations.push(SchemaViolation::NotAllowed(key));
+                }
+                continue;
+            }
+            let valid = self
+                .validators
+                .iter()
+                .filter(|(pattern, _)| glob_matches(pattern, &key))
+                .all(|(_, validator)| validator(value));
+            if !valid {
+                violations.push(SchemaViolation::InvalidValue {
+                    tag: key,
+                    value: value.to_string(),
+                });
+            }
+        }
+        violations
+    }
+}
@@ -0,0 +1,70 @@
+// Display fields paired with their *SORT counterparts
+
+use crate::keys;
+use crate::{CommentHeader, VorbisComments};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SortField {
+    Artist,
+    AlbumArtist,
+    Album,
+    Title,
+}
+
+impl SortField {
+    pub fn display_tag(&self) -> &'static str {
+        match self {
+            SortField::Artist => keys::ARTIST,
+            SortField::AlbumArtist => keys::ALBUMARTIST,
+            SortField::Album => keys::ALBUM,
+            SortField::Title => keys::TITLE,
+        }
+    }
+
+    pub fn sort_tag(&self) -> &'static str {
+        match self {
+            SortField::Artist => keys::ARTISTSORT,
+            SortField::AlbumArtist => keys::ALBUMARTISTSORT,
+            SortField::Album => keys::ALBUMSORT,
+            SortField::Title => keys::TITLESORT,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SortedValue {
+    pub display: Option<String>,
+    pub sort: Option<String>,
+}
+
+impl SortedValue {
+    /// The value to sort by: the sort field, falling back to the display field.
+    pub fn sort_key(&self) -> Option<&str> {
+        self.sort.as_deref().or(self.display.as_deref())
+    }
+}
+
+pub trait VorbisSortTags {
+    fn sorted(&self, field: SortField) -> SortedValue;
+    /// Replace both tags of the field. A `None` sort value removes the sort
+    /// tag, so it can't go stale against the new display value.
+    fn set_sorted(&mut self, field: SortField, display: &str, sort: Option<&str>);
+}
+
+impl VorbisSortTags for CommentHeader {
+    fn sorted(&self, field: SortField) -> SortedValue {
+        SortedValue {
+            display: self.get_tag_single(field.display_tag()),
+            sort: self.get_tag_single(field.sort_tag()),
+        }
+    }
+
+    fn set_sorted(&mut self, field: SortField, display: &str, sort: Option<&str>) {
+        self.clear_tag(field.display_tag());
+        self.add_tag_single(field.display_tag(), display);
+        self.clear_tag(field.sort_tag());
+        if let Some(sort) = sort {
+            self.add_tag_single(field.sort_tag(), sort);
+        }
+    }
+}
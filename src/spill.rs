@@ -0,0 +1,131 @@
+// Replace output kept in memory up to a threshold, then in a temporary file
+
+use crate::{
+    safe_replace_comment_header_into_with_options, CommentHeader, ReplaceOptions, ReplaceReport,
+    VorbisReplaceCommentError,
+};
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static SPILL_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// A temporary file, removed when dropped.
+#[derive(Debug)]
+pub struct SpillFile {
+    file: File,
+    path: PathBuf,
+}
+
+impl SpillFile {
+    fn create(dir: &Path) -> io::Result<SpillFile> {
+        let count = SPILL_COUNT.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!(
+            ".oggvorbismeta-spill-{}-{}.tmp",
+            process::id(),
+            count
+        ));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        Ok(SpillFile { file, path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// The file with its new header, returned by
+/// `safe_replace_comment_header_spilled`.
+#[derive(Debug)]
+pub enum ReplaceOutput {
+    Memory(Cursor<Vec<u8>>),
+    /// The output grew past `ReplaceOptions::spill_threshold`.
+    File(SpillFile),
+}
+
+impl Read for ReplaceOutput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            ReplaceOutput::Memory(cursor) => cursor.read(buf),
+            ReplaceOutput::File(spill) => spill.file.read(buf),
+        }
+    }
+}
+
+impl Seek for ReplaceOutput {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            ReplaceOutput::Memory(cursor) => cursor.seek(pos),
+            ReplaceOutput::File(spill) => spill.file.seek(pos),
+        }
+    }
+}
+
+struct SpillWriter {
+    threshold: Option<usize>,
+    dir: PathBuf,
+    output: ReplaceOutput,
+}
+
+impl SpillWriter {
+    fn finish(mut self) -> io::Result<ReplaceOutput> {
+        self.output.seek(SeekFrom::Start(0))?;
+        Ok(self.output)
+    }
+}
+
+impl Write for SpillWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let ReplaceOutput::Memory(cursor) = &self.output {
+            let size = cursor.get_ref().len() + buf.len();
+            if self.threshold.is_some_and(|threshold| size > threshold) {
+                let mut spill = SpillFile::create(&self.dir)?;
+                spill.file.write_all(cursor.get_ref())?;
+                self.output = ReplaceOutput::File(spill);
+            }
+        }
+        match &mut self.output {
+            ReplaceOutput::Memory(cursor) => cursor.write(buf),
+            ReplaceOutput::File(spill) => spill.file.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.output {
+            ReplaceOutput::Memory(_) => Ok(()),
+            ReplaceOutput::File(spill) => spill.file.flush(),
+        }
+    }
+}
+
+/// `safe_replace_comment_header_with_options`, moving the output to a
+/// temporary file in `ReplaceOptions::spill_dir` once it grows past
+/// `ReplaceOptions::spill_threshold`. With `check_conformance` the output is
+/// still collected in memory to be checked.
+pub fn safe_replace_comment_header_spilled<T: Read + Seek>(
+    f_in: T,
+    new_header: CommentHeader,
+    options: &ReplaceOptions,
+) -> Result<(ReplaceOutput, ReplaceReport), VorbisReplaceCommentError> {
+    let mut writer = SpillWriter {
+        threshold: options.spill_threshold,
+        dir: options.spill_dir.clone().unwrap_or_else(env::temp_dir),
+        output: ReplaceOutput::Memory(Cursor::new(vec![])),
+    };
+    let report =
+        safe_replace_comment_header_into_with_options(f_in, &mut writer, new_header, options)?;
+    Ok((writer.finish()?, report))
+}
@@ -0,0 +1,228 @@
+// LANGUAGE, SPEAKER, TRANSCRIPT and SEGMENT tags used by spoken-word archives
+
+use crate::chapters::{format_timestamp, parse_timestamp};
+use crate::keys;
+use crate::language::tag_language;
+use crate::{CommentHeader, VorbisComments};
+use std::time::Duration;
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum VorbisLanguageError {
+    #[error("invalid BCP 47 language tag: {0}")]
+    InvalidLanguageTag(String),
+}
+
+fn is_alpha(part: &str, min: usize, max: usize) -> bool {
+    (min..=max).contains(&part.len()) && part.bytes().all(|b| b.is_ascii_alphabetic())
+}
+
+fn is_alphanumeric(part: &str, min: usize, max: usize) -> bool {
+    (min..=max).contains(&part.len()) && part.bytes().all(|b| b.is_ascii_alphanumeric())
+}
+
+/// Check the structure of a BCP 47 language tag such as "en", "pt-BR",
+/// "zh-Hant-TW" or "sl-rozaj-biske": language, extended languages, script,
+/// region, variants, extensions and private use subtags, in that order.
+/// Irregular grandfathered tags are not accepted.
+pub fn is_valid_language_tag(tag: &str) -> bool {
+    let parts: Vec<&str> = tag.split('-').collect();
+    let mut parts = parts.iter().peekable();
+    let language = match parts.next() {
+        Some(language) => *language,
+        None => return false,
+    };
+    if language.eq_ignore_ascii_case("x") {
+        return parts.len() > 0 && parts.all(|part| is_alphanumeric(part, 1, 8));
+    }
+    if !is_alpha(language, 2, 3) && !is_alpha(language, 5, 8) {
+        return false;
+    }
+    if language.len() <= 3 {
+        for _ in 0..3 {
+            match parts.peek() {
+                Some(part) if is_alpha(part, 3, 3) => parts.next(),
+                _ => break,
+            };
+        }
+    }
+    if let Some(part) = parts.peek() {
+        if is_alpha(part, 4, 4) {
+            parts.next();
+        }
+    }
+    if let Some(part) = parts.peek() {
+        if is_alpha(part, 2, 2) || (part.len() == 3 && part.bytes().all(|b| b.is_ascii_digit())) {
+            parts.next();
+        }
+    }
+    while let Some(part) = parts.peek() {
+        let digit_variant =
+            part.len() == 4 && part.as_bytes()[0].is_ascii_digit() && is_alphanumeric(part, 4, 4);
+        if is_alphanumeric(part, 5, 8) || digit_variant {
+            parts.next();
+        } else {
+            break;
+        }
+    }
+    while let Some(singleton) = parts.next() {
+        if singleton.len() != 1 || !singleton.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            return false;
+        }
+        let private = singleton.eq_ignore_ascii_case("x");
+        let (min, max) = if private { (1, 8) } else { (2, 8) };
+        let mut count = 0;
+        while let Some(part) = parts.peek() {
+            if part.len() == 1 && !private {
+                break;
+            }
+            if !is_alphanumeric(part, min, max) {
+                return false;
+            }
+            parts.next();
+            count += 1;
+        }
+        if count == 0 {
+            return false;
+        }
+    }
+    true
+}
+
+/// A reference to a transcript, such as a URL or an archive identifier.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transcript {
+    /// Lowercase language from a `TRANSCRIPT-<LANGUAGE>` tag name, if any.
+    pub language: Option<String>,
+    pub reference: String,
+}
+
+/// A segment of a recording, stored in a SEGMENT tag as
+/// "START | END | SPEAKER | DESCRIPTION" with "HH:MM:SS.mmm" times. END and
+/// SPEAKER may be left empty.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Segment {
+    pub start: Duration,
+    pub end: Option<Duration>,
+    pub speaker: Option<String>,
+    pub description: String,
+}
+
+impl Segment {
+    pub fn parse(value: &str) -> Option<Segment> {
+        let fields: Vec<&str> = value.splitn(4, '|').map(|field| field.trim()).collect();
+        if fields.len() != 4 {
+            return None;
+        }
+        let end = match fields[1] {
+            "" => None,
+            end => Some(parse_timestamp(end)?),
+        };
+        Some(Segment {
+            start: parse_timestamp(fields[0])?,
+            end,
+            speaker: Some(fields[2])
+                .filter(|speaker| !speaker.is_empty())
+                .map(String::from),
+            description: fields[3].to_string(),
+        })
+    }
+
+    pub fn to_tag_value(&self) -> String {
+        format!(
+            "{} | {} | {} | {}",
+            format_timestamp(self.start),
+            self.end.map(format_timestamp).unwrap_or_default(),
+            self.speaker.as_deref().unwrap_or(""),
+            self.description
+        )
+    }
+}
+
+fn transcript_language(key: &str) -> Option<Option<String>> {
+    tag_language(key, keys::TRANSCRIPT)
+}
+
+pub trait VorbisSpokenWord {
+    /// All LANGUAGE values, one language per value.
+    fn languages(&self) -> Vec<String>;
+    /// Replace LANGUAGE with one value per language, after checking they
+    /// are all valid BCP 47 tags.
+    fn set_languages(&mut self, languages: &[&str]) -> Result<(), VorbisLanguageError>;
+    fn speakers(&self) -> Vec<String>;
+    fn set_speakers(&mut self, speakers: &[&str]);
+    /// All transcript references, in tag order.
+    fn transcripts(&self) -> Vec<Transcript>;
+    /// Add a transcript reference, written as TRANSCRIPT or
+    /// `TRANSCRIPT-<LANGUAGE>`.
+    fn add_transcript(&mut self, transcript: &Transcript);
+    fn clear_transcripts(&mut self);
+    /// The SEGMENT tags that parse, in tag order.
+    fn segments(&self) -> Vec<Segment>;
+    /// Replace the SEGMENT tags, sorted by start time.
+    fn set_segments(&mut self, segments: &[Segment]);
+}
+
+impl VorbisSpokenWord for CommentHeader {
+    fn languages(&self) -> Vec<String> {
+        self.get_tag_multi(keys::LANGUAGE)
+    }
+
+    fn set_languages(&mut self, languages: &[&str]) -> Result<(), VorbisLanguageError> {
+        if let Some(invalid) = languages.iter().find(|tag| !is_valid_language_tag(tag)) {
+            return Err(VorbisLanguageError::InvalidLanguageTag(invalid.to_string()));
+        }
+        self.clear_tag(keys::LANGUAGE);
+        self.add_tag_multi(keys::LANGUAGE, languages);
+        Ok(())
+    }
+
+    fn speakers(&self) -> Vec<String> {
+        self.get_tag_multi(keys::SPEAKER)
+    }
+
+    fn set_speakers(&mut self, speakers: &[&str]) {
+        self.clear_tag(keys::SPEAKER);
+        self.add_tag_multi(keys::SPEAKER, speakers);
+    }
+
+    fn transcripts(&self) -> Vec<Transcript> {
+        self.iter()
+            .filter_map(|(key, value)| {
+                transcript_language(key).map(|language| Transcript {
+                    language,
+                    reference: value.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    fn add_transcript(&mut self, transcript: &Transcript) {
+        let key = match &transcript.language {
+            Some(language) => format!("{}-{}", keys::TRANSCRIPT, language),
+            None => keys::TRANSCRIPT.to_string(),
+        };
+        self.add_tag_single(&key, &transcript.reference);
+    }
+
+    fn clear_transcripts(&mut self) {
+        self.retain(|key, _| transcript_language(key).is_none());
+    }
+
+    fn segments(&self) -> Vec<Segment> {
+        self.get_tag_multi(keys::SEGMENT)
+            .iter()
+            .filter_map(|value| Segment::parse(value))
+            .collect()
+    }
+
+    fn set_segments(&mut self, segments: &[Segment]) {
+        let mut segments = segments.to_vec();
+        segments.sort_by_key(|segment| segment.start);
+        self.clear_tag(keys::SEGMENT);
+        for segment in segments.iter() {
+            self.add_tag_single(keys::SEGMENT, &segment.to_tag_value());
+        }
+    }
+}
@@ -0,0 +1,188 @@
+// Counting the pages, packets and bytes going through a rewrite
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// Figures of a rewrite, for logs and for checking batch jobs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RewriteStats {
+    pub pages_read: u64,
+    pub pages_written: u64,
+    /// Packets written other than the new comment header.
+    pub packets_copied: u64,
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+    /// The header was patched in the file with
+    /// `safe_patch_comment_header_in_place` rather than rewritten.
+    pub in_place: bool,
+    /// The pages after the headers were transferred without being parsed,
+    /// see `ReplaceOptions::raw_copy`.
+    pub raw_copy: bool,
+    pub warnings: Vec<RewriteWarning>,
+}
+
+/// Something done to the output that the input didn't call for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RewriteWarning {
+    /// The input ended in the middle of a page, which was left out.
+    PartialPageDropped,
+    /// The input ended in the middle of a page, the `packets` complete
+    /// packets on it were kept and the rest left out, see
+    /// `ReplaceOptions::salvage_truncated_page`.
+    PartialPageSalvaged { packets: usize },
+    /// The end-of-stream flag missing from the input was set on the last
+    /// page, see `ReplaceOptions::mark_end_of_stream`.
+    EndOfStreamMarked,
+}
+
+/// Counts the pages and the packet ends of the data going through, page
+/// headers are followed from the start of the data.
+#[derive(Default)]
+struct PageCounter {
+    header: Vec<u8>,
+    /// Body bytes left in the current page.
+    remaining: usize,
+    /// Keep the bytes of the current page in `current` once its header is
+    /// complete.
+    keep: bool,
+    current: Vec<u8>,
+    bytes: u64,
+    pages: u64,
+    packets: u64,
+}
+
+impl PageCounter {
+    fn feed(&mut self, mut data: &[u8]) {
+        self.bytes += data.len() as u64;
+        while !data.is_empty() {
+            if self.remaining > 0 {
+                let skipped = self.remaining.min(data.len());
+                if self.keep {
+                    self.current.extend(&data[..skipped]);
+                }
+                self.remaining -= skipped;
+                data = &data[skipped..];
+                continue;
+            }
+            let needed = match self.header.get(26) {
+                Some(segments) => 27 + *segments as usize - self.header.len(),
+                None => 27 - self.header.len(),
+            };
+            let taken = needed.min(data.len());
+            self.header.extend(&data[..taken]);
+            data = &data[taken..];
+            // Not page data, look for the next capture pattern
+            while self.header.len() >= 4 && &self.header[..4] != b"OggS" {
+                self.header.remove(0);
+            }
+            if self.header.len() > 26 && self.header.len() == 27 + self.header[26] as usize {
+                let lacing = &self.header[27..];
+                self.pages += 1;
+                self.packets += lacing.iter().filter(|lacing| **lacing < 255).count() as u64;
+                self.remaining = lacing.iter().map(|lacing| *lacing as usize).sum();
+                self.current.clear();
+                if self.keep && self.remaining > 0 {
+                    self.current.append(&mut self.header);
+                }
+                self.header.clear();
+            }
+        }
+    }
+
+    /// The bytes of the page not read to its end, from its capture pattern.
+    fn partial_page(&self) -> &[u8] {
+        if self.remaining > 0 {
+            &self.current
+        } else {
+            &self.header
+        }
+    }
+}
+
+/// A reader or writer counting what goes through it. Bytes read again
+/// after seeking back are only counted once, and the page headers are
+/// followed again from the next capture pattern after seeking forward.
+pub(crate) struct Counted<T> {
+    inner: T,
+    counter: PageCounter,
+    position: u64,
+    /// The end of what the counter has been fed.
+    farthest: u64,
+}
+
+impl<T> Counted<T> {
+    pub(crate) fn new(inner: T) -> Counted<T> {
+        Counted {
+            inner,
+            counter: PageCounter::default(),
+            position: 0,
+            farthest: 0,
+        }
+    }
+
+    fn feed(&mut self, data: &[u8]) {
+        let end = self.position + data.len() as u64;
+        if self.position > self.farthest {
+            self.counter.header.clear();
+            self.counter.current.clear();
+            self.counter.remaining = 0;
+            self.farthest = self.position;
+        }
+        if end > self.farthest {
+            let seen = (self.farthest - self.position) as usize;
+            self.counter.feed(&data[seen..]);
+            self.farthest = end;
+        }
+        self.position = end;
+    }
+
+    /// Keep the bytes of the page being read, for `partial_page`.
+    pub(crate) fn keep_partial_page(&mut self) {
+        self.counter.keep = true;
+    }
+
+    /// What was read of a page the data ended in, after `keep_partial_page`.
+    pub(crate) fn partial_page(&self) -> &[u8] {
+        self.counter.partial_page()
+    }
+}
+
+impl<T: Read> Read for Counted<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.feed(&buf[..read]);
+        Ok(read)
+    }
+}
+
+impl<T: Seek> Seek for Counted<T> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        // Positions are kept relative to where the data started
+        let before = self.inner.stream_position()?;
+        let after = self.inner.seek(pos)?;
+        self.position = (self.position as i128 + after as i128 - before as i128).max(0) as u64;
+        Ok(after)
+    }
+}
+
+impl<T: Write> Write for Counted<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.feed(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl RewriteStats {
+    /// Fill in the figures counted on the input and the output.
+    pub(crate) fn count<R, W>(&mut self, f_in: &Counted<R>, f_out: &Counted<W>) {
+        self.pages_read = f_in.counter.pages;
+        self.bytes_read = f_in.counter.bytes;
+        self.pages_written = f_out.counter.pages;
+        self.bytes_written = f_out.counter.bytes;
+        self.packets_copied = f_out.counter.packets.saturating_sub(1);
+    }
+}
@@ -0,0 +1,216 @@
+// Ordered tag storage with a case-insensitive index
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// The entries of a header in insertion order, plus the positions of the
+/// entries of each tag by lowercase name. Removed entries leave a hole so
+/// the positions of the others stay valid, the holes are compacted away
+/// once they outnumber the live entries.
+#[derive(Clone, Default)]
+pub(crate) struct TagStore {
+    entries: Vec<Option<(String, String)>>,
+    index: HashMap<String, Vec<usize>>,
+    live: usize,
+}
+
+fn fold(key: &str) -> String {
+    key.to_lowercase()
+}
+
+impl TagStore {
+    pub(crate) fn from_vec(comments: Vec<(String, String)>) -> TagStore {
+        let mut store = TagStore::default();
+        for (key, value) in comments {
+            store.push(key, value);
+        }
+        store
+    }
+
+    pub(crate) fn into_vec(self) -> Vec<(String, String)> {
+        self.entries.into_iter().flatten().collect()
+    }
+
+    pub(crate) fn push(&mut self, key: String, value: String) {
+        self.index
+            .entry(fold(&key))
+            .or_default()
+            .push(self.entries.len());
+        self.entries.push(Some((key, value)));
+        self.live += 1;
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.live
+    }
+
+    pub(crate) fn contains(&self, tag: &str) -> bool {
+        self.index.contains_key(&fold(tag))
+    }
+
+    /// Every slot, holes included, for indexing with `positions`.
+    pub(crate) fn slots(&self) -> &[Option<(String, String)>] {
+        &self.entries
+    }
+
+    /// Positions of the entries of the tag, in order.
+    pub(crate) fn positions(&self, tag: &str) -> &[usize] {
+        self.index
+            .get(&fold(tag))
+            .map_or(&[], |positions| positions.as_slice())
+    }
+
+    pub(crate) fn iter(&self) -> impl DoubleEndedIterator<Item = &(String, String)> {
+        self.entries.iter().flatten()
+    }
+
+    /// The lowercase tag names, in no particular order.
+    pub(crate) fn names(&self) -> impl Iterator<Item = &String> {
+        self.index.keys()
+    }
+
+    /// The key of the first entry of each tag, in order of first appearance.
+    pub(crate) fn first_keys(&self) -> Vec<&str> {
+        let mut first = self
+            .index
+            .values()
+            .map(|positions| positions[0])
+            .collect::<Vec<usize>>();
+        first.sort_unstable();
+        first
+            .into_iter()
+            .filter_map(|position| self.entries[position].as_ref())
+            .map(|(key, _)| key.as_str())
+            .collect()
+    }
+
+    /// The values of every entry, the keys can't be changed this way.
+    pub(crate) fn values_mut(&mut self) -> impl Iterator<Item = (&str, &mut String)> {
+        self.entries
+            .iter_mut()
+            .flatten()
+            .map(|(key, value)| (key.as_str(), value))
+    }
+
+    pub(crate) fn modify_tag<F: FnMut(&mut String)>(&mut self, tag: &str, mut modify: F) {
+        if let Some(positions) = self.index.get(&fold(tag)) {
+            for position in positions {
+                if let Some((_, value)) = self.entries[*position].as_mut() {
+                    modify(value);
+                }
+            }
+        }
+    }
+
+    /// Remove all entries of the tag, returning how many there were.
+    pub(crate) fn remove_tag(&mut self, tag: &str) -> usize {
+        let positions = match self.index.remove(&fold(tag)) {
+            Some(positions) => positions,
+            None => return 0,
+        };
+        for position in positions.iter() {
+            self.entries[*position] = None;
+        }
+        self.live -= positions.len();
+        self.maybe_compact();
+        positions.len()
+    }
+
+    /// Keep the entries of the tag for which `keep(value)` is true,
+    /// returning how many were removed.
+    pub(crate) fn retain_tag<F: FnMut(&str) -> bool>(&mut self, tag: &str, mut keep: F) -> usize {
+        let folded = fold(tag);
+        let positions = match self.index.get_mut(&folded) {
+            Some(positions) => positions,
+            None => return 0,
+        };
+        let entries = &mut self.entries;
+        let before = positions.len();
+        positions.retain(|position| {
+            let kept = entries[*position]
+                .as_ref()
+                .is_some_and(|(_, value)| keep(value));
+            if !kept {
+                entries[*position] = None;
+            }
+            kept
+        });
+        let removed = before - positions.len();
+        if positions.is_empty() {
+            self.index.remove(&folded);
+        }
+        self.live -= removed;
+        self.maybe_compact();
+        removed
+    }
+
+    /// Keep the entries for which `keep(key, value)` is true, returning how
+    /// many were removed.
+    pub(crate) fn retain<F: FnMut(&str, &str) -> bool>(&mut self, mut keep: F) -> usize {
+        let before = self.live;
+        for slot in self.entries.iter_mut() {
+            if let Some((key, value)) = slot {
+                if !keep(key, value) {
+                    *slot = None;
+                    self.live -= 1;
+                }
+            }
+        }
+        if self.live != before {
+            self.reindex();
+        }
+        before - self.live
+    }
+
+    /// Replace the key of every entry for which `rename` returns a new one,
+    /// returning how many were renamed.
+    pub(crate) fn rename_keys<F: FnMut(&str) -> Option<String>>(&mut self, mut rename: F) -> usize {
+        let mut renamed = 0;
+        for (key, _) in self.entries.iter_mut().flatten() {
+            if let Some(new_key) = rename(key) {
+                *key = new_key;
+                renamed += 1;
+            }
+        }
+        if renamed > 0 {
+            self.reindex();
+        }
+        renamed
+    }
+
+    fn maybe_compact(&mut self) {
+        if self.entries.len() > 32 && self.live * 2 < self.entries.len() {
+            self.reindex();
+        }
+    }
+
+    /// Drop the holes and rebuild the index.
+    fn reindex(&mut self) {
+        let entries = std::mem::take(&mut self.entries);
+        *self = TagStore::from_vec(entries.into_iter().flatten().collect());
+    }
+}
+
+impl PartialEq for TagStore {
+    fn eq(&self, other: &TagStore) -> bool {
+        self.live == other.live && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for TagStore {}
+
+impl Hash for TagStore {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.live.hash(state);
+        for entry in self.iter() {
+            entry.hash(state);
+        }
+    }
+}
+
+impl fmt::Debug for TagStore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,655 @@
+// Editing the header pages of a file in place, copying every other page as is
+
+use crate::cancel;
+use crate::granule::{GranuleCounter, GranuleMode};
+use crate::page::{self, paginate, parse_pages, salvage_packets, Page, PageError, PageReadError};
+use crate::parse::parse_comment_header;
+use crate::stats::{Counted, RewriteStats, RewriteWarning};
+use crate::{
+    fit_comment_packet, fit_replacement, safe_make_comment_header, CommentHeader, ReplaceOptions,
+    ReplaceReport, ShrinkStrategy, VorbisMakeCommentError, VorbisReadCommentError,
+    VorbisReplaceCommentError,
+};
+
+use std::fs::OpenOptions;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum VorbisSurgicalError {
+    #[error("invalid page at offset {0}")]
+    MalformedPage(usize),
+    #[error("the file ends in the page at offset {0}")]
+    Truncated(usize),
+    #[error("the file does not start with a vorbis stream")]
+    NotVorbis,
+    #[error("the header packets don't have pages of their own")]
+    HeadersNotPageAligned,
+    #[error("failed to make the comment header")]
+    Make(#[from] VorbisMakeCommentError),
+    /// The new comment header needs `size` bytes where the old one had
+    /// only `available`.
+    #[error("comment header of {size} bytes does not fit in the {available} bytes available")]
+    DoesNotFit { size: usize, available: usize },
+    #[error("failed to read or write the file")]
+    Io(#[from] io::Error),
+}
+
+/// Number of packets ending on the page, and whether the page ends with one.
+fn packet_ends(page: &Page) -> (usize, bool) {
+    let ends = page.lacing.iter().filter(|lacing| **lacing < 255).count();
+    (ends, page.lacing.last().map(|l| *l < 255).unwrap_or(false))
+}
+
+fn shift_page(page: &mut Page, delta: i64) {
+    page.sequence = (page.sequence as i64).wrapping_add(delta) as u32;
+    page.checksum = page.compute_checksum();
+}
+
+/// Add `delta` to the sequence number of every page of stream `serial`
+/// numbered `from_sequence` or later, updating their checksums. All other
+/// bytes are kept as they are.
+pub fn shift_page_sequences(
+    data: &[u8],
+    serial: u32,
+    from_sequence: u32,
+    delta: i64,
+) -> Result<Vec<u8>, VorbisSurgicalError> {
+    let parsed = parse_pages(data);
+    if let Some((offset, _)) = parsed.error {
+        return Err(VorbisSurgicalError::MalformedPage(offset));
+    }
+    let mut out = Vec::with_capacity(data.len());
+    for (offset, mut page) in parsed.pages {
+        if delta != 0 && page.serial == serial && page.sequence >= from_sequence {
+            shift_page(&mut page, delta);
+            out.extend(page.to_bytes());
+        } else {
+            out.extend(&data[offset..offset + page.size()]);
+        }
+    }
+    Ok(out)
+}
+
+/// Replace the comment header by rewriting only the pages holding the
+/// comment and setup headers. Later pages of the stream are copied with their
+/// sequence numbers shifted when the header needs more or fewer pages than
+/// before, so the output never has a gap, and pages of other streams are
+/// copied unchanged.
+///
+/// This needs the identification header alone on the first page and the
+/// setup header ending a page, as the Vorbis Ogg mapping requires.
+pub fn safe_surgical_replace_comment_header(
+    data: &[u8],
+    new_header: &CommentHeader,
+) -> Result<Vec<u8>, VorbisSurgicalError> {
+    let parsed = parse_pages(data);
+    if let Some((offset, _)) = parsed.error {
+        return Err(VorbisSurgicalError::MalformedPage(offset));
+    }
+    let pages = parsed.pages;
+    let first = match pages.first() {
+        Some((_, page)) if page.is_first() && page.body.starts_with(b"\x01vorbis") => page,
+        _ => return Err(VorbisSurgicalError::NotVorbis),
+    };
+    let serial = first.serial;
+    if packet_ends(first) != (1, true) {
+        return Err(VorbisSurgicalError::HeadersNotPageAligned);
+    }
+
+    // Find the pages holding the comment and setup headers
+    let mut header_pages = vec![];
+    let mut packets: Vec<Vec<u8>> = vec![vec![]];
+    for (index, (_, page)) in pages.iter().enumerate().skip(1) {
+        if page.serial != serial {
+            continue;
+        }
+        header_pages.push(index);
+        let mut pos = 0;
+        for lacing in page.lacing.iter() {
+            let len = *lacing as usize;
+            packets
+                .last_mut()
+                .unwrap()
+                .extend(&page.body[pos..pos + len]);
+            pos += len;
+            if len < 255 {
+                packets.push(vec![]);
+            }
+        }
+        if packets.len() > 2 {
+            break;
+        }
+    }
+    let last_page = match header_pages.last() {
+        Some(index) => &pages[*index].1,
+        None => return Err(VorbisSurgicalError::NotVorbis),
+    };
+    // Exactly the comment and setup headers, then an empty packet to come
+    if packets.len() != 3 || !packets[2].is_empty() || !packet_ends(last_page).1 {
+        return Err(VorbisSurgicalError::HeadersNotPageAligned);
+    }
+
+    let comment = safe_make_comment_header(new_header)?;
+    let first_sequence = first.sequence.wrapping_add(1);
+    let new_pages = paginate(&[&comment, &packets[1]], serial, first_sequence, 0);
+    let last_sequence = last_page.sequence;
+    let delta = new_pages.len() as i64 - header_pages.len() as i64;
+
+    let mut out = Vec::with_capacity(data.len() + comment.len());
+    for (index, (offset, page)) in pages.iter().enumerate() {
+        if header_pages.contains(&index) {
+            if index == header_pages[0] {
+                for new_page in new_pages.iter() {
+                    out.extend(new_page.to_bytes());
+                }
+            }
+        } else if delta != 0 && page.serial == serial && page.sequence > last_sequence {
+            let mut page = page.clone();
+            shift_page(&mut page, delta);
+            out.extend(page.to_bytes());
+        } else {
+            out.extend(&data[*offset..offset + page.size()]);
+        }
+    }
+    Ok(out)
+}
+
+pub fn surgical_replace_comment_header(data: &[u8], new_header: &CommentHeader) -> Vec<u8> {
+    safe_surgical_replace_comment_header(data, new_header).unwrap()
+}
+
+/// Read the page at `offset`, the current position of `f`, `None` at the
+/// end of the file.
+fn read_page<R: Read>(f: &mut R, offset: u64) -> Result<Option<Page>, VorbisSurgicalError> {
+    match page::read_page(f, false) {
+        Ok(page) => Ok(page.map(|(page, _)| page)),
+        Err(PageReadError::Io(err)) => Err(VorbisSurgicalError::Io(err)),
+        Err(PageReadError::Page(PageError::Truncated)) => {
+            Err(VorbisSurgicalError::Truncated(offset as usize))
+        }
+        Err(PageReadError::Page(_)) => Err(VorbisSurgicalError::MalformedPage(offset as usize)),
+    }
+}
+
+/// The pages of a file up to the end of the setup header, laid out as
+/// `safe_surgical_replace_comment_header` requires.
+struct HeaderPages {
+    serial: u32,
+    first_sequence: u32,
+    /// The pages of the stream holding the comment and setup headers.
+    pages: Vec<Page>,
+    ident: Vec<u8>,
+    comment: Vec<u8>,
+    setup: Vec<u8>,
+    /// Bytes read, the first page and pages of other streams included.
+    read: u64,
+}
+
+/// Read the pages of `f_in` up to the end of the setup header, passing the
+/// first page and pages of other streams met on the way to `kept`.
+fn read_header_pages<T, F>(f_in: &mut T, mut kept: F) -> Result<HeaderPages, VorbisSurgicalError>
+where
+    T: Read,
+    F: FnMut(&Page) -> io::Result<()>,
+{
+    let mut offset = 0;
+    let first = match read_page(f_in, offset)? {
+        Some(page) if page.is_first() && page.body.starts_with(b"\x01vorbis") => page,
+        _ => return Err(VorbisSurgicalError::NotVorbis),
+    };
+    if packet_ends(&first) != (1, true) {
+        return Err(VorbisSurgicalError::HeadersNotPageAligned);
+    }
+    offset += first.size() as u64;
+    kept(&first)?;
+
+    let mut pages = vec![];
+    let mut packets: Vec<Vec<u8>> = vec![vec![]];
+    while packets.len() < 3 {
+        let page = match read_page(f_in, offset)? {
+            Some(page) => page,
+            None => return Err(VorbisSurgicalError::NotVorbis),
+        };
+        offset += page.size() as u64;
+        if page.serial != first.serial {
+            kept(&page)?;
+            continue;
+        }
+        let mut pos = 0;
+        for lacing in page.lacing.iter() {
+            let len = *lacing as usize;
+            packets
+                .last_mut()
+                .unwrap()
+                .extend(&page.body[pos..pos + len]);
+            pos += len;
+            if len < 255 {
+                packets.push(vec![]);
+            }
+        }
+        pages.push(page);
+    }
+    if packets.len() != 3 || !packets[2].is_empty() || !packet_ends(pages.last().unwrap()).1 {
+        return Err(VorbisSurgicalError::HeadersNotPageAligned);
+    }
+    let setup = packets.swap_remove(1);
+    let comment = packets.swap_remove(0);
+    Ok(HeaderPages {
+        serial: first.serial,
+        first_sequence: first.sequence,
+        pages,
+        ident: first.body,
+        comment,
+        setup,
+        read: offset,
+    })
+}
+
+/// The granule position of `page` counted from the packets ending on it, -1
+/// if none does. `partial` holds the start of a packet continued from the
+/// previous page.
+fn count_granule(
+    page: &Page,
+    counter: &mut GranuleCounter,
+    partial: &mut Vec<u8>,
+) -> Result<u64, VorbisReplaceCommentError> {
+    let mut granule = None;
+    let mut pos = 0;
+    for lacing in page.lacing.iter() {
+        let len = *lacing as usize;
+        partial.extend(&page.body[pos..pos + len]);
+        pos += len;
+        if len < 255 {
+            let position = counter
+                .next(partial)
+                .map_err(VorbisReadCommentError::from)?;
+            granule = Some(position);
+            partial.clear();
+        }
+    }
+    Ok(match granule {
+        Some(position) if page.is_last() => position.min(page.granule_position),
+        Some(position) => position,
+        None => u64::MAX,
+    })
+}
+
+/// A page of its own for the complete packets of `data`, what was read of
+/// the page of stream `serial` the input ended in, with the granule position
+/// counted after them. `None` if there are none.
+fn salvage_page(
+    data: &[u8],
+    serial: u32,
+    counter: &mut GranuleCounter,
+    delta: i64,
+) -> Result<Option<(Vec<Page>, usize)>, VorbisReplaceCommentError> {
+    let salvaged = match salvage_packets(data) {
+        Some(salvaged) if salvaged.serial == serial && !salvaged.packets.is_empty() => salvaged,
+        _ => return Ok(None),
+    };
+    let mut granule = 0;
+    for packet in salvaged.packets.iter() {
+        granule = counter.next(packet).map_err(VorbisReadCommentError::from)?;
+    }
+    let packets = salvaged
+        .packets
+        .iter()
+        .map(|packet| packet.as_slice())
+        .collect::<Vec<&[u8]>>();
+    let sequence = (salvaged.sequence as i64).wrapping_add(delta) as u32;
+    let pages = paginate(&packets, serial, sequence, granule);
+    Ok(Some((pages, packets.len())))
+}
+
+/// `io::copy` checking the cancel token between chunks.
+fn copy_cancellable<T: Read, W: Write>(
+    f_in: &mut T,
+    f_out: &mut W,
+    options: &ReplaceOptions,
+) -> Result<(), VorbisReplaceCommentError> {
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        if cancel::is_cancelled(&options.cancel) {
+            return Err(VorbisReplaceCommentError::Cancelled);
+        }
+        let read = match f_in.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err.into()),
+        };
+        f_out.write_all(&buffer[..read])?;
+    }
+}
+
+/// Copy `f_in` to `f_out` page by page, writing new pages only for the
+/// comment and setup headers, for `ReplaceOptions::copy_pages` and
+/// `ReplaceOptions::raw_copy`. The new header is `make_header` of the old.
+pub(crate) fn write_copied_pages<T, W, H>(
+    f_in: T,
+    f_out: W,
+    make_header: H,
+    options: &ReplaceOptions,
+) -> Result<ReplaceReport, VorbisReplaceCommentError>
+where
+    T: Read,
+    W: Write,
+    H: FnOnce(&CommentHeader) -> CommentHeader,
+{
+    let mut f_in = Counted::new(f_in);
+    if options.salvage_truncated_page {
+        f_in.keep_partial_page();
+    }
+    let mut f_out = Counted::new(f_out);
+    // The first page and pages of other streams met on the way are copied
+    // right away
+    let headers = read_header_pages(&mut f_in, |page| f_out.write_all(&page.to_bytes()))?;
+    let (first_sequence, serial) = (headers.first_sequence, headers.serial);
+    let header_pages = headers.pages.len();
+    let mut offset = headers.read;
+    let last_page = headers.pages.last().unwrap();
+    let packets = [headers.comment, headers.setup];
+
+    let old_header = parse_comment_header(&packets[0]).map_err(VorbisReadCommentError::from)?;
+    let new_header = make_header(&old_header);
+    let (comment, shrink, slack) =
+        fit_replacement(&new_header, old_header, packets[0].len(), options);
+    let unchanged = comment == packets[0];
+    let new_pages = paginate(
+        &[&comment, &packets[1]],
+        serial,
+        first_sequence.wrapping_add(1),
+        0,
+    );
+    for page in new_pages.iter() {
+        f_out.write_all(&page.to_bytes())?;
+    }
+    let delta = new_pages.len() as i64 - header_pages as i64;
+    // Also counted to give salvaged packets their granule position
+    let recompute = options.granule_mode == GranuleMode::Recompute;
+    let mut counter = None;
+    if recompute || options.salvage_truncated_page {
+        let mut headers_counter = GranuleCounter::new();
+        for packet in [&headers.ident, &packets[0], &packets[1]] {
+            headers_counter
+                .next(packet)
+                .map_err(VorbisReadCommentError::from)?;
+        }
+        counter = Some((headers_counter, vec![]));
+    }
+    if options.raw_copy && delta == 0 && !recompute {
+        if options.cancel.is_some() {
+            copy_cancellable(&mut f_in, &mut f_out, options)?;
+        } else {
+            io::copy(&mut f_in, &mut f_out)?;
+        }
+        f_out.flush()?;
+        let mut report = ReplaceReport {
+            truncated: false,
+            shrink,
+            slack,
+            unchanged,
+            stats: RewriteStats {
+                raw_copy: true,
+                ..Default::default()
+            },
+        };
+        report.stats.count(&f_in, &f_out);
+        return Ok(report);
+    }
+
+    // Pages are written one behind, so the last one can still be marked as
+    // the end of the stream
+    let mut end_of_stream = last_page.is_last();
+    let mut pending: Option<Page> = None;
+    let mut warnings = vec![];
+    let mut cut_short = false;
+    loop {
+        if cancel::is_cancelled(&options.cancel) {
+            return Err(VorbisReplaceCommentError::Cancelled);
+        }
+        let mut page = match read_page(&mut f_in, offset) {
+            Ok(Some(page)) => page,
+            // The last page was cut short, treat it like the end of the file
+            Ok(None) => break,
+            Err(VorbisSurgicalError::Truncated(_)) => {
+                cut_short = true;
+                break;
+            }
+            Err(err) => return Err(err.into()),
+        };
+        offset += page.size() as u64;
+        if page.serial == serial {
+            end_of_stream |= page.is_last();
+            if let Some((counter, partial)) = counter.as_mut() {
+                let granule = count_granule(&page, counter, partial)?;
+                if recompute {
+                    page.granule_position = granule;
+                }
+            }
+            if delta != 0 || recompute {
+                shift_page(&mut page, delta);
+            }
+        }
+        if let Some(previous) = pending.replace(page) {
+            f_out.write_all(&previous.to_bytes())?;
+        }
+    }
+    if cut_short {
+        let salvaged = match counter.as_mut() {
+            Some((counter, _)) if options.salvage_truncated_page => {
+                salvage_page(f_in.partial_page(), serial, counter, delta)?
+            }
+            _ => None,
+        };
+        match salvaged {
+            Some((pages, packets)) => {
+                warnings.push(RewriteWarning::PartialPageSalvaged { packets });
+                for page in pages {
+                    if let Some(previous) = pending.replace(page) {
+                        f_out.write_all(&previous.to_bytes())?;
+                    }
+                }
+            }
+            None => warnings.push(RewriteWarning::PartialPageDropped),
+        }
+    }
+    if let Some(mut last) = pending {
+        if !end_of_stream && options.mark_end_of_stream && last.serial == serial {
+            last.flags |= Page::LAST;
+            last.checksum = last.compute_checksum();
+            warnings.push(RewriteWarning::EndOfStreamMarked);
+        }
+        f_out.write_all(&last.to_bytes())?;
+    }
+    f_out.flush()?;
+    let mut report = ReplaceReport {
+        truncated: !end_of_stream,
+        shrink,
+        slack,
+        unchanged,
+        stats: RewriteStats {
+            warnings,
+            ..Default::default()
+        },
+    };
+    report.stats.count(&f_in, &f_out);
+    Ok(report)
+}
+
+/// Overwrite the comment header of `f` without moving any other byte, when
+/// the new packet is no larger than the old one. The packet is padded to the
+/// old size as with `ShrinkStrategy::PaddingTag`, so every page keeps its
+/// size and lacing and only the pages holding the comment header are
+/// written again, with new checksums. Nothing is written when they would be
+/// written unchanged. Fails with `DoesNotFit`, leaving `f` untouched, when
+/// the new header is larger.
+pub fn safe_patch_comment_header_in_place<F: Read + Write + Seek>(
+    f: &mut F,
+    new_header: &CommentHeader,
+) -> Result<ReplaceReport, VorbisSurgicalError> {
+    let mut offset = f.seek(SeekFrom::Start(0))?;
+    let serial = match read_page(f, offset)? {
+        Some(page) if page.is_first() && page.body.starts_with(b"\x01vorbis") => {
+            if packet_ends(&page) != (1, true) {
+                return Err(VorbisSurgicalError::HeadersNotPageAligned);
+            }
+            offset += page.size() as u64;
+            page.serial
+        }
+        _ => return Err(VorbisSurgicalError::NotVorbis),
+    };
+
+    // The pages holding the comment header, with the part of their body it takes
+    let mut comment_pages = vec![];
+    let mut old_len = 0;
+    let mut pages_read = 1;
+    'pages: loop {
+        let page = match read_page(f, offset)? {
+            Some(page) => page,
+            None => return Err(VorbisSurgicalError::NotVorbis),
+        };
+        pages_read += 1;
+        let page_offset = offset;
+        offset += page.size() as u64;
+        if page.serial != serial {
+            continue;
+        }
+        let mut end = 0;
+        for lacing in page.lacing.iter() {
+            end += *lacing as usize;
+            if *lacing < 255 {
+                old_len += end;
+                comment_pages.push((page_offset, page, end));
+                break 'pages;
+            }
+        }
+        old_len += end;
+        comment_pages.push((page_offset, page, end));
+    }
+
+    let (comment, shrink, slack) =
+        fit_comment_packet(new_header, old_len, ShrinkStrategy::PaddingTag, 0);
+    if comment.len() > old_len {
+        return Err(VorbisSurgicalError::DoesNotFit {
+            size: comment.len(),
+            available: old_len,
+        });
+    }
+    let old_comment = comment_pages
+        .iter()
+        .flat_map(|(_, page, end)| page.body[..*end].iter().copied())
+        .collect::<Vec<u8>>();
+    let unchanged = comment == old_comment;
+    let mut stats = RewriteStats {
+        pages_read,
+        bytes_read: offset,
+        in_place: true,
+        ..Default::default()
+    };
+    if unchanged {
+        return Ok(ReplaceReport {
+            shrink,
+            slack,
+            unchanged,
+            stats,
+            ..Default::default()
+        });
+    }
+    let mut pos = 0;
+    for (page_offset, mut page, end) in comment_pages {
+        page.body[..end].copy_from_slice(&comment[pos..pos + end]);
+        pos += end;
+        page.checksum = page.compute_checksum();
+        f.seek(SeekFrom::Start(page_offset))?;
+        f.write_all(&page.to_bytes())?;
+        stats.pages_written += 1;
+        stats.bytes_written += page.size() as u64;
+    }
+    f.flush()?;
+    Ok(ReplaceReport {
+        truncated: false,
+        shrink,
+        slack,
+        unchanged,
+        stats,
+    })
+}
+
+pub fn patch_comment_header_in_place<F: Read + Write + Seek>(
+    f: &mut F,
+    new_header: &CommentHeader,
+) -> ReplaceReport {
+    safe_patch_comment_header_in_place(f, new_header).unwrap()
+}
+
+/// `safe_patch_comment_header_in_place` on the file at `path`.
+pub fn safe_patch_comment_header_file<P: AsRef<Path>>(
+    path: P,
+    new_header: &CommentHeader,
+) -> Result<ReplaceReport, VorbisSurgicalError> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    let report = safe_patch_comment_header_in_place(&mut file, new_header)?;
+    file.sync_all()?;
+    Ok(report)
+}
+
+/// What replacing the comment header would do, see `safe_plan_replace`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplacePlan {
+    /// The new header fits, so `safe_patch_comment_header_in_place` would
+    /// succeed.
+    pub in_place: bool,
+    pub old_packet_size: usize,
+    pub new_packet_size: usize,
+    pub input_size: u64,
+    /// Size of the output of a rewrite copying the pages, see
+    /// `ReplaceOptions::copy_pages`.
+    pub output_size: u64,
+    /// Sequence numbers of the pages holding the comment and setup headers,
+    /// which are written anew.
+    pub header_pages: Vec<u32>,
+    /// Number of pages the headers take after the rewrite.
+    pub new_header_pages: usize,
+    /// The headers take another number of pages, so every later page of the
+    /// stream gets a new sequence number as well.
+    pub renumbers_pages: bool,
+}
+
+/// Work out what replacing the comment header of `f_in` with `new_header`
+/// would do, reading only the header pages and writing nothing.
+pub fn safe_plan_replace<T: Read + Seek>(
+    mut f_in: T,
+    new_header: &CommentHeader,
+) -> Result<ReplacePlan, VorbisSurgicalError> {
+    f_in.seek(SeekFrom::Start(0))?;
+    let headers = read_header_pages(&mut f_in, |_| Ok(()))?;
+    let input_size = f_in.seek(SeekFrom::End(0))?;
+
+    let old_len = headers.comment.len();
+    let comment = safe_make_comment_header(new_header)?;
+    let (patched, _, _) = fit_comment_packet(new_header, old_len, ShrinkStrategy::PaddingTag, 0);
+    let new_pages = paginate(
+        &[&comment, &headers.setup],
+        headers.serial,
+        headers.first_sequence.wrapping_add(1),
+        0,
+    );
+    let size = |pages: &[Page]| pages.iter().map(|page| page.size() as u64).sum::<u64>();
+    Ok(ReplacePlan {
+        in_place: patched.len() <= old_len,
+        old_packet_size: old_len,
+        new_packet_size: comment.len(),
+        input_size,
+        output_size: input_size - size(&headers.pages) + size(&new_pages),
+        header_pages: headers.pages.iter().map(|page| page.sequence).collect(),
+        new_header_pages: new_pages.len(),
+        renumbers_pages: new_pages.len() != headers.pages.len(),
+    })
+}
+
+pub fn plan_replace<T: Read + Seek>(f_in: T, new_header: &CommentHeader) -> ReplacePlan {
+    safe_plan_replace(f_in, new_header).unwrap()
+}
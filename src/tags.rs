@@ -0,0 +1,86 @@
+// The tag container, owned by this crate rather than borrowed from lewton
+
+use crate::iter::TagIter;
+use crate::store::TagStore;
+use crate::VorbisComments;
+use std::collections::HashMap;
+use std::fmt;
+use std::vec;
+
+/// The vendor string and the comments of a Vorbis comment header. Its
+/// contents are accessed through the `VorbisComments` trait and the
+/// `Vorbis*` helper traits.
+///
+/// Convert from and to `lewton::header::CommentHeader` with `From`/`Into`.
+/// Equality and hashing take the order and case of the entries into
+/// account, see `VorbisComments::eq_ignore_order_and_case` otherwise.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct VorbisTags {
+    pub(crate) vendor: String,
+    pub(crate) comments: TagStore,
+}
+
+impl From<lewton::header::CommentHeader> for VorbisTags {
+    fn from(header: lewton::header::CommentHeader) -> VorbisTags {
+        VorbisTags {
+            vendor: header.vendor,
+            comments: TagStore::from_vec(header.comment_list),
+        }
+    }
+}
+
+impl From<VorbisTags> for lewton::header::CommentHeader {
+    fn from(tags: VorbisTags) -> lewton::header::CommentHeader {
+        lewton::header::CommentHeader {
+            vendor: tags.vendor,
+            comment_list: tags.comments.into_vec(),
+        }
+    }
+}
+
+impl From<HashMap<String, Vec<String>>> for VorbisTags {
+    /// Same as `VorbisComments::from_map`.
+    fn from(map: HashMap<String, Vec<String>>) -> VorbisTags {
+        <VorbisTags as VorbisComments>::from_map(map)
+    }
+}
+
+impl IntoIterator for VorbisTags {
+    type Item = (String, String);
+    type IntoIter = vec::IntoIter<(String, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.comments.into_vec().into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a VorbisTags {
+    type Item = (&'a str, &'a str);
+    type IntoIter = TagIter<'a>;
+
+    fn into_iter(self) -> TagIter<'a> {
+        self.iter()
+    }
+}
+
+impl FromIterator<(String, String)> for VorbisTags {
+    /// Tags with no vendor, keys lowercased as by `add_tag_single`.
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(tags: I) -> VorbisTags {
+        let mut header = VorbisTags::default();
+        header.extend_tags(tags);
+        header
+    }
+}
+
+impl Extend<(String, String)> for VorbisTags {
+    fn extend<I: IntoIterator<Item = (String, String)>>(&mut self, tags: I) {
+        self.extend_tags(tags);
+    }
+}
+
+impl fmt::Display for VorbisTags {
+    /// The `to_pretty_string` output.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_pretty_string())
+    }
+}
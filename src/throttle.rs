@@ -0,0 +1,60 @@
+// Pacing scans and batch rewrites so they don't hog the disk
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Limits for background work. The default doesn't limit anything.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Throttle {
+    /// Maximum number of files processed per second.
+    pub files_per_second: Option<f64>,
+    /// Maximum number of bytes (file sizes) processed per second.
+    pub bytes_per_second: Option<u64>,
+    /// Yield the thread to the scheduler after every file.
+    pub yield_between_files: bool,
+}
+
+pub(crate) struct Throttler {
+    throttle: Throttle,
+    start: Instant,
+    files: u64,
+    bytes: u64,
+}
+
+impl Throttler {
+    pub(crate) fn new(throttle: &Throttle) -> Throttler {
+        Throttler {
+            throttle: throttle.clone(),
+            start: Instant::now(),
+            files: 0,
+            bytes: 0,
+        }
+    }
+
+    /// Wait until starting another file stays within the limits.
+    pub(crate) fn wait(&self) {
+        // A rate tiny enough to put the next file past `Duration::MAX` waits
+        // as long as it can
+        let seconds = |seconds: f64| Duration::try_from_secs_f64(seconds).unwrap_or(Duration::MAX);
+        let mut due = Duration::ZERO;
+        if let Some(rate) = self.throttle.files_per_second.filter(|rate| *rate > 0.0) {
+            due = due.max(seconds(self.files as f64 / rate));
+        }
+        if let Some(rate) = self.throttle.bytes_per_second.filter(|rate| *rate > 0) {
+            due = due.max(seconds(self.bytes as f64 / rate as f64));
+        }
+        let elapsed = self.start.elapsed();
+        if due > elapsed {
+            thread::sleep(due - elapsed);
+        }
+    }
+
+    /// Account for a processed file of `bytes` bytes.
+    pub(crate) fn file_done(&mut self, bytes: u64) {
+        self.files += 1;
+        self.bytes += bytes;
+        if self.throttle.yield_between_files {
+            thread::yield_now();
+        }
+    }
+}
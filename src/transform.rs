@@ -0,0 +1,125 @@
+// Opt-in cleanup transforms for tag values, applied through a transaction
+
+use crate::find_replace::FieldFilter;
+use crate::CommentHeader;
+
+/// Words kept in lowercase by title casing, unless first or last.
+const SMALL_WORDS: [&str; 20] = [
+    "a", "an", "and", "as", "at", "but", "by", "en", "for", "from", "if", "in", "nor", "of", "on",
+    "or", "the", "to", "vs", "with",
+];
+
+#[derive(Clone, Debug)]
+pub enum Transform {
+    /// Capitalize words, keeping small words like "of" and "the" in lowercase
+    /// except at the start and end. Words that already contain capitals
+    /// (acronyms, "iPhone") are left alone.
+    TitleCase,
+    /// Trim and collapse runs of whitespace into a single space.
+    CollapseSpaces,
+    /// Replace mis-decoded sequences, e.g. ("Ã©", "é"), using the given table.
+    FixMojibake(Vec<(String, String)>),
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn title_case(value: &str) -> String {
+    let words: Vec<&str> = value.split(' ').collect();
+    let last = words.len().saturating_sub(1);
+    words
+        .iter()
+        .enumerate()
+        .map(|(index, word)| {
+            let small = index != 0 && index != last && SMALL_WORDS.contains(word);
+            if small || word.chars().any(|c| c.is_uppercase()) {
+                word.to_string()
+            } else {
+                capitalize(word)
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+impl Transform {
+    pub fn apply(&self, value: &str) -> String {
+        match self {
+            Transform::TitleCase => title_case(value),
+            Transform::CollapseSpaces => value.split_whitespace().collect::<Vec<&str>>().join(" "),
+            Transform::FixMojibake(table) => {
+                let mut fixed = value.to_string();
+                for (broken, good) in table.iter() {
+                    if !broken.is_empty() {
+                        fixed = fixed.replace(broken.as_str(), good);
+                    }
+                }
+                fixed
+            }
+        }
+    }
+}
+
+/// A single value changed by a transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValueChange {
+    pub tag: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// Edits staged on a copy of a header. Nothing is changed in the original
+/// until `commit` is called, dropping the transaction discards the edits.
+pub struct TagTransaction<'a> {
+    header: &'a mut CommentHeader,
+    working: CommentHeader,
+    changes: Vec<ValueChange>,
+}
+
+impl<'a> TagTransaction<'a> {
+    pub fn new(header: &'a mut CommentHeader) -> TagTransaction<'a> {
+        let working = header.clone();
+        TagTransaction {
+            header,
+            working,
+            changes: vec![],
+        }
+    }
+
+    /// Apply a transform to the values of the tags selected by `filter`,
+    /// returning the changes it made.
+    pub fn apply(&mut self, transform: &Transform, filter: &FieldFilter) -> &[ValueChange] {
+        let first = self.changes.len();
+        for (key, value) in self.working.comments.values_mut() {
+            if !filter.matches(key) {
+                continue;
+            }
+            let new = transform.apply(value);
+            if new != *value {
+                let old = std::mem::replace(value, new.clone());
+                self.changes.push(ValueChange {
+                    tag: key.to_string(),
+                    old,
+                    new,
+                });
+            }
+        }
+        &self.changes[first..]
+    }
+
+    /// All changes staged so far, in the order they were made.
+    pub fn changes(&self) -> &[ValueChange] {
+        &self.changes
+    }
+
+    /// Write the staged edits to the header and return the changes.
+    pub fn commit(self) -> Vec<ValueChange> {
+        *self.header = self.working;
+        self.changes
+    }
+}
@@ -0,0 +1,31 @@
+// Unicode normalization of tag values, enabled by the `nfc` feature
+
+use crate::{safe_read_comment_header, CommentHeader, VorbisComments, VorbisReadCommentError};
+use std::io::Read;
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+
+/// `value` in Normalization Form C, with combining sequences composed the
+/// way most tools other than macOS ones write them.
+pub fn to_nfc(value: &str) -> String {
+    value.nfc().collect()
+}
+
+pub trait VorbisUnicode {
+    /// Convert every value to NFC, returning how many changed.
+    fn normalize_nfc(&mut self) -> usize;
+}
+
+impl VorbisUnicode for CommentHeader {
+    fn normalize_nfc(&mut self) -> usize {
+        self.map_values(|_, value| (!is_nfc(value)).then(|| to_nfc(value)))
+    }
+}
+
+/// `safe_read_comment_header` with every value converted to NFC.
+pub fn safe_read_comment_header_nfc<T: Read>(
+    f_in: T,
+) -> Result<CommentHeader, VorbisReadCommentError> {
+    let mut header = safe_read_comment_header(f_in)?;
+    header.normalize_nfc();
+    Ok(header)
+}
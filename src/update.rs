@@ -0,0 +1,85 @@
+// Read-modify-write of a single file, refusing to overwrite concurrent edits
+
+use crate::batch::{read_path, stage, VorbisBatchError};
+use crate::scan::header_fingerprint;
+use crate::CommentHeader;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// The state of a file when its comments were read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileSnapshot {
+    pub path: PathBuf,
+    pub header: CommentHeader,
+    size: u64,
+    modified: Option<SystemTime>,
+    fingerprint: u32,
+}
+
+fn file_state(path: &Path) -> Result<(u64, Option<SystemTime>, u32), VorbisBatchError> {
+    let io_err = |err| VorbisBatchError::Io(path.to_path_buf(), err);
+    let metadata = fs::metadata(path).map_err(io_err)?;
+    let fingerprint = header_fingerprint(path).map_err(io_err)?;
+    Ok((metadata.len(), metadata.modified().ok(), fingerprint))
+}
+
+impl FileSnapshot {
+    /// Whether the file still has the size, modification time and header
+    /// fingerprint it had when read.
+    pub fn is_current(&self) -> Result<bool, VorbisBatchError> {
+        Ok(file_state(&self.path)? == (self.size, self.modified, self.fingerprint))
+    }
+}
+
+pub fn read_file_comments<P: AsRef<Path>>(path: P) -> Result<FileSnapshot, VorbisBatchError> {
+    let path = path.as_ref();
+    let (size, modified, fingerprint) = file_state(path)?;
+    let header = read_path(path)?;
+    Ok(FileSnapshot {
+        path: path.to_path_buf(),
+        header,
+        size,
+        modified,
+        fingerprint,
+    })
+}
+
+/// Write `header` to the file of `snapshot`, failing with
+/// `ConcurrentModification` if the file changed since it was read. The new
+/// file is staged first and the check is repeated right before it is renamed
+/// into place, which leaves only a very small window for a racing writer.
+pub fn write_file_comments(
+    snapshot: &FileSnapshot,
+    header: CommentHeader,
+) -> Result<(), VorbisBatchError> {
+    let path = &snapshot.path;
+    let concurrent = || VorbisBatchError::ConcurrentModification(path.clone());
+    if !snapshot.is_current()? {
+        return Err(concurrent());
+    }
+    let staged = stage(path, header)?;
+    let current = snapshot.is_current().and_then(|current| {
+        if current {
+            fs::rename(&staged, path).map_err(|err| VorbisBatchError::Io(path.clone(), err))
+        } else {
+            Err(concurrent())
+        }
+    });
+    if current.is_err() {
+        let _ = fs::remove_file(&staged);
+    }
+    current
+}
+
+/// Read the comments of a file, edit them with `edit` and write them back,
+/// unless another writer changed the file in the meantime.
+pub fn update_file_comments<P: AsRef<Path>, F: FnOnce(&mut CommentHeader)>(
+    path: P,
+    edit: F,
+) -> Result<(), VorbisBatchError> {
+    let snapshot = read_file_comments(path)?;
+    let mut header = snapshot.header.clone();
+    edit(&mut header);
+    write_file_comments(&snapshot, header)
+}
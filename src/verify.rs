@@ -0,0 +1,248 @@
+// Checking a rewrite against its input
+
+use crate::page::{self, PageError, PageReadError};
+use crate::VorbisReadCommentError;
+use ogg::{OggReadError, PacketReader};
+use std::collections::HashMap;
+use std::io::{self, Read, Seek};
+
+/// The packets of the first logical stream read by `reader`, `None` at the
+/// end of the file or in a page cut short.
+fn next_packet<T: Read + Seek>(
+    reader: &mut PacketReader<T>,
+    serial: &mut Option<u32>,
+) -> Result<Option<Vec<u8>>, OggReadError> {
+    loop {
+        let packet = match reader.read_packet() {
+            Ok(Some(packet)) => packet,
+            Ok(None) => return Ok(None),
+            Err(OggReadError::ReadError(ref error))
+                if error.kind() == io::ErrorKind::UnexpectedEof =>
+            {
+                return Ok(None)
+            }
+            Err(error) => return Err(error),
+        };
+        if *serial.get_or_insert(packet.stream_serial()) == packet.stream_serial() {
+            return Ok(Some(packet.data));
+        }
+    }
+}
+
+/// Compare the packets of the first logical stream of `original` and
+/// `rewritten`, all but the comment header. Returns the index in the stream
+/// of the first packet that differs or that only one of them has, `None`
+/// when the audio and the other headers are byte for byte the same, in the
+/// same order. Page layout and granule positions are not compared.
+pub fn safe_compare_audio<A: Read + Seek, B: Read + Seek>(
+    original: A,
+    rewritten: B,
+) -> Result<Option<u64>, VorbisReadCommentError> {
+    let mut original = PacketReader::new(original);
+    let mut rewritten = PacketReader::new(rewritten);
+    let (mut original_serial, mut rewritten_serial) = (None, None);
+    let mut index = 0;
+    loop {
+        let expected = next_packet(&mut original, &mut original_serial)?;
+        let found = next_packet(&mut rewritten, &mut rewritten_serial)?;
+        match (expected, found) {
+            (None, None) => return Ok(None),
+            (Some(expected), Some(found)) if index == 1 || expected == found => {}
+            _ => return Ok(Some(index)),
+        }
+        index += 1;
+    }
+}
+
+/// Keeps a copy of what is read, to compare an input that can't seek with
+/// the output.
+pub(crate) struct Recorded<T> {
+    inner: T,
+    pub(crate) data: Vec<u8>,
+}
+
+impl<T> Recorded<T> {
+    pub(crate) fn new(inner: T) -> Recorded<T> {
+        Recorded {
+            inner,
+            data: vec![],
+        }
+    }
+}
+
+impl<T: Read> Read for Recorded<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.data.extend(&buf[..read]);
+        Ok(read)
+    }
+}
+
+/// A problem with the pages of a file, found by `verify_file`. Offsets are
+/// in bytes from where reading started.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PageIssue {
+    /// Bytes that aren't part of any page, skipped to find the next one.
+    Garbage { offset: u64, len: u64 },
+    /// The file ends in the middle of a page starting after `offset`.
+    Truncated { offset: u64 },
+    /// A page of an Ogg version other than 0.
+    BadVersion { offset: u64, version: u8 },
+    ChecksumMismatch {
+        offset: u64,
+        serial: u32,
+        sequence: u32,
+        expected: u32,
+        computed: u32,
+    },
+    SequenceGap {
+        offset: u64,
+        serial: u32,
+        expected: u32,
+        found: u32,
+    },
+    /// The first page of the stream lacks the beginning-of-stream flag.
+    MissingBeginOfStream { offset: u64, serial: u32 },
+    /// A page other than the first of its stream has the beginning-of-stream
+    /// flag.
+    UnexpectedBeginOfStream {
+        offset: u64,
+        serial: u32,
+        sequence: u32,
+    },
+    /// A page of the stream after the one with the end-of-stream flag.
+    PageAfterEndOfStream {
+        offset: u64,
+        serial: u32,
+        sequence: u32,
+    },
+    /// The last page of the stream lacks the end-of-stream flag.
+    MissingEndOfStream { serial: u32 },
+}
+
+/// Tracks the position in the file.
+struct Position<T> {
+    inner: T,
+    position: u64,
+}
+
+impl<T: Read> Read for Position<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+/// The state of a logical stream met in the file.
+struct StreamState {
+    next_sequence: u32,
+    ended: bool,
+}
+
+/// Read every page of `f_in` to the end, checking the checksums, the
+/// sequence numbers and the beginning and end-of-stream flags of every
+/// logical stream. Unlike `check_conformance` nothing is kept in memory but
+/// the page being checked. Returns an empty list for an intact file.
+pub fn safe_verify_file<T: Read>(f_in: T) -> io::Result<Vec<PageIssue>> {
+    let mut f_in = Position {
+        inner: f_in,
+        position: 0,
+    };
+    let mut issues = vec![];
+    let mut streams: HashMap<u32, StreamState> = HashMap::new();
+    // Streams in order of appearance, to report the unterminated ones in order
+    let mut serials = vec![];
+    loop {
+        let start = f_in.position;
+        let (page, skipped) = match page::read_page(&mut f_in, true) {
+            Ok(Some(read)) => read,
+            Ok(None) => break,
+            Err(PageReadError::Io(err)) => return Err(err),
+            Err(PageReadError::Page(PageError::Truncated)) => {
+                issues.push(PageIssue::Truncated { offset: start });
+                break;
+            }
+            // Only at the end, when no capture pattern came
+            Err(PageReadError::Page(PageError::NoCapturePattern)) => {
+                let len = f_in.position - start;
+                issues.push(PageIssue::Garbage { offset: start, len });
+                break;
+            }
+            Err(PageReadError::Page(PageError::BadVersion(version))) => {
+                issues.push(PageIssue::BadVersion {
+                    offset: start,
+                    version,
+                });
+                continue;
+            }
+        };
+        if skipped > 0 {
+            issues.push(PageIssue::Garbage {
+                offset: start,
+                len: skipped,
+            });
+        }
+        let offset = start + skipped;
+        let (serial, sequence) = (page.serial, page.sequence);
+        let computed = page.compute_checksum();
+        if page.checksum != computed {
+            issues.push(PageIssue::ChecksumMismatch {
+                offset,
+                serial,
+                sequence,
+                expected: page.checksum,
+                computed,
+            });
+        }
+        let stream = match streams.get_mut(&serial) {
+            Some(stream) => {
+                if page.is_first() {
+                    issues.push(PageIssue::UnexpectedBeginOfStream {
+                        offset,
+                        serial,
+                        sequence,
+                    });
+                }
+                if stream.ended {
+                    issues.push(PageIssue::PageAfterEndOfStream {
+                        offset,
+                        serial,
+                        sequence,
+                    });
+                }
+                stream
+            }
+            None => {
+                if !page.is_first() {
+                    issues.push(PageIssue::MissingBeginOfStream { offset, serial });
+                }
+                serials.push(serial);
+                streams.entry(serial).or_insert(StreamState {
+                    next_sequence: sequence,
+                    ended: false,
+                })
+            }
+        };
+        if sequence != stream.next_sequence {
+            issues.push(PageIssue::SequenceGap {
+                offset,
+                serial,
+                expected: stream.next_sequence,
+                found: sequence,
+            });
+        }
+        stream.next_sequence = sequence.wrapping_add(1);
+        stream.ended |= page.is_last();
+    }
+    for serial in serials {
+        if !streams[&serial].ended {
+            issues.push(PageIssue::MissingEndOfStream { serial });
+        }
+    }
+    Ok(issues)
+}
+
+pub fn verify_file<T: Read>(f_in: T) -> Vec<PageIssue> {
+    safe_verify_file(f_in).unwrap()
+}
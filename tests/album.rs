@@ -0,0 +1,93 @@
+use oggvorbismeta::{
+    read_comment_header, renumber_tracks, AlbumInconsistency, AlbumSession, VorbisBatchError,
+    VorbisComments,
+};
+use std::fs::{self, File};
+use std::path::PathBuf;
+
+fn album_files(name: &str, count: usize) -> Vec<PathBuf> {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    (0..count)
+        .map(|n| {
+            let path = dir.join(format!("track{}.ogg", n));
+            fs::copy("tests/noise.ogg", &path).unwrap();
+            path
+        })
+        .collect()
+}
+
+#[test]
+fn test_album_session_edit_and_save() {
+    let paths = album_files("album_session", 3);
+    let mut session = AlbumSession::open(&paths).unwrap();
+    session.set_album("Greatest Hits");
+    session.set_album_artist("Some Guy");
+    session.set_date("1997");
+    session.renumber_tracks(1, 0).unwrap();
+    assert!(session.validate().is_empty());
+    session.save().unwrap();
+
+    let header = read_comment_header(File::open(&paths[2]).unwrap());
+    assert_eq!(header.get_tag_single("album").unwrap(), "Greatest Hits");
+    assert_eq!(header.get_tag_single("tracknumber").unwrap(), "3");
+    assert_eq!(header.get_tag_single("tracktotal").unwrap(), "3");
+    assert_eq!(header.get_tag_single("title").unwrap(), "Noise");
+    assert_eq!(fs::read_dir(paths[0].parent().unwrap()).unwrap().count(), 3);
+}
+
+#[test]
+fn test_album_session_validate() {
+    let paths = album_files("album_validate", 2);
+    let mut session = AlbumSession::open(&paths).unwrap();
+    session.set_album("Greatest Hits");
+    session.header_mut(1).unwrap().clear_tag("album");
+    let issues = session.validate();
+    assert_eq!(
+        issues[0],
+        AlbumInconsistency::MismatchedTag {
+            tag: "album".to_string(),
+            values: vec!["Greatest Hits".to_string(), "".to_string()],
+        }
+    );
+}
+
+#[test]
+fn test_renumber_tracks_padded() {
+    let paths = album_files("album_renumber", 2);
+    renumber_tracks(&paths, 9, 2).unwrap();
+    let numbers = paths
+        .iter()
+        .map(|path| {
+            read_comment_header(File::open(path).unwrap())
+                .get_tag_single("tracknumber")
+                .unwrap()
+        })
+        .collect::<Vec<String>>();
+    assert_eq!(numbers, vec!["09".to_string(), "10".to_string()]);
+}
+
+#[test]
+fn test_renumber_tracks_aliases_and_overflow() {
+    let paths = album_files("album_renumber_alias", 2);
+    let mut session = AlbumSession::open(&paths).unwrap();
+    session
+        .header_mut(0)
+        .unwrap()
+        .add_tag_single("totaltracks", "12");
+    session.renumber_tracks(1, 0).unwrap();
+    let header = &session.tracks()[0].1;
+    assert!(header.get_tag_single("totaltracks").is_none());
+    assert_eq!(header.get_tag_single("tracktotal").unwrap(), "2");
+
+    assert!(matches!(
+        session.renumber_tracks(u32::MAX, 0),
+        Err(VorbisBatchError::TrackNumberOverflow(u32::MAX))
+    ));
+    assert_eq!(
+        session.tracks()[1].1.get_tag_single("tracknumber").unwrap(),
+        "2"
+    );
+    session.renumber_tracks(u32::MAX - 1, 0).unwrap();
+}
@@ -0,0 +1,32 @@
+use oggvorbismeta::{CommentHeader, KeyAliases, VorbisAliases, VorbisComments};
+
+#[test]
+fn test_canonicalize_keys() {
+    let mut header = CommentHeader::new();
+    header.add_tag_verbatim("Album Artist", "Various");
+    header.add_tag_single("year", "1997");
+    header.add_tag_single("title", "Noise");
+    header.add_tag_single("track", "3");
+    assert_eq!(header.canonicalize_keys(&KeyAliases::default()), 3);
+    assert_eq!(
+        header.get_tag_names_verbatim(),
+        vec!["albumartist", "date", "title", "tracknumber"]
+    );
+}
+
+#[test]
+fn test_custom_aliases() {
+    let mut aliases = KeyAliases::empty();
+    aliases
+        .insert("Catalog #", "CATALOGNUMBER")
+        .insert("year", "ORIGINALDATE");
+    assert_eq!(aliases.canonical("CATALOG #"), Some("catalognumber"));
+    aliases.remove("year");
+
+    let mut header = CommentHeader::new();
+    header.add_tag_single("catalog #", "ABC-1");
+    header.add_tag_single("year", "1997");
+    assert_eq!(header.canonicalize_keys(&aliases), 1);
+    assert_eq!(header.get_tag_single("catalognumber").unwrap(), "ABC-1");
+    assert!(header.contains_tag("year"));
+}
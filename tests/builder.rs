@@ -0,0 +1,42 @@
+use oggvorbismeta::{
+    is_valid_tag_name, make_comment_header, CommentHeaderBuilder, Picture, PictureType,
+    VorbisBuildError, VorbisComments, VorbisPictures,
+};
+
+#[test]
+fn test_build_header() {
+    let picture = Picture::new(PictureType::FrontCover, "image/png", vec![1, 2, 3]);
+    let header = CommentHeaderBuilder::new()
+        .vendor("Ogg")
+        .tag("TITLE", "Noise")
+        .tags(vec![("artist", "A"), ("artist", "B")])
+        .picture(&picture)
+        .build()
+        .unwrap();
+    assert_eq!(header.get_vendor(), "Ogg");
+    assert_eq!(header.get_tag_multi("artist"), vec!["A", "B"]);
+    assert_eq!(header.get_pictures(), vec![picture]);
+}
+
+#[test]
+fn test_build_rejects_bad_headers() {
+    assert!(is_valid_tag_name("REPLAYGAIN_TRACK_GAIN"));
+    assert!(!is_valid_tag_name("ti=tle"));
+    assert!(!is_valid_tag_name("tïtle"));
+    assert!(!is_valid_tag_name(""));
+    assert_eq!(
+        CommentHeaderBuilder::new().tag("ti=tle", "x").build(),
+        Err(VorbisBuildError::InvalidTagName("ti=tle".to_string()))
+    );
+
+    let builder = CommentHeaderBuilder::new().tag("comment", &"x".repeat(100));
+    let size = make_comment_header(&builder.clone().build().unwrap()).len();
+    assert!(builder.clone().max_size(size).build().is_ok());
+    assert_eq!(
+        builder.max_size(size - 1).build(),
+        Err(VorbisBuildError::TooLarge {
+            size,
+            limit: size - 1
+        })
+    );
+}
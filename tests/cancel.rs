@@ -0,0 +1,71 @@
+use oggvorbismeta::{
+    safe_replace_comment_header_in_path_with_options, safe_replace_comment_header_with_options,
+    safe_replace_comment_header_with_progress, CancelToken, CommentHeader, ReplaceOptions,
+    SaveOptions, VorbisBatchError, VorbisComments, VorbisReplaceCommentError,
+};
+use std::fs::{self, File};
+use std::path::PathBuf;
+
+fn cancelled_options() -> ReplaceOptions {
+    let cancel = CancelToken::new();
+    cancel.cancel();
+    ReplaceOptions {
+        cancel: Some(cancel),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_cancelled_replace() {
+    let header = CommentHeader::new();
+    for copy_pages in [false, true] {
+        let options = ReplaceOptions {
+            copy_pages,
+            ..cancelled_options()
+        };
+        let f_in = File::open("tests/noise.ogg").unwrap();
+        let result = safe_replace_comment_header_with_options(f_in, header.clone(), &options);
+        assert!(matches!(result, Err(VorbisReplaceCommentError::Cancelled)));
+    }
+
+    // Cancelled part way, from the progress callback
+    let cancel = CancelToken::new();
+    let options = ReplaceOptions {
+        cancel: Some(cancel.clone()),
+        ..Default::default()
+    };
+    let f_in = File::open("tests/noise.ogg").unwrap();
+    let result =
+        safe_replace_comment_header_with_progress(f_in, vec![], header, &options, |_, _| {
+            cancel.cancel()
+        });
+    assert!(matches!(result, Err(VorbisReplaceCommentError::Cancelled)));
+}
+
+#[test]
+fn test_cancelled_save_leaves_no_file() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("cancelled_save");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("song.ogg");
+    fs::copy("tests/noise.ogg", &path).unwrap();
+
+    let options = SaveOptions {
+        replace: cancelled_options(),
+        ..Default::default()
+    };
+    let result =
+        safe_replace_comment_header_in_path_with_options(&path, CommentHeader::new(), &options);
+    assert!(matches!(
+        result,
+        Err(VorbisBatchError::Replace(
+            _,
+            VorbisReplaceCommentError::Cancelled
+        ))
+    ));
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    assert_eq!(
+        fs::read(&path).unwrap(),
+        fs::read("tests/noise.ogg").unwrap()
+    );
+}
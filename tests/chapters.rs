@@ -0,0 +1,57 @@
+use oggvorbismeta::{
+    format_timestamp, parse_timestamp, Chapter, CommentHeader, VorbisChapters, VorbisComments,
+};
+use std::time::Duration;
+
+#[test]
+fn test_timestamps() {
+    assert_eq!(
+        parse_timestamp("01:02:03.5"),
+        Some(Duration::from_millis(3_723_500))
+    );
+    assert_eq!(parse_timestamp("00:00:10"), Some(Duration::from_secs(10)));
+    assert_eq!(parse_timestamp("00:61:00.000"), None);
+    assert_eq!(parse_timestamp("9999999999999999999:00:00"), None);
+    assert_eq!(
+        format_timestamp(Duration::from_millis(3_723_500)),
+        "01:02:03.500"
+    );
+}
+
+#[test]
+fn test_read_chapters() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("CHAPTER002", "00:05:00.000");
+    header.add_tag_single("CHAPTER002NAME", "Second");
+    header.add_tag_single("CHAPTER1", "00:00:00.000");
+    header.add_tag_single("CHAPTER001URL", "http://example.com");
+    let chapters = header.chapters();
+    assert_eq!(chapters.len(), 2);
+    assert_eq!(chapters[0].index, 1);
+    assert_eq!(chapters[0].url.as_deref(), Some("http://example.com"));
+    assert_eq!(chapters[1].name.as_deref(), Some("Second"));
+}
+
+#[test]
+fn test_write_chapters_renumbered() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("CHAPTER007", "00:00:00.000");
+    header.add_tag_single("title", "Book");
+    let chapter = |index, secs, name: &str| Chapter {
+        index,
+        start: Duration::from_secs(secs),
+        name: Some(name.to_string()),
+        url: None,
+    };
+    header.set_chapters(&[chapter(9, 60, "Two"), chapter(4, 0, "One")]);
+    assert_eq!(
+        header.get_tag_single("chapter001name").unwrap(),
+        "One".to_string()
+    );
+    assert_eq!(
+        header.get_tag_single("chapter002").unwrap(),
+        "00:01:00.000".to_string()
+    );
+    assert!(header.get_tag_single("chapter007").is_none());
+    assert_eq!(header.chapters()[1].index, 2);
+}
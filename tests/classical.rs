@@ -0,0 +1,36 @@
+use oggvorbismeta::{roman_numeral, CommentHeader, Position, VorbisClassical, VorbisComments};
+
+#[test]
+fn test_roman_numeral() {
+    assert_eq!(roman_numeral(4).unwrap(), "IV");
+    assert_eq!(roman_numeral(1994).unwrap(), "MCMXCIV");
+    assert!(roman_numeral(0).is_none());
+}
+
+#[test]
+fn test_work_and_movement() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("title", "Adagio");
+    assert_eq!(header.display_title().unwrap(), "Adagio");
+
+    header.set_work(Some("Symphony No. 9"));
+    header.set_movement_name(Some("Molto vivace"));
+    assert_eq!(
+        header.display_title().unwrap(),
+        "Symphony No. 9: Molto vivace"
+    );
+    header.set_movement(Some(Position {
+        number: 2,
+        total: Some(4),
+    }));
+    assert_eq!(header.get_tag_single("movementtotal").unwrap(), "4");
+    assert_eq!(
+        header.display_title().unwrap(),
+        "Symphony No. 9: II. Molto vivace"
+    );
+
+    header.clear_tag("movementtotal");
+    header.clear_tag("movement");
+    header.add_tag_single("MOVEMENT", "3/4");
+    assert_eq!(header.movement().unwrap().total, Some(4));
+}
@@ -0,0 +1,52 @@
+use oggvorbismeta::{
+    check_conformance, safe_replace_comment_header_with_options, CommentHeader, ConformanceIssue,
+    ReplaceOptions, VorbisComments, VorbisReplaceCommentError,
+};
+use std::fs::{self, File};
+use std::io::Cursor;
+
+fn paranoid() -> ReplaceOptions {
+    ReplaceOptions {
+        check_conformance: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_fixture_conforms() {
+    let data = fs::read("tests/noise.ogg").unwrap();
+    assert_eq!(check_conformance(&data), vec![]);
+}
+
+#[test]
+fn test_rewritten_file_conforms() {
+    let f_in = File::open("tests/tiny_pages.ogg").expect("Can't open file");
+    let mut header = CommentHeader::new();
+    header.add_tag_single("title", "Noise");
+    let (f_out, _) = safe_replace_comment_header_with_options(f_in, header, &paranoid()).unwrap();
+    assert_eq!(check_conformance(f_out.get_ref()), vec![]);
+}
+
+#[test]
+fn test_detect_issues() {
+    let mut data = fs::read("tests/noise.ogg").unwrap();
+    // Corrupt a byte of the second page
+    data[100] ^= 0xff;
+    assert!(check_conformance(&data).contains(&ConformanceIssue::ChecksumMismatch { sequence: 1 }));
+
+    let mut data = fs::read("tests/noise.ogg").unwrap();
+    data.truncate(5000);
+    let issues = check_conformance(&data);
+    assert!(issues.contains(&ConformanceIssue::MalformedPage { offset: 4429 }));
+    assert!(issues.contains(&ConformanceIssue::MissingEndOfStream));
+
+    let result = safe_replace_comment_header_with_options(
+        Cursor::new(data),
+        CommentHeader::new(),
+        &paranoid(),
+    );
+    assert!(matches!(
+        result,
+        Err(VorbisReplaceCommentError::NonConformant(_))
+    ));
+}
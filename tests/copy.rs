@@ -0,0 +1,52 @@
+use oggvorbismeta::{
+    copy_comment_header, copy_comment_header_from, read_file_comments, update_file_comments,
+    CopyOptions, FieldFilter, VorbisComments,
+};
+use std::fs::{self, File};
+use std::path::PathBuf;
+
+fn work_files(name: &str) -> (PathBuf, PathBuf) {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let src = dir.join("src.ogg");
+    let dst = dir.join("dst.ogg");
+    fs::copy("tests/noise.ogg", &src).unwrap();
+    fs::copy("tests/noise.ogg", &dst).unwrap();
+    update_file_comments(&src, |header| {
+        header.set_vendor("Source encoder");
+        header.set_tag_single("title", "Noise");
+        header.set_tag_single("comment", "ripped");
+    })
+    .unwrap();
+    update_file_comments(&dst, |header| {
+        header.set_vendor("Destination encoder");
+        header.set_tag_single("artist", "Somebody");
+    })
+    .unwrap();
+    (src, dst)
+}
+
+#[test]
+fn test_copy_comment_header() {
+    let (src, dst) = work_files("copy_paths");
+    copy_comment_header(&src, &dst, &CopyOptions::default()).unwrap();
+    let copied = read_file_comments(&dst).unwrap().header;
+    assert_eq!(copied.get_vendor(), "Destination encoder");
+    assert_eq!(copied.get_tag_multi("artist"), vec!["Nobody"]);
+    assert_eq!(copied.get_tag_single("comment").unwrap(), "ripped");
+}
+
+#[test]
+fn test_copy_filtered_from_reader() {
+    let (src, dst) = work_files("copy_reader");
+    let options = CopyOptions {
+        filter: FieldFilter::Except(vec!["COMMENT".to_string()]),
+        keep_destination_vendor: false,
+    };
+    copy_comment_header_from(File::open(&src).unwrap(), &dst, &options).unwrap();
+    let copied = read_file_comments(&dst).unwrap().header;
+    assert_eq!(copied.get_vendor(), "Source encoder");
+    assert_eq!(copied.get_tag_single("title").unwrap(), "Noise");
+    assert!(!copied.contains_tag("comment"));
+}
@@ -0,0 +1,20 @@
+use oggvorbismeta::{ogg_crc32, OggCrc32};
+use std::fs;
+
+#[test]
+fn test_ogg_crc32_matches_page_checksum() {
+    assert_eq!(ogg_crc32(b""), 0);
+    let data = fs::read("tests/noise.ogg").unwrap();
+    // The first page is a header, its segment table and a 30 byte body
+    let page_len = 27 + data[26] as usize + data[27] as usize;
+    let mut page = data[..page_len].to_vec();
+    let checksum = u32::from_le_bytes([page[22], page[23], page[24], page[25]]);
+    page[22..26].copy_from_slice(&[0; 4]);
+    assert_eq!(ogg_crc32(&page), checksum);
+
+    let mut hasher = OggCrc32::new();
+    for chunk in page.chunks(7) {
+        hasher.update(chunk);
+    }
+    assert_eq!(hasher.finish(), checksum);
+}
@@ -0,0 +1,42 @@
+use oggvorbismeta::{CommentHeader, Performer, VorbisComments, VorbisCredits};
+
+#[test]
+fn test_parse_performer() {
+    assert_eq!(
+        Performer::parse("Glenn Gould (piano)"),
+        Performer {
+            name: "Glenn Gould".to_string(),
+            role: Some("piano".to_string()),
+        }
+    );
+    let band = Performer::parse("Sunn O)))");
+    assert_eq!(band.name, "Sunn O)))");
+    assert!(band.role.is_none());
+    assert_eq!(Performer::parse("(vocals)").role, None);
+}
+
+#[test]
+fn test_credits() {
+    let mut header = CommentHeader::new();
+    header.add_tag_multi(
+        "performer",
+        &[
+            "Jane (vocals)",
+            "John (drums)",
+            "Jane (guitar)",
+            "Jane (vocals)",
+            "Orchestra",
+        ],
+    );
+    let credits = header.credits();
+    assert_eq!(credits.len(), 3);
+    assert_eq!(credits[0].roles, vec!["vocals", "guitar"]);
+    assert!(credits[2].roles.is_empty());
+
+    let performers = header.performers();
+    header.set_performers(&performers[..2]);
+    assert_eq!(
+        header.get_tag_multi("PERFORMER"),
+        vec!["Jane (vocals)", "John (drums)"]
+    );
+}
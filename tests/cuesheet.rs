@@ -0,0 +1,67 @@
+use oggvorbismeta::{
+    parse_cue_time, CommentHeader, CueIndex, CueSheet, VorbisComments, VorbisCueSheet,
+    VorbisCueSheetError, CUE_FRAMES_PER_SECOND,
+};
+use std::time::Duration;
+
+const CUE: &str = "REM GENRE Noise\r
+PERFORMER \"Some Band\"\r
+TITLE \"Some Album\"\r
+FILE \"album.wav\" WAVE\r
+  TRACK 01 AUDIO\r
+    TITLE \"First\"\r
+    INDEX 01 00:00:00\r
+  TRACK 02 AUDIO\r
+    TITLE \"Second\"\r
+    PERFORMER \"Guest\"\r
+    FLAGS DCP\r
+    INDEX 00 03:58:50\r
+    INDEX 01 04:00:15\r
+";
+
+#[test]
+fn test_parse_cue_sheet() {
+    let sheet = CueSheet::parse(CUE).unwrap();
+    assert_eq!(sheet.performer.as_deref(), Some("Some Band"));
+    assert_eq!(sheet.file.as_deref(), Some("album.wav"));
+    assert_eq!(sheet.extra, vec!["REM GENRE Noise".to_string()]);
+    assert_eq!(sheet.tracks.len(), 2);
+    let second = &sheet.tracks[1];
+    assert_eq!(second.performer.as_deref(), Some("Guest"));
+    assert_eq!(second.extra, vec!["FLAGS DCP".to_string()]);
+    assert_eq!(second.indexes[1].frames, 240 * 75 + 15);
+    assert_eq!(second.indexes[1].start(), Duration::from_millis(240_200));
+
+    assert_eq!(
+        CueSheet::parse("INDEX 01 00:00:00"),
+        Err(VorbisCueSheetError::IndexOutsideTrack(1))
+    );
+    assert_eq!(
+        CueSheet::parse("TRACK 01 AUDIO\nINDEX 01 00:61:00"),
+        Err(VorbisCueSheetError::InvalidArgument(2))
+    );
+}
+
+#[test]
+fn test_cue_sheet_roundtrip() {
+    let sheet = CueSheet::parse(CUE).unwrap();
+    assert_eq!(sheet.to_tag_value(), CUE);
+
+    let mut header = CommentHeader::new();
+    assert_eq!(header.cue_sheet(), Ok(None));
+    header.set_cue_sheet(Some(&sheet));
+    assert_eq!(header.cue_sheet(), Ok(Some(sheet)));
+    header.set_cue_sheet(None);
+    assert!(header.get_tag_single("cuesheet").is_none());
+}
+
+#[test]
+fn test_cue_time_overflow() {
+    assert_eq!(parse_cue_time("999999999999999999:00:00"), None);
+    assert_eq!(parse_cue_time("01:02:03"), Some(4653));
+    let index = CueIndex {
+        number: 1,
+        frames: u64::MAX,
+    };
+    assert_eq!(index.start().as_secs(), u64::MAX / CUE_FRAMES_PER_SECOND);
+}
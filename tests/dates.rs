@@ -0,0 +1,31 @@
+#![cfg(feature = "chrono")]
+
+use chrono::NaiveDate;
+use oggvorbismeta::{CommentHeader, TagDate, VorbisComments, VorbisDates};
+
+#[test]
+fn test_parse_precisions() {
+    assert_eq!(TagDate::parse("1997"), TagDate::Year(1997));
+    assert_eq!(TagDate::parse("1997-03"), TagDate::YearMonth(1997, 3));
+    assert_eq!(
+        TagDate::parse(" 1997-03-14 "),
+        TagDate::Date(NaiveDate::from_ymd_opt(1997, 3, 14).unwrap())
+    );
+    assert_eq!(
+        TagDate::parse("1997-02-30"),
+        TagDate::Unparsed("1997-02-30".to_string())
+    );
+}
+
+#[test]
+fn test_write_back() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("date", "Spring 1997");
+    let date = header.parsed_date().unwrap();
+    assert_eq!(date.year(), None);
+    header.set_parsed_date(&date);
+    assert_eq!(header.get_tag_single("date").unwrap(), "Spring 1997");
+
+    header.set_parsed_original_date(&TagDate::YearMonth(1969, 7));
+    assert_eq!(header.get_tag_single("originaldate").unwrap(), "1969-07");
+}
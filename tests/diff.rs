@@ -0,0 +1,37 @@
+use oggvorbismeta::{diff, CommentHeader, TagChange, VorbisComments};
+
+#[test]
+fn test_diff() {
+    let mut old = CommentHeader::new();
+    old.set_vendor("Ogg");
+    old.add_tag_single("title", "Noise");
+    old.add_tag_multi("artist", &["A", "B"]);
+    old.add_tag_single("comment", "old");
+    let mut new = CommentHeader::new();
+    new.add_tag_verbatim("TITLE", "Noise");
+    new.add_tag_multi("artist", &["A", "C"]);
+    new.add_tag_single("date", "2001");
+
+    let changes = diff(&old, &new);
+    assert_eq!(
+        changes.added,
+        vec![("date".to_string(), "2001".to_string())]
+    );
+    assert_eq!(
+        changes.removed,
+        vec![("comment".to_string(), "old".to_string())]
+    );
+    assert_eq!(
+        changes.changed,
+        vec![TagChange {
+            tag: "artist".to_string(),
+            old: vec!["A".to_string(), "B".to_string()],
+            new: vec!["A".to_string(), "C".to_string()],
+        }]
+    );
+    assert_eq!(
+        changes.to_string(),
+        "-comment=old\n+date=2001\n~artist=A; B -> A; C\n"
+    );
+    assert!(diff(&old, &old.clone()).is_empty());
+}
@@ -0,0 +1,33 @@
+#![cfg(feature = "differential")]
+
+use oggvorbismeta::differential::{compare, set_reporter, Finding};
+use oggvorbismeta::{make_comment_header, read_comment_header, CommentHeader, VorbisComments};
+use std::fs::File;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static FINDINGS: AtomicUsize = AtomicUsize::new(0);
+
+fn count_finding(_finding: &Finding) {
+    FINDINGS.fetch_add(1, Ordering::SeqCst);
+}
+
+#[test]
+fn test_parsers_agree_on_truncations() {
+    let mut header = CommentHeader::new();
+    header.set_vendor("Ogg");
+    header.add_tag_single("artist", "Some Guy");
+    header.add_tag_single("title", "A very good song");
+    let packet = make_comment_header(&header);
+    for len in 0..=packet.len() {
+        assert_eq!(compare(&packet[..len]), None, "length {}", len);
+    }
+}
+
+#[test]
+fn test_reading_reports_nothing() {
+    set_reporter(Some(count_finding));
+    let f_in = File::open("tests/noise.ogg").expect("Can't open file");
+    read_comment_header(f_in);
+    set_reporter(None);
+    assert_eq!(FINDINGS.load(Ordering::SeqCst), 0);
+}
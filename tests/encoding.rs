@@ -0,0 +1,50 @@
+use oggvorbismeta::{
+    make_comment_header, CommentHeader, CommentHeaderRef, LegacyEncoding, VorbisComments,
+    VorbisRecode,
+};
+
+#[test]
+fn test_fallback_decoding() {
+    let mut header = CommentHeader::new();
+    header.set_vendor("me");
+    header.add_tag_single("title", "caf?");
+    header.add_tag_single("artist", "ok");
+    let mut packet = make_comment_header(&header);
+    let title_end = packet.len() - 1 - 4 - "artist=ok".len() - 1;
+    packet[title_end] = 0x80;
+
+    let (latin1, report) =
+        CommentHeaderRef::parse_with_fallback(&packet, LegacyEncoding::Latin1).unwrap();
+    assert_eq!(latin1.get("title"), Some("caf\u{80}"));
+    assert_eq!(latin1.get("artist"), Some("ok"));
+    assert!(!report.lossy_vendor);
+    assert_eq!(report.lossy_tags, ["title"]);
+
+    let (windows, _) =
+        CommentHeaderRef::parse_with_fallback(&packet, LegacyEncoding::Windows1252).unwrap();
+    assert_eq!(windows.get("title"), Some("caf€"));
+
+    assert_eq!(
+        LegacyEncoding::Windows1252.encode("caf€"),
+        Some(b"caf\x80".to_vec())
+    );
+    assert_eq!(LegacyEncoding::Latin1.encode("caf€"), None);
+}
+
+#[test]
+fn test_recode_values() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("title", "cafÃ©");
+    header.add_tag_single("artist", "Café");
+    header.add_tag_single("album", "plain");
+    header.add_tag_single("comment", "â€œquotedâ€");
+    assert_eq!(header.recode_values(LegacyEncoding::Latin1), 1);
+    assert_eq!(header.get_tag_single("title").unwrap(), "café");
+    assert_eq!(header.get_tag_single("artist").unwrap(), "Café");
+    assert_eq!(header.get_tag_single("album").unwrap(), "plain");
+
+    assert_eq!(header.recode_values(LegacyEncoding::Windows1252), 0);
+    header.set_tag_single("comment", "â€œquotedâ€\u{9d}");
+    assert_eq!(header.recode_values(LegacyEncoding::Windows1252), 1);
+    assert_eq!(header.get_tag_single("comment").unwrap(), "“quoted”");
+}
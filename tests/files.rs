@@ -0,0 +1,145 @@
+use oggvorbismeta::{
+    backup_path, read_comment_header_from_path, replace_comment_header_in_path,
+    safe_replace_comment_header_in_path_with_options, write_comment_header_to_path, CommentHeader,
+    SaveOptions, VorbisComments,
+};
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+fn test_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_path_apis() {
+    let dir = test_dir("path_apis");
+    let mut header = CommentHeader::new();
+    header.set_vendor("Ogg");
+    header.add_tag_single("title", "By path");
+
+    let out = dir.join("out.ogg");
+    let f_in = File::open("tests/noise.ogg").unwrap();
+    write_comment_header_to_path(f_in, &out, header.clone());
+    assert_eq!(read_comment_header_from_path(&out), header);
+
+    header.set_tag_single("title", "Replaced");
+    replace_comment_header_in_path(&out, header.clone());
+    assert_eq!(read_comment_header_from_path(&out), header);
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+}
+
+#[test]
+fn test_save_with_backup() {
+    let dir = test_dir("save_with_backup");
+    let path = dir.join("song.ogg");
+    fs::copy("tests/noise.ogg", &path).unwrap();
+    let original = fs::read(&path).unwrap();
+
+    let mut header = CommentHeader::new();
+    header.add_tag_single("title", "Saved");
+    let options = SaveOptions {
+        backup: true,
+        ..Default::default()
+    };
+    safe_replace_comment_header_in_path_with_options(&path, header.clone(), &options).unwrap();
+    assert_eq!(backup_path(&path), dir.join("song.ogg.bak"));
+    assert_eq!(fs::read(backup_path(&path)).unwrap(), original);
+    assert_eq!(read_comment_header_from_path(&path), header);
+
+    // A second save replaces the first backup
+    safe_replace_comment_header_in_path_with_options(&path, header.clone(), &options).unwrap();
+    assert_eq!(read_comment_header_from_path(backup_path(&path)), header);
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+}
+
+#[test]
+fn test_save_preserving_metadata() {
+    let dir = test_dir("save_preserving_metadata");
+    let path = dir.join("song.ogg");
+    fs::copy("tests/noise.ogg", &path).unwrap();
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+    let mut permissions = fs::metadata(&path).unwrap().permissions();
+    permissions.set_readonly(true);
+    fs::set_permissions(&path, permissions).unwrap();
+
+    let options = SaveOptions {
+        preserve_modified: true,
+        preserve_permissions: true,
+        ..Default::default()
+    };
+    let mut header = CommentHeader::new();
+    header.add_tag_single("title", "Same time");
+    safe_replace_comment_header_in_path_with_options(&path, header.clone(), &options).unwrap();
+    assert_eq!(read_comment_header_from_path(&path), header);
+    let metadata = fs::metadata(&path).unwrap();
+    assert_eq!(metadata.modified().unwrap(), modified);
+    assert!(metadata.permissions().readonly());
+}
+
+#[test]
+fn test_skip_unchanged() {
+    let dir = test_dir("skip_unchanged");
+    let path = dir.join("song.ogg");
+    fs::copy("tests/noise.ogg", &path).unwrap();
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+
+    let header = read_comment_header_from_path(&path);
+    let out = dir.join("out.ogg");
+    let report = write_comment_header_to_path(File::open(&path).unwrap(), &out, header.clone());
+    assert!(report.unchanged);
+
+    let options = SaveOptions {
+        skip_unchanged: true,
+        ..Default::default()
+    };
+    let rewritten =
+        safe_replace_comment_header_in_path_with_options(&path, header.clone(), &options).unwrap();
+    assert!(!rewritten);
+    assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), modified);
+
+    let mut header = header;
+    header.add_tag_single("title", "Changed");
+    let rewritten =
+        safe_replace_comment_header_in_path_with_options(&path, header.clone(), &options).unwrap();
+    assert!(rewritten);
+    assert_eq!(read_comment_header_from_path(&path), header);
+}
+
+#[test]
+fn test_concurrent_saves() {
+    let dir = test_dir("concurrent_saves");
+    let path = dir.join("song.ogg");
+    fs::copy("tests/noise.ogg", &path).unwrap();
+    let titles = ["First", "Second", "Third", "Fourth"];
+    std::thread::scope(|scope| {
+        for title in titles {
+            let path = &path;
+            scope.spawn(move || {
+                let mut header = CommentHeader::new();
+                header.add_tag_single("title", title);
+                replace_comment_header_in_path(path, header);
+            });
+        }
+    });
+    let title = read_comment_header_from_path(&path)
+        .get_tag_single("title")
+        .unwrap();
+    assert!(titles.contains(&title.as_str()));
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+}
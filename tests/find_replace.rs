@@ -0,0 +1,55 @@
+use oggvorbismeta::{
+    find_replace, find_replace_files, read_comment_header, CommentHeader, FieldFilter, Pattern,
+    VorbisComments,
+};
+use std::fs::{self, File};
+use std::path::PathBuf;
+
+fn make_header() -> CommentHeader {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("artist", "The Beatels");
+    header.add_tag_single("album", "Beatels For Sale");
+    header.add_tag_single("title", "Beatels");
+    header
+}
+
+#[test]
+fn test_find_replace_filtered() {
+    let mut header = make_header();
+    let pattern = Pattern::Plain("Beatels".to_string());
+    let filter = FieldFilter::Only(vec!["ARTIST".to_string(), "album".to_string()]);
+    assert_eq!(find_replace(&mut header, &pattern, "Beatles", &filter), 2);
+    assert_eq!(header.get_tag_single("artist").unwrap(), "The Beatles");
+    assert_eq!(header.get_tag_single("album").unwrap(), "Beatles For Sale");
+    assert_eq!(header.get_tag_single("title").unwrap(), "Beatels");
+
+    let filter = FieldFilter::Except(vec!["title".to_string()]);
+    assert_eq!(find_replace(&mut header, &pattern, "Beatles", &filter), 0);
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn test_find_replace_regex() {
+    let mut header = make_header();
+    let pattern = Pattern::Regex(regex::Regex::new(r"^The (\w+)$").unwrap());
+    assert_eq!(
+        find_replace(&mut header, &pattern, "$1, The", &FieldFilter::All),
+        1
+    );
+    assert_eq!(header.get_tag_single("artist").unwrap(), "Beatels, The");
+}
+
+#[test]
+fn test_find_replace_files() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("find_replace");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("noise.ogg");
+    fs::copy("tests/noise.ogg", &path).unwrap();
+
+    let pattern = Pattern::Plain("Noi".to_string());
+    let changed = find_replace_files(&[&path], &pattern, "Poi", &FieldFilter::All).unwrap();
+    assert!(changed >= 1);
+    let header = read_comment_header(File::open(&path).unwrap());
+    assert_eq!(header.get_tag_single("title").unwrap(), "Poise");
+}
@@ -0,0 +1,29 @@
+use oggvorbismeta::{id3v1_genre, split_genres, CommentHeader, VorbisComments, VorbisGenres};
+
+#[test]
+fn test_split_and_dedup() {
+    assert_eq!(split_genres(" Rock; Indie ;"), vec!["Rock", "Indie"]);
+    let mut header = CommentHeader::new();
+    header.add_tag_single("genre", "Rock; Indie");
+    header.add_tag_multi("GENRE", &["indie", "Shoegaze"]);
+    assert_eq!(header.genres(), vec!["Rock", "Indie", "Shoegaze"]);
+}
+
+#[test]
+fn test_normalize_genres() {
+    assert_eq!(id3v1_genre(17), Some("Rock"));
+    assert_eq!(id3v1_genre(147), Some("Synthpop"));
+    assert_eq!(id3v1_genre(200), None);
+
+    let mut header = CommentHeader::new();
+    header.add_tag_single("genre", "(17); Techno");
+    header.add_tag_single("genre", "rock;18;254");
+    let mut unmapped = header.clone();
+    header.normalize_genres(true);
+    assert_eq!(header.get_tag_multi("genre"), vec!["Rock", "Techno", "254"]);
+    unmapped.normalize_genres(false);
+    assert_eq!(
+        unmapped.get_tag_multi("genre"),
+        vec!["(17)", "Techno", "rock", "18", "254"]
+    );
+}
@@ -0,0 +1,82 @@
+use oggvorbismeta::{
+    check_conformance, ogg_crc32, safe_replace_comment_header_with_options, CommentHeader,
+    GranuleMode, ReplaceOptions, VorbisComments,
+};
+use std::fs;
+use std::io::Cursor;
+
+/// The granule position of every page, in order.
+fn granules(data: &[u8]) -> Vec<u64> {
+    let mut granules = vec![];
+    let mut pos = 0;
+    while pos + 27 <= data.len() {
+        let mut granule = [0u8; 8];
+        granule.copy_from_slice(&data[pos + 6..pos + 14]);
+        granules.push(u64::from_le_bytes(granule));
+        let segments = data[pos + 26] as usize;
+        let lacing = &data[pos + 27..pos + 27 + segments];
+        pos += 27 + segments + lacing.iter().map(|l| *l as usize).sum::<usize>();
+    }
+    granules
+}
+
+fn replaced(input: &[u8], options: &ReplaceOptions) -> Vec<u8> {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("title", &"Noise".repeat(100));
+    let f_in = Cursor::new(input);
+    let (f_out, _) = safe_replace_comment_header_with_options(f_in, header, options).unwrap();
+    f_out.into_inner()
+}
+
+#[test]
+fn test_granule_modes() {
+    let data = fs::read("tests/noise.ogg").unwrap();
+    let input = granules(&data);
+    let audio = &input[input.len() - 2..];
+
+    let preserved = granules(&replaced(
+        &data,
+        &ReplaceOptions {
+            granule_mode: GranuleMode::Preserve,
+            ..Default::default()
+        },
+    ));
+    assert_eq!(&preserved[preserved.len() - 2..], audio);
+
+    // An encoder computes them the same way, so they come out as in the input
+    for copy_pages in [false, true] {
+        let output = replaced(
+            &data,
+            &ReplaceOptions {
+                granule_mode: GranuleMode::Recompute,
+                copy_pages,
+                ..Default::default()
+            },
+        );
+        let recomputed = granules(&output);
+        assert_eq!(&recomputed[recomputed.len() - 2..], audio);
+        assert!(check_conformance(&output).is_empty());
+    }
+}
+
+#[test]
+fn test_recompute_wrong_granule() {
+    // The audio page, no longer the last one and with a wrong position
+    let mut data = fs::read("tests/noise.ogg").unwrap();
+    let page = &mut data[4429..];
+    page[5] = 0;
+    page[6..14].copy_from_slice(&1_000_000u64.to_le_bytes());
+    page[22..26].copy_from_slice(&[0; 4]);
+    let checksum = ogg_crc32(page);
+    page[22..26].copy_from_slice(&checksum.to_le_bytes());
+
+    for copy_pages in [false, true] {
+        let options = ReplaceOptions {
+            granule_mode: GranuleMode::Recompute,
+            copy_pages,
+            ..Default::default()
+        };
+        // The whole blocks decoded, without the 70 samples trimmed at the end
+        assert_eq!(granules(&replaced(&data, &options)), [0, 0, 4480]);
+    }
+}
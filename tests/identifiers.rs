@@ -0,0 +1,40 @@
+use oggvorbismeta::{
+    is_valid_barcode, normalize_isrc, CommentHeader, VorbisComments, VorbisIdentifierError,
+    VorbisIdentifiers,
+};
+
+#[test]
+fn test_identifier_formats() {
+    assert_eq!(normalize_isrc("us-rc1-76-07839").unwrap(), "USRC17607839");
+    assert!(normalize_isrc("USRC1760783").is_none());
+    assert!(normalize_isrc("1SRC17607839").is_none());
+
+    assert!(is_valid_barcode("4006381333931"));
+    assert!(is_valid_barcode("036000291452"));
+    assert!(is_valid_barcode("96385074"));
+    assert!(!is_valid_barcode("4006381333932"));
+    assert!(!is_valid_barcode("40063813339"));
+}
+
+#[test]
+fn test_identifier_accessors() {
+    let mut header = CommentHeader::new();
+    header.set_isrc("USRC1-76-07839").unwrap();
+    assert_eq!(header.get_tag_single("isrc").unwrap(), "USRC17607839");
+    assert_eq!(
+        header.set_barcode("123"),
+        Err(VorbisIdentifierError::InvalidBarcode("123".to_string()))
+    );
+    header.set_catalog_number(" CAT-001 ").unwrap();
+    assert_eq!(header.catalog_number().unwrap(), "CAT-001");
+    assert!(header.set_catalog_number("a\nb").is_err());
+
+    header.add_tag_single("barcode", "4006381333932");
+    assert!(header.barcode().is_none());
+    assert_eq!(
+        header.invalid_identifiers(),
+        vec![VorbisIdentifierError::InvalidBarcode(
+            "4006381333932".to_string()
+        )]
+    );
+}
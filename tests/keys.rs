@@ -0,0 +1,72 @@
+use oggvorbismeta::{
+    keys, CommentHeader, TagKey, VorbisComments, VorbisCompilation, VorbisStandardTags,
+};
+
+#[test]
+fn test_standard_accessors() {
+    let mut header = CommentHeader::new();
+    assert_eq!(header.title(), None);
+    header.set_title("First");
+    header.set_title("A very good song");
+    header.set_artist("Some Guy");
+    assert_eq!(header.title().unwrap(), "A very good song");
+    assert_eq!(header.get_tag_multi(keys::TITLE).len(), 1);
+    assert_eq!(header.get_tag_single("artist").unwrap(), "Some Guy");
+}
+
+#[test]
+fn test_total_accessors_clear_aliases() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("totaltracks", "12");
+    header.add_tag_single("totaldiscs", "3");
+    assert_eq!(header.track_total().unwrap(), "12");
+    header.set_track_total("10");
+    header.set_disc_total("2");
+    assert!(header.get_tag_single("totaltracks").is_none());
+    assert!(header.get_tag_single("totaldiscs").is_none());
+    assert_eq!(header.track_total().unwrap(), "10");
+    assert_eq!(header.disc_total().unwrap(), "2");
+}
+
+#[test]
+fn test_key_constants_are_case_insensitive() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("albumartist", "Another Dude");
+    assert_eq!(header.album_artist().unwrap(), "Another Dude");
+    assert_eq!(header.get_tag_multi(keys::ALBUMARTIST).len(), 1);
+}
+
+#[test]
+fn test_tag_key() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single(TagKey::Title, "Noise");
+    header.add_tag_single(TagKey::Other("MOOD".to_string()), "Calm");
+    assert_eq!(header.get_tag_single("TITLE").unwrap(), "Noise");
+    assert_eq!(header.get_tag_single(TagKey::from("mood")).unwrap(), "Calm");
+    assert_eq!(TagKey::from("tracknumber"), TagKey::TrackNumber);
+    assert_eq!(TagKey::AlbumArtist.to_string(), "ALBUMARTIST");
+
+    header.clear_tag(TagKey::Title);
+    assert!(header.get_tag_single(TagKey::Title).is_none());
+}
+
+#[test]
+fn test_compilation_flag() {
+    let mut header = CommentHeader::new();
+    assert!(!header.is_compilation());
+    for (value, expected) in [
+        ("1", true),
+        ("TRUE", true),
+        (" yes", true),
+        ("0", false),
+        ("false", false),
+    ] {
+        header.clear_tag("compilation");
+        header.add_tag_single("COMPILATION", value);
+        assert_eq!(header.is_compilation(), expected, "{}", value);
+    }
+    header.set_compilation(true);
+    assert_eq!(header.get_tag_multi("compilation"), vec!["1"]);
+    header.set_compilation(false);
+    assert_eq!(header.get_tag_multi("compilation"), vec!["0"]);
+}
@@ -0,0 +1,36 @@
+use oggvorbismeta::{CommentHeader, TagKey, VorbisComments, VorbisLanguageTags};
+
+#[test]
+fn test_localized_tags() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("description", "Noise");
+    header.add_tag_single("DESCRIPTION-FRA", "Bruit");
+    header.add_tag_single("description-eng", "Noise, in English");
+    header.add_tag_single("DESCRIPTIONS", "not a suffix");
+    header.add_tag_single("comment-deu", "Rauschen");
+
+    assert_eq!(
+        header.get_localized(TagKey::Description),
+        vec![
+            (None, "Noise".to_string()),
+            (Some("fra".to_string()), "Bruit".to_string()),
+            (Some("eng".to_string()), "Noise, in English".to_string()),
+        ]
+    );
+    assert_eq!(header.tag_languages("description"), vec!["fra", "eng"]);
+    assert_eq!(
+        header
+            .get_localized_single("description", Some("FRA"))
+            .unwrap(),
+        "Bruit"
+    );
+
+    header.set_localized("description", Some("fra"), "Du bruit");
+    header.clear_localized("description", None);
+    assert_eq!(header.get_localized("description").len(), 2);
+    assert_eq!(
+        header.get_tag_single("description-fra").unwrap(),
+        "Du bruit"
+    );
+    assert_eq!(header.tag_languages("comment"), vec!["deu"]);
+}
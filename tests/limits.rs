@@ -0,0 +1,84 @@
+use ogg::{PacketWriteEndInfo, PacketWriter};
+use oggvorbismeta::{
+    make_comment_header, read_comment_header, safe_read_comment_header_with_limits, CommentHeader,
+    CommentHeaderRef, LazyCommentHeader, ParseLimits, VorbisComments, VorbisReadCommentError,
+};
+use std::fs::File;
+
+#[test]
+fn test_parse_limits() {
+    // A packet claiming 4 billion comments
+    let mut packet = b"\x03vorbis".to_vec();
+    packet.extend(0u32.to_le_bytes());
+    packet.extend(u32::MAX.to_le_bytes());
+    let limits = ParseLimits::untrusted();
+    assert!(matches!(
+        CommentHeaderRef::parse_with_limits(&packet, &limits),
+        Err(VorbisReadCommentError::TooManyComments(4294967295))
+    ));
+    assert!(matches!(
+        LazyCommentHeader::parse_with_limits(&packet, &limits),
+        Err(VorbisReadCommentError::TooManyComments(_))
+    ));
+
+    let mut header = CommentHeader::new();
+    header.set_vendor(&"v".repeat(2000));
+    assert!(matches!(
+        CommentHeaderRef::parse_with_limits(&make_comment_header(&header), &limits),
+        Err(VorbisReadCommentError::VendorTooLong(2000))
+    ));
+
+    let mut header = CommentHeader::new();
+    header.add_tag_single("lyrics", &"la".repeat(100));
+    let packet = make_comment_header(&header);
+    let limits = ParseLimits {
+        max_comment_len: Some(100),
+        ..Default::default()
+    };
+    assert!(matches!(
+        CommentHeaderRef::parse_with_limits(&packet, &limits),
+        Err(VorbisReadCommentError::CommentTooLong(207))
+    ));
+    assert!(CommentHeaderRef::parse_with_limits(&packet, &ParseLimits::untrusted()).is_ok());
+}
+
+#[test]
+fn test_read_with_limits() {
+    let f_in = File::open("tests/noise.ogg").unwrap();
+    let header = safe_read_comment_header_with_limits(f_in, &ParseLimits::untrusted()).unwrap();
+    assert_eq!(
+        header,
+        read_comment_header(File::open("tests/noise.ogg").unwrap())
+    );
+
+    let limits = ParseLimits {
+        max_header_size: Some(100),
+        ..Default::default()
+    };
+    let f_in = File::open("tests/noise.ogg").unwrap();
+    assert!(matches!(
+        safe_read_comment_header_with_limits(f_in, &limits),
+        Err(VorbisReadCommentError::HeaderTooLarge(_))
+    ));
+}
+
+#[test]
+fn test_oversized_identification_packet() {
+    // A first packet that keeps going instead of the 30 bytes of a real one
+    let mut packet = b"\x01vorbis".to_vec();
+    packet.resize(1 << 20, 0);
+    let mut data = vec![];
+    let mut writer = PacketWriter::new(&mut data);
+    writer
+        .write_packet(packet, 1, PacketWriteEndInfo::EndPage, 0)
+        .unwrap();
+    drop(writer);
+    let limits = ParseLimits {
+        max_header_size: Some(64 << 10),
+        ..Default::default()
+    };
+    assert!(matches!(
+        safe_read_comment_header_with_limits(&data[..], &limits),
+        Err(VorbisReadCommentError::HeaderTooLarge(_))
+    ));
+}
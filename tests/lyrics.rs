@@ -0,0 +1,33 @@
+use oggvorbismeta::{CommentHeader, Lyrics, VorbisComments, VorbisLyrics};
+
+#[test]
+fn test_read_lyrics_variants() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("UNSYNCEDLYRICS", "la la la");
+    header.add_tag_single("LYRICS-ENG", "hello");
+    header.add_tag_single("LYRICSIST", "not lyrics");
+    assert_eq!(
+        header.lyrics(),
+        vec![
+            Lyrics {
+                language: None,
+                text: "la la la".to_string()
+            },
+            Lyrics {
+                language: Some("eng".to_string()),
+                text: "hello".to_string()
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_set_lyrics_per_language() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("LYRICS-ENG", "hello");
+    header.add_tag_single("LYRICS", "la la la");
+    header.set_lyrics(Some("ENG"), "goodbye");
+    assert_eq!(header.lyrics().len(), 2);
+    assert_eq!(header.get_tag_single("lyrics-eng").unwrap(), "goodbye");
+    assert_eq!(header.get_tag_single("lyrics").unwrap(), "la la la");
+}
@@ -0,0 +1,49 @@
+use oggvorbismeta::{CommentHeader, MergeStrategy, VorbisComments, VorbisMerge};
+
+fn existing() -> CommentHeader {
+    let mut header = CommentHeader::new();
+    header.set_vendor("Ogg");
+    header.add_tag_single("title", "Noise");
+    header.add_tag_single("genre", "Jazz");
+    header
+}
+
+fn scraped() -> CommentHeader {
+    let mut header = CommentHeader::new();
+    header.set_vendor("Scraper");
+    header.add_tag_single("GENRE", "Rock");
+    header.add_tag_single("genre", "Jazz");
+    header.add_tag_single("date", "2001");
+    header
+}
+
+#[test]
+fn test_merge_strategies() {
+    let mut header = existing();
+    header.merge(&scraped(), MergeStrategy::Overwrite);
+    assert_eq!(header.get_tag_multi("genre"), vec!["Rock", "Jazz"]);
+    assert_eq!(header.get_tag_single("date").unwrap(), "2001");
+    assert_eq!(header.get_vendor(), "Ogg");
+
+    let mut header = existing();
+    header.merge(&scraped(), MergeStrategy::Append);
+    assert_eq!(header.get_tag_multi("genre"), vec!["Jazz", "Rock"]);
+
+    let mut header = existing();
+    header.merge(&scraped(), MergeStrategy::KeepExisting);
+    assert_eq!(header.get_tag_multi("genre"), vec!["Jazz"]);
+    assert_eq!(header.get_tag_single("date").unwrap(), "2001");
+    assert_eq!(header.get_tag_single("title").unwrap(), "Noise");
+}
+
+#[test]
+fn test_merge_per_tag() {
+    let mut header = existing();
+    header.add_tag_single("date", "1999");
+    header.merge_with(&scraped(), |tag| match tag {
+        "date" => MergeStrategy::KeepExisting,
+        _ => MergeStrategy::Overwrite,
+    });
+    assert_eq!(header.get_tag_multi("date"), vec!["1999"]);
+    assert_eq!(header.get_tag_multi("genre"), vec!["Rock", "Jazz"]);
+}
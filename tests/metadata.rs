@@ -1,9 +1,18 @@
-use lewton;
-use oggvorbismeta;
+use ogg::PacketReader;
 use oggvorbismeta::{
-    make_comment_header, read_comment_header, replace_comment_header, CommentHeader, VorbisComments,
+    make_comment_header, read_comment_header, replace_comment_header, replace_comment_header_into,
+    safe_make_comment_header_with_case, safe_patch_comment_header_in_place,
+    safe_read_comment_header, safe_replace_comment_header,
+    safe_replace_comment_header_into_with_options, safe_replace_comment_header_streaming,
+    safe_replace_comment_header_with_options, transform_comment_header, CasePolicy, CommentHeader,
+    ReplaceOptions, RewriteWarning, ShrinkStrategy, VorbisComments, VorbisReadCommentError,
+    VorbisReplaceCommentError,
 };
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{Cursor, Read, Seek, SeekFrom};
 
 fn make_header() -> CommentHeader {
     let mut new_comment = CommentHeader::new();
@@ -52,7 +61,7 @@ fn test_artist() {
 #[test]
 fn test_add_multi() {
     let mut header = make_header();
-    header.add_tag_multi("letters", &vec!["a", "b", "c"]);
+    header.add_tag_multi("letters", &["a", "b", "c"]);
     assert_eq!(header.get_tag_multi("letters").len(), 3);
     assert_eq!(header.get_tag_multi("letters")[2], "c".to_string());
 }
@@ -73,6 +82,15 @@ fn test_get_tag_single_fail() {
     let _sometag = header.get_tag_single("sometag").unwrap();
 }
 
+#[test]
+fn test_borrowed_accessors() {
+    let header = make_header();
+    let artists: Vec<&str> = header.get_tag_refs("ARTIST").collect();
+    assert_eq!(artists, vec!["Some Guy", "Another Dude"]);
+    assert_eq!(header.get_tag_single_ref("Artist"), Some("Some Guy"));
+    assert_eq!(header.get_tag_single_ref("nothing"), None);
+}
+
 #[test]
 fn test_clear() {
     let mut header = make_header();
@@ -81,15 +99,280 @@ fn test_clear() {
     assert_eq!(header.get_tag_multi("artist").len(), 0);
 }
 
+#[test]
+fn test_many_edits_keep_order() {
+    let mut header = CommentHeader::new();
+    for n in 0..100 {
+        header.add_tag_single(format!("Tag{}", n % 10), &n.to_string());
+    }
+    for n in 0..8 {
+        header.clear_tag(format!("TAG{}", n));
+    }
+    header.remove_tag_value("tag9", "9");
+    assert_eq!(header.len(), 19);
+    assert!(!header.contains_tag("tag0"));
+    assert_eq!(header.get_tag_refs("tag8").count(), 10);
+    assert_eq!(header.get_tag_single_ref("TAG9"), Some("19"));
+    let values = header.iter().map(|(_, value)| value).collect::<Vec<&str>>();
+    assert_eq!(&values[..3], &["8", "18", "19"]);
+    assert_eq!(header.iter().next_back(), Some(("tag9", "99")));
+}
+
+#[test]
+fn test_set_tag() {
+    let mut header = make_header();
+    header.set_tag_single("TITLE", "First");
+    header.set_tag_single("title", "Second");
+    assert_eq!(header.get_tag_multi("title"), vec!["Second"]);
+    header.set_tag_multi("artist", &["A", "B"]);
+    assert_eq!(header.get_tag_multi("artist"), vec!["A", "B"]);
+}
+
+#[test]
+fn test_remove_tag_value() {
+    let mut header = make_header();
+    header.set_tag_multi("genre", &["Rock", "Indie", "Pop", "Indie"]);
+    assert_eq!(header.remove_tag_value("GENRE", "Indie"), 2);
+    assert_eq!(header.remove_tag_value("genre", "rock"), 0);
+    assert_eq!(header.get_tag_multi("genre"), vec!["Rock", "Pop"]);
+    let removed = header.remove_tag_values_where("genre", |genre| genre.starts_with('P'));
+    assert_eq!(removed, 1);
+    assert_eq!(header.get_tag_multi("genre"), vec!["Rock"]);
+    assert_eq!(header.get_tag_multi("artist").len(), 2);
+}
+
+#[test]
+fn test_rename_tag() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("ALBUM ARTIST", "A");
+    header.add_tag_single("title", "T");
+    header.add_tag_single("album artist", "B");
+    assert_eq!(header.rename_tag("Album Artist", "ALBUMARTIST"), 2);
+    assert_eq!(header.get_tag_multi("albumartist"), vec!["A", "B"]);
+    let keys = header.iter().map(|(key, _)| key).collect::<Vec<&str>>();
+    assert_eq!(keys, vec!["albumartist", "title", "albumartist"]);
+}
+
+#[test]
+fn test_map_values_and_keys() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("title", " Noise\u{200b} ");
+    header.add_tag_single("artist", "Nobody");
+    header.add_tag_verbatim("MB_TRACKID", "1234");
+    let changed =
+        header.map_values(|_, value| Some(value.replace('\u{200b}', "").trim().to_string()));
+    assert_eq!(changed, 1);
+    assert_eq!(header.get_tag_single("title").unwrap(), "Noise");
+
+    let renamed = header.map_keys(|key| {
+        key.strip_prefix("MB_")
+            .map(|rest| format!("MUSICBRAINZ_{}", rest))
+    });
+    assert_eq!(renamed, 1);
+    assert_eq!(
+        header.get_tag_single("musicbrainz_trackid").unwrap(),
+        "1234"
+    );
+    assert_eq!(header.map_keys(|key| Some(key.to_string())), 0);
+}
+
+#[test]
+fn test_entry() {
+    let mut header = make_header();
+    header.entry("genre").or_insert("Unknown").append("Noise");
+    assert_eq!(header.get_tag_multi("genre"), vec!["Unknown", "Noise"]);
+    header
+        .entry("GENRE")
+        .or_insert("Ignored")
+        .and_modify(|genre| genre.make_ascii_uppercase());
+    assert_eq!(header.get_tag_multi("genre"), vec!["UNKNOWN", "NOISE"]);
+
+    let entry = header.entry("Artist");
+    assert!(entry.is_occupied());
+    assert_eq!(entry.key(), "artist");
+    assert_eq!(entry.remove().len(), 2);
+    assert!(!header.entry("artist").is_occupied());
+}
+
+#[test]
+fn test_iter_and_extend() {
+    let header = make_header();
+    let artists = header
+        .iter()
+        .filter(|(key, _)| *key == "artist")
+        .map(|(_, value)| value)
+        .collect::<Vec<&str>>();
+    assert_eq!(artists, vec!["Some Guy", "Another Dude"]);
+    assert_eq!(header.iter().len(), header.len());
+
+    let mut copy = CommentHeader::new();
+    copy.extend_tags(
+        header
+            .iter()
+            .rev()
+            .map(|(key, value)| (key.to_uppercase(), value.to_string())),
+    );
+    assert_eq!(copy.iter().next(), header.iter().next_back());
+}
+
+#[test]
+fn test_map_conversions() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("Title", "Noise");
+    header.add_tag_verbatim("ARTIST", "A");
+    header.add_tag_single("artist", "B");
+    let map = header.to_map();
+    assert_eq!(
+        map.keys().collect::<Vec<&String>>(),
+        vec!["artist", "title"]
+    );
+    assert_eq!(map["artist"], vec!["A", "B"]);
+
+    let hash_map = map.into_iter().collect::<HashMap<String, Vec<String>>>();
+    let rebuilt = CommentHeader::from_map(hash_map);
+    assert_eq!(rebuilt.get_tag_multi("artist"), vec!["A", "B"]);
+    assert_eq!(rebuilt.get_tag_names(), vec!["artist", "title"]);
+}
+
+#[test]
+fn test_retain() {
+    let mut header = make_header();
+    header.add_tag_single("MUSICBRAINZ_TRACKID", "1234");
+    header.add_tag_single("musicbrainz_albumid", "5678");
+    header.retain(|key, _| !key.to_uppercase().starts_with("MUSICBRAINZ_"));
+    assert!(header
+        .get_tag_names()
+        .iter()
+        .all(|name| !name.starts_with("musicbrainz")));
+    header.retain(|key, value| key != "artist" || value != "Some Guy");
+    assert_eq!(header.get_tag_multi("artist"), vec!["Another Dude"]);
+}
+
+#[test]
+fn test_filter_and_remove_keys() {
+    let mut header = make_header();
+    header.add_tag_verbatim("MUSICBRAINZ_TRACKID", "1234");
+    header.add_tag_single("musicbrainz_albumid", "5678");
+    header.add_tag_single("musicbrainzish", "x");
+    assert_eq!(header.remove_keys(&["MusicBrainz_*", "track?umber"]), 3);
+    assert_eq!(
+        header.get_tag_names(),
+        vec!["album", "artist", "date", "musicbrainzish", "title"]
+    );
+    assert_eq!(header.filter_keys(&["ARTIST", "*ish"]), 3);
+    assert_eq!(header.get_tag_names(), vec!["artist", "musicbrainzish"]);
+    assert_eq!(header.filter_keys(&["*"]), 0);
+}
+
+#[test]
+fn test_inspection() {
+    let mut header = CommentHeader::new();
+    assert!(header.is_empty());
+    assert_eq!(header.encoded_size(), make_comment_header(&header).len());
+    header = make_header();
+    header.add_tag_single("comment", "é");
+    assert!(header.contains_tag("ARTIST"));
+    assert!(!header.contains_tag("composer"));
+    assert_eq!(header.len(), header.iter().count());
+    assert_eq!(header.encoded_size(), make_comment_header(&header).len());
+}
+
+#[test]
+fn test_verbatim_keys() {
+    let mut header = CommentHeader::new();
+    header.add_tag_verbatim("Artist", "A");
+    header.add_tag_single("ARTIST", "B");
+    header.set_tag_verbatim("TiTlE", "Noise");
+    assert_eq!(header.get_tag_multi("artist"), vec!["A", "B"]);
+    assert_eq!(header.get_tag_names_verbatim(), vec!["Artist", "TiTlE"]);
+
+    // Casing survives a write and read
+    let packet = make_comment_header(&header);
+    let read: CommentHeader = lewton::header::read_header_comment(&packet).unwrap().into();
+    assert_eq!(read.get_tag_names_verbatim(), vec!["Artist", "TiTlE"]);
+}
+
+#[test]
+fn test_case_policy() {
+    let mut header = CommentHeader::new();
+    header.add_tag_verbatim("Artist", "A");
+    header.add_tag_single("title", "Noise");
+    let packet = safe_make_comment_header_with_case(&header, CasePolicy::Uppercase).unwrap();
+    let read: CommentHeader = lewton::header::read_header_comment(&packet).unwrap().into();
+    assert_eq!(read.get_tag_names_verbatim(), vec!["ARTIST", "TITLE"]);
+
+    let options = ReplaceOptions {
+        case_policy: CasePolicy::Lowercase,
+        ..Default::default()
+    };
+    let f_in = File::open("tests/noise.ogg").expect("Can't open file");
+    let (f_out, _) = safe_replace_comment_header_with_options(f_in, header, &options).unwrap();
+    assert_eq!(
+        read_comment_header(f_out).get_tag_names_verbatim(),
+        vec!["artist", "title"]
+    );
+}
+
+#[test]
+fn test_pretty_string() {
+    let mut header = CommentHeader::new();
+    header.set_vendor("Ogg");
+    header.add_tag_single("title", "Noise");
+    header.add_tag_multi("artist", &["A", "B"]);
+    assert_eq!(
+        header.to_pretty_string(),
+        "title=Noise\nartist=A\nartist=B\n"
+    );
+}
+
 #[test]
 fn test_pack_unpack() {
     let header = make_header();
     let binary_header = make_comment_header(&header);
-    let unpacked = lewton::header::read_header_comment(&binary_header).unwrap();
+    let unpacked: CommentHeader = lewton::header::read_header_comment(&binary_header)
+        .unwrap()
+        .into();
     assert_eq!(unpacked.get_tag_names().len(), 5);
     assert_eq!(unpacked.get_vendor(), "Ogg".to_string());
 }
 
+#[test]
+fn test_vorbis_tags_traits() {
+    let header = make_header();
+    let lewton_header: lewton::header::CommentHeader = header.clone().into();
+    assert_eq!(lewton_header.vendor, "Ogg");
+    let back: CommentHeader = lewton_header.into();
+    assert_eq!(back, header);
+
+    let collected: CommentHeader = header.clone().into_iter().collect();
+    assert_eq!((&collected).into_iter().len(), header.len());
+    assert_eq!(collected.get_vendor(), "");
+    let mut extended = CommentHeader::new();
+    extended.extend(vec![("TITLE".to_string(), "Noise".to_string())]);
+    assert_eq!(extended.to_string(), "title=Noise\n");
+}
+
+#[test]
+fn test_equality_and_hashing() {
+    let hash = |header: &CommentHeader| {
+        let mut hasher = DefaultHasher::new();
+        header.hash(&mut hasher);
+        hasher.finish()
+    };
+    let header = make_header();
+    assert_eq!(hash(&header), hash(&header.clone()));
+
+    let mut reordered = CommentHeader::new();
+    reordered.set_vendor("Ogg");
+    for (key, value) in header.iter().rev() {
+        reordered.add_tag_verbatim(key.to_uppercase(), value);
+    }
+    assert_ne!(reordered, header);
+    assert!(reordered.eq_ignore_order_and_case(&header));
+    reordered.set_tag_single("title", "Other");
+    assert!(!reordered.eq_ignore_order_and_case(&header));
+}
+
 #[test]
 fn test_read_from_file() {
     let f_in = File::open("tests/noise.ogg").expect("Can't open file");
@@ -109,3 +392,354 @@ fn test_update_file() {
     assert_eq!(unpacked.get_tag_names().len(), 5);
     assert_eq!(unpacked.get_vendor(), "Ogg".to_string());
 }
+
+fn read_truncated(len: usize) -> Vec<u8> {
+    let mut data = std::fs::read("tests/noise.ogg").expect("Can't open file");
+    data.truncate(len);
+    data
+}
+
+#[test]
+fn test_read_truncated_header() {
+    let f_in = Cursor::new(read_truncated(100));
+    let result = safe_read_comment_header(f_in);
+    assert!(matches!(result, Err(VorbisReadCommentError::Truncated)));
+}
+
+/// Counts the bytes read through it.
+struct CountingReader<R> {
+    inner: R,
+    read: usize,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.read += read;
+        Ok(read)
+    }
+}
+
+impl<R: Seek> Seek for CountingReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[test]
+fn test_read_stops_after_comment_header() {
+    // The audio page of noise.ogg starts at 4429
+    let mut f_in = CountingReader {
+        inner: File::open("tests/noise.ogg").unwrap(),
+        read: 0,
+    };
+    let header = safe_read_comment_header(&mut f_in).unwrap();
+    assert!(header.get_vendor().starts_with("Xiph.Org libVorbis"));
+    assert!(f_in.read <= 4429);
+}
+
+/// A reader that can't seek, like a pipe.
+struct Pipe<R>(R);
+
+impl<R: Read> Read for Pipe<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+#[test]
+fn test_non_seekable_input() {
+    let f_in = Pipe(File::open("tests/noise.ogg").unwrap());
+    assert_eq!(
+        read_comment_header(f_in),
+        read_comment_header(File::open("tests/noise.ogg").unwrap())
+    );
+
+    let header = make_header();
+    let f_in = Pipe(File::open("tests/noise.ogg").unwrap());
+    let mut out = vec![];
+    let options = ReplaceOptions {
+        check_conformance: true,
+        ..Default::default()
+    };
+    let report =
+        safe_replace_comment_header_streaming(f_in, &mut out, header.clone(), &options).unwrap();
+    assert!(!report.truncated);
+    assert_eq!(read_comment_header(Pipe(&out[..])), header);
+}
+
+#[test]
+fn test_transform_stream() {
+    let f_in = Pipe(File::open("tests/noise.ogg").unwrap());
+    let mut out = vec![];
+    transform_comment_header(f_in, &mut out, |header| {
+        header.set_tag_single("title", "Piped")
+    });
+    let mut expected = read_comment_header(File::open("tests/noise.ogg").unwrap());
+    expected.set_tag_single("title", "Piped");
+    assert_eq!(read_comment_header(Pipe(&out[..])), expected);
+}
+
+#[test]
+fn test_update_truncated_file() {
+    let f_in = Cursor::new(read_truncated(5000));
+    let options = ReplaceOptions {
+        mark_end_of_stream: true,
+        ..Default::default()
+    };
+    let (f_out, report) =
+        safe_replace_comment_header_with_options(f_in, make_header(), &options).unwrap();
+    assert!(report.truncated);
+
+    let mut reader = PacketReader::new(f_out);
+    let mut last = None;
+    while let Some(packet) = reader.read_packet().unwrap() {
+        last = Some(packet);
+    }
+    assert!(last.unwrap().last_in_stream());
+}
+
+#[test]
+fn test_salvage_truncated_page() {
+    // Cut in the fourth packet of the audio page, which has 8
+    let data = read_truncated(5470);
+    for copy_pages in [false, true] {
+        let options = ReplaceOptions {
+            copy_pages,
+            salvage_truncated_page: true,
+            mark_end_of_stream: true,
+            ..Default::default()
+        };
+        let (f_out, report) =
+            safe_replace_comment_header_with_options(Cursor::new(&data), make_header(), &options)
+                .unwrap();
+        assert!(report.truncated);
+        assert_eq!(
+            report.stats.warnings,
+            [
+                RewriteWarning::PartialPageSalvaged { packets: 3 },
+                RewriteWarning::EndOfStreamMarked
+            ]
+        );
+
+        let mut reader = PacketReader::new(f_out);
+        let mut packets = vec![];
+        while let Some(packet) = reader.read_packet().unwrap() {
+            packets.push(packet);
+        }
+        assert_eq!(packets.len(), 6);
+        let last = packets.last().unwrap();
+        assert!(last.last_in_stream());
+        assert!(last.absgp_page() > 0 && last.absgp_page() < 4410);
+
+        let mut piped = vec![];
+        let report =
+            safe_replace_comment_header_streaming(&data[..], &mut piped, make_header(), &options)
+                .unwrap();
+        assert_eq!(
+            report.stats.warnings[0],
+            RewriteWarning::PartialPageSalvaged { packets: 3 }
+        );
+    }
+
+    let (f_out, report) = safe_replace_comment_header_with_options(
+        Cursor::new(&data),
+        make_header(),
+        &ReplaceOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(report.stats.warnings, [RewriteWarning::PartialPageDropped]);
+    let mut reader = PacketReader::new(f_out);
+    let mut count = 0;
+    while reader.read_packet().unwrap().is_some() {
+        count += 1;
+    }
+    assert_eq!(count, 3);
+}
+
+#[test]
+fn test_update_complete_file_not_truncated() {
+    let f_in = File::open("tests/noise.ogg").expect("Can't open file");
+    let (f_out, report) =
+        safe_replace_comment_header_with_options(f_in, make_header(), &ReplaceOptions::default())
+            .unwrap();
+    assert!(!report.truncated);
+    assert_eq!(read_comment_header(f_out).get_vendor(), "Ogg".to_string());
+}
+
+#[test]
+fn test_replace_into_sink() {
+    let f_in = File::open("tests/noise.ogg").expect("Can't open file");
+    let buffered = replace_comment_header(f_in, make_header()).into_inner();
+
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("replace_into.ogg");
+    let f_in = File::open("tests/noise.ogg").expect("Can't open file");
+    let report = replace_comment_header_into(f_in, File::create(&path).unwrap(), make_header());
+    assert!(!report.truncated);
+    assert_eq!(std::fs::read(&path).unwrap(), buffered);
+
+    let options = ReplaceOptions {
+        check_conformance: true,
+        ..Default::default()
+    };
+    let mut sink: Vec<u8> = vec![];
+    let f_in = File::open("tests/noise.ogg").expect("Can't open file");
+    safe_replace_comment_header_into_with_options(f_in, &mut sink, make_header(), &options)
+        .unwrap();
+    assert_eq!(sink, buffered);
+}
+
+#[test]
+fn test_update_corrupt_file_fails() {
+    let mut data = std::fs::read("tests/noise.ogg").expect("Can't open file");
+    let last = data.len() - 10;
+    data[last] ^= 0xff;
+    let result = safe_replace_comment_header(Cursor::new(data), make_header());
+    assert!(matches!(
+        result,
+        Err(VorbisReplaceCommentError::ReadStream(_))
+    ));
+}
+
+// tiny_pages.ogg is noise.ogg with the comment and setup headers spread over
+// one page per lacing segment, and an empty page in the middle of the comment.
+#[test]
+fn test_read_from_tiny_pages() {
+    let f_in = File::open("tests/tiny_pages.ogg").expect("Can't open file");
+    let read_comments = read_comment_header(f_in);
+    assert_eq!(read_comments.get_vendor(), "tiny page encoder".to_string());
+    assert_eq!(
+        read_comments.get_tag_single("title").unwrap(),
+        "Noise".to_string()
+    );
+    assert_eq!(
+        read_comments.get_tag_single("description").unwrap().len(),
+        1000
+    );
+}
+
+#[test]
+fn test_update_tiny_pages() {
+    let f_in = File::open("tests/tiny_pages.ogg").expect("Can't open file");
+    let (f_out, report) =
+        safe_replace_comment_header_with_options(f_in, make_header(), &ReplaceOptions::default())
+            .unwrap();
+    assert!(!report.truncated);
+    let mut reader = PacketReader::new(f_out);
+    let mut packets = 0;
+    while reader.read_packet().unwrap().is_some() {
+        packets += 1;
+    }
+    assert_eq!(packets, 11);
+}
+
+fn comment_packet_len(data: &[u8]) -> usize {
+    let mut reader = PacketReader::new(Cursor::new(data));
+    reader.read_packet().unwrap();
+    reader.read_packet().unwrap().unwrap().data.len()
+}
+
+#[test]
+fn test_preserve_vendor() {
+    let original = read_comment_header(File::open("tests/noise.ogg").unwrap()).get_vendor();
+    assert!(!original.is_empty());
+    let options = ReplaceOptions {
+        preserve_vendor: true,
+        ..Default::default()
+    };
+    let f_in = File::open("tests/noise.ogg").expect("Can't open file");
+    let (f_out, _) =
+        safe_replace_comment_header_with_options(f_in, CommentHeader::new(), &options).unwrap();
+    assert_eq!(read_comment_header(f_out).get_vendor(), original);
+
+    let f_in = File::open("tests/noise.ogg").expect("Can't open file");
+    let (f_out, _) =
+        safe_replace_comment_header_with_options(f_in, make_header(), &options).unwrap();
+    assert_eq!(read_comment_header(f_out).get_vendor(), "Ogg");
+}
+
+#[test]
+fn test_shrink_strategies() {
+    let original = std::fs::read("tests/tiny_pages.ogg").unwrap();
+    let old_len = comment_packet_len(&original);
+    let mut header = CommentHeader::new();
+    header.add_tag_single("title", "Short");
+    let replace = |shrink| {
+        let options = ReplaceOptions {
+            shrink,
+            ..Default::default()
+        };
+        safe_replace_comment_header_with_options(Cursor::new(&original), header.clone(), &options)
+            .unwrap()
+    };
+
+    let (f_out, report) = replace(ShrinkStrategy::Compact);
+    assert_eq!(report.shrink, Some(ShrinkStrategy::Compact));
+    assert_eq!(report.slack, 0);
+    assert!(comment_packet_len(f_out.get_ref()) < old_len);
+
+    let (f_out, report) = replace(ShrinkStrategy::PadPacket);
+    assert_eq!(comment_packet_len(f_out.get_ref()), old_len);
+    assert_eq!(read_comment_header(f_out), header);
+    assert_eq!(report.slack, old_len - make_comment_header(&header).len());
+
+    let (f_out, report) = replace(ShrinkStrategy::PaddingTag);
+    assert_eq!(report.shrink, Some(ShrinkStrategy::PaddingTag));
+    assert_eq!(comment_packet_len(f_out.get_ref()), old_len);
+    let padded = read_comment_header(f_out);
+    assert_eq!(padded.get_tag_single("title").unwrap(), "Short");
+    assert!(padded.get_tag_single("padding").unwrap().len() > 900);
+}
+
+#[test]
+fn test_deterministic_output() {
+    let tags = [("title", "Noise"), ("artist", "One"), ("Artist", "Two")];
+    let mut forward = CommentHeader::new();
+    let mut backward = CommentHeader::new();
+    for (tag, value) in tags.iter() {
+        forward.add_tag_single(tag, value);
+    }
+    backward.add_tag_single("artist", "One");
+    backward.add_tag_single("artist", "Two");
+    backward.add_tag_single("title", "Noise");
+    let options = ReplaceOptions {
+        deterministic: true,
+        ..Default::default()
+    };
+    let write = |header: &CommentHeader| {
+        let f_in = File::open("tests/noise.ogg").expect("Can't open file");
+        let (f_out, _) =
+            safe_replace_comment_header_with_options(f_in, header.clone(), &options).unwrap();
+        f_out.into_inner()
+    };
+    assert_eq!(write(&forward), write(&backward));
+    let sorted = read_comment_header(Cursor::new(write(&forward)));
+    assert_eq!(sorted.get_tag_multi("artist"), ["One", "Two"]);
+    assert_eq!(sorted.get_tag_names(), ["artist", "title"]);
+}
+
+#[test]
+fn test_reserve_padding() {
+    let header = make_header();
+    let options = ReplaceOptions {
+        reserve_padding: 4096,
+        ..Default::default()
+    };
+    let f_in = File::open("tests/noise.ogg").expect("Can't open file");
+    let (mut f_out, report) =
+        safe_replace_comment_header_with_options(f_in, header.clone(), &options).unwrap();
+    assert_eq!(report.slack, 4096);
+    assert_eq!(
+        comment_packet_len(f_out.get_ref()),
+        make_comment_header(&header).len() + 4096
+    );
+
+    // The reserve leaves room for a later edit in place
+    let mut grown = header.clone();
+    grown.add_tag_single("description", &"x".repeat(3000));
+    safe_patch_comment_header_in_place(&mut f_out, &grown).unwrap();
+    f_out.set_position(0);
+    let mut patched = read_comment_header(f_out);
+    patched.clear_tag("padding");
+    assert_eq!(patched, grown);
+}
@@ -0,0 +1,29 @@
+#![cfg(feature = "mmap")]
+
+use oggvorbismeta::{
+    read_comment_header, safe_read_comment_header_mapped, safe_replace_comment_header_mapped_into,
+    CommentHeader, MappedFile, ReplaceOptions, VorbisComments,
+};
+use std::fs::{self, File};
+use std::io::Cursor;
+
+#[test]
+fn test_mapped_read_and_replace() {
+    let mapped = MappedFile::open("tests/noise.ogg").unwrap();
+    assert_eq!(&mapped[..], &fs::read("tests/noise.ogg").unwrap()[..]);
+    assert_eq!(
+        safe_read_comment_header_mapped("tests/noise.ogg").unwrap(),
+        read_comment_header(File::open("tests/noise.ogg").unwrap())
+    );
+
+    let mut header = CommentHeader::new();
+    header.add_tag_single("title", "Mapped");
+    let options = ReplaceOptions {
+        raw_copy: true,
+        ..Default::default()
+    };
+    let mut out = vec![];
+    safe_replace_comment_header_mapped_into("tests/noise.ogg", &mut out, header.clone(), &options)
+        .unwrap();
+    assert_eq!(read_comment_header(Cursor::new(&out)), header);
+}
@@ -0,0 +1,25 @@
+use oggvorbismeta::{split_value, CommentHeader, VorbisComments, VorbisMultiValue};
+
+#[test]
+fn test_split_multivalue() {
+    assert_eq!(split_value("A; B / C", &[";", "/"]), vec!["A", "B", "C"]);
+    assert_eq!(split_value("AC/DC", &[";"]), vec!["AC/DC"]);
+
+    let mut header = CommentHeader::new();
+    header.add_tag_single("artist", "A; B / C");
+    header.add_tag_single("artist", "D");
+    header.add_tag_single("title", "Noise");
+    assert_eq!(header.split_multivalue("ARTIST", &[";", " / "]), 2);
+    assert_eq!(header.get_tag_multi("artist"), vec!["A", "B", "C", "D"]);
+    assert_eq!(header.split_multivalue("artist", &[";"]), 0);
+}
+
+#[test]
+fn test_join_multivalue() {
+    let mut header = CommentHeader::new();
+    header.add_tag_multi("artist", &["A", "B"]);
+    header.join_multivalue("artist", "; ");
+    assert_eq!(header.get_tag_multi("artist"), vec!["A; B"]);
+    header.split_multivalue("artist", &[";"]);
+    assert_eq!(header.get_tag_multi("artist"), vec!["A", "B"]);
+}
@@ -0,0 +1,50 @@
+use oggvorbismeta::{CommentHeader, Mode, MusicalKey, Note, VorbisComments, VorbisMusical};
+
+#[test]
+fn test_musical_key_notations() {
+    let a_minor = MusicalKey {
+        note: Note::A,
+        mode: Mode::Minor,
+    };
+    assert_eq!(MusicalKey::parse("Am"), Some(a_minor));
+    assert_eq!(MusicalKey::parse("A minor"), Some(a_minor));
+    assert_eq!(MusicalKey::parse("8A"), Some(a_minor));
+    assert_eq!(a_minor.to_camelot(), "8A");
+    assert_eq!(MusicalKey::parse("Bbm").unwrap().to_tag_value(), "Bbm");
+    assert_eq!(MusicalKey::parse("A#m").unwrap().to_camelot(), "3A");
+    assert_eq!(MusicalKey::parse("F#").unwrap().to_camelot(), "2B");
+    assert_eq!(MusicalKey::parse("12b").unwrap().to_tag_value(), "E");
+    assert_eq!(MusicalKey::parse("C♯").unwrap().note, Note::CSharp);
+    assert!(MusicalKey::parse("H").is_none());
+    assert!(MusicalKey::parse("13A").is_none());
+
+    // Every key survives both notations
+    for number in 1..=12 {
+        for letter in ['A', 'B'] {
+            let camelot = format!("{}{}", number, letter);
+            let key = MusicalKey::from_camelot(&camelot).unwrap();
+            assert_eq!(key.to_camelot(), camelot);
+            assert_eq!(MusicalKey::parse(&key.to_tag_value()), Some(key));
+        }
+    }
+}
+
+#[test]
+fn test_bpm_and_key_tags() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("bpm", " 127.5 ");
+    header.add_tag_single("initialkey", "5A");
+    assert_eq!(header.bpm(), Some(127.5));
+    assert_eq!(header.initial_key().unwrap().to_tag_value(), "Cm");
+
+    header.set_bpm(Some(128.0));
+    assert_eq!(header.get_tag_single("bpm").unwrap(), "128");
+    header.set_initial_key(MusicalKey::parse("Ebm"));
+    assert_eq!(header.get_tag_single("initialkey").unwrap(), "Ebm");
+    header.set_bpm(None);
+    assert!(header.bpm().is_none());
+    for invalid in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, -120.0, 0.0] {
+        header.set_bpm(Some(invalid));
+        assert!(header.get_tag_single("bpm").is_none());
+    }
+}
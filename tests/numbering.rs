@@ -0,0 +1,87 @@
+use oggvorbismeta::{CommentHeader, Position, TotalConvention, VorbisComments, VorbisNumbering};
+
+#[test]
+fn test_track_slash_form() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("tracknumber", "03/12");
+    assert_eq!(
+        header.track(),
+        Some(Position {
+            number: 3,
+            total: Some(12)
+        })
+    );
+
+    let track = header.track();
+    header.set_track(track);
+    assert_eq!(header.get_tag_single("tracknumber").unwrap(), "3");
+    assert_eq!(header.get_tag_single("tracktotal").unwrap(), "12");
+}
+
+#[test]
+fn test_disc_split_total() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("discnumber", "2");
+    header.add_tag_single("disctotal", "3");
+    assert_eq!(
+        header.disc(),
+        Some(Position {
+            number: 2,
+            total: Some(3)
+        })
+    );
+    header.set_disc(None);
+    assert!(header.disc().is_none());
+    assert!(header.get_tag_names().is_empty());
+}
+
+#[test]
+fn test_invalid_number() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("tracknumber", "A1");
+    assert!(header.track().is_none());
+}
+
+#[test]
+fn test_normalize_totals() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("tracknumber", "4");
+    header.add_tag_single("totaltracks", "10");
+    header.add_tag_single("disctotal", "2");
+    header.add_tag_single("totaldiscs", "3");
+    assert_eq!(header.track().unwrap().total, Some(10));
+
+    header.normalize_totals(TotalConvention::TrackTotal);
+    assert_eq!(header.get_tag_multi("tracktotal"), vec!["10"]);
+    assert_eq!(header.get_tag_multi("disctotal"), vec!["2"]);
+    assert!(!header.contains_tag("totaltracks"));
+    assert!(!header.contains_tag("totaldiscs"));
+
+    header.normalize_totals(TotalConvention::TotalTracks);
+    assert_eq!(
+        header.get_tag_names(),
+        vec!["totaldiscs", "totaltracks", "tracknumber"]
+    );
+    assert_eq!(header.disc(), None);
+}
+
+#[test]
+fn test_set_track_clears_alternate_total() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("tracknumber", "4");
+    header.add_tag_single("totaltracks", "12");
+    header.add_tag_single("totaldiscs", "2");
+    header.set_track(Some(Position {
+        number: 5,
+        total: None,
+    }));
+    assert_eq!(
+        header.track(),
+        Some(Position {
+            number: 5,
+            total: None
+        })
+    );
+    header.set_disc(None);
+    assert!(header.get_tag_single("totaldiscs").is_none());
+}
@@ -0,0 +1,42 @@
+use oggvorbismeta::{
+    safe_replace_comment_header_with_options, CommentHeader, ReplaceOptions, ShrinkStrategy,
+    VorbisComments,
+};
+use std::fs::File;
+
+#[test]
+fn test_options_builder() {
+    let options = ReplaceOptions::builder()
+        .preserve_vendor(true)
+        .shrink(ShrinkStrategy::PaddingTag)
+        .reserve_padding(256)
+        .copy_pages(true)
+        .spill_threshold(1 << 20)
+        .build();
+    assert!(options.preserve_vendor);
+    assert_eq!(options.shrink, ShrinkStrategy::PaddingTag);
+    assert_eq!(options.reserve_padding, 256);
+    assert!(options.copy_pages);
+    assert_eq!(options.spill_threshold, Some(1 << 20));
+    assert!(!options.raw_copy);
+    assert!(options.cancel.is_none());
+
+    let mut header = CommentHeader::new();
+    header.add_tag_single("title", "Noise");
+    let mut out = vec![];
+    let report = options
+        .replace(
+            File::open("tests/noise.ogg").unwrap(),
+            &mut out,
+            header.clone(),
+        )
+        .unwrap();
+    let (expected, expected_report) = safe_replace_comment_header_with_options(
+        File::open("tests/noise.ogg").unwrap(),
+        header,
+        &options,
+    )
+    .unwrap();
+    assert_eq!(&out, expected.get_ref());
+    assert_eq!(report, expected_report);
+}
@@ -0,0 +1,115 @@
+use ogg::writing::PacketWriteEndInfo;
+use ogg::{PacketReader, PacketWriter};
+use oggvorbismeta::{
+    check_conformance, safe_replace_comment_header_with_options, CommentHeader, ConformanceIssue,
+    GranuleMode, Pagination, ReplaceOptions, VorbisComments,
+};
+use std::fs;
+use std::io::Cursor;
+
+fn packets(data: &[u8]) -> Vec<Vec<u8>> {
+    let mut reader = PacketReader::new(Cursor::new(data));
+    let mut packets = vec![];
+    while let Some(packet) = reader.read_packet().unwrap() {
+        packets.push(packet.data);
+    }
+    packets
+}
+
+/// The body size and the number of packets ending on each page.
+fn pages(data: &[u8]) -> Vec<(usize, usize)> {
+    let mut pages = vec![];
+    let mut pos = 0;
+    while pos + 27 <= data.len() {
+        let segments = data[pos + 26] as usize;
+        let lacing = &data[pos + 27..pos + 27 + segments];
+        let body = lacing.iter().map(|l| *l as usize).sum::<usize>();
+        pages.push((body, lacing.iter().filter(|l| **l < 255).count()));
+        pos += 27 + segments + body;
+    }
+    pages
+}
+
+fn repaginated(pagination: Pagination) -> Vec<u8> {
+    let options = ReplaceOptions {
+        pagination,
+        granule_mode: GranuleMode::Recompute,
+        ..Default::default()
+    };
+    let f_in = fs::File::open("tests/noise.ogg").unwrap();
+    let (f_out, _) =
+        safe_replace_comment_header_with_options(f_in, CommentHeader::new(), &options).unwrap();
+    let output = f_out.into_inner();
+    assert!(check_conformance(&output).is_empty());
+    // Only the comment header changed
+    let input = packets(&fs::read("tests/noise.ogg").unwrap());
+    let output_packets = packets(&output);
+    assert_eq!(output_packets.len(), input.len());
+    assert_eq!(output_packets[2..], input[2..]);
+    output
+}
+
+#[test]
+fn test_max_packets_per_page() {
+    let output = repaginated(Pagination {
+        max_packets_per_page: Some(2),
+        ..Default::default()
+    });
+    // The 8 audio packets of the single audio page of the input
+    let audio = &pages(&output)[2..];
+    assert_eq!(audio.len(), 4);
+    assert!(audio.iter().all(|(_, packets)| *packets == 2));
+}
+
+#[test]
+fn test_target_page_size() {
+    let output = repaginated(Pagination {
+        target_page_size: Some(256),
+        ..Default::default()
+    });
+    let audio = &pages(&output)[2..];
+    assert!(audio.len() > 1);
+    let (last, full) = audio.split_last().unwrap();
+    assert!(full.iter().all(|(body, _)| *body >= 256));
+    assert!(last.1 > 0);
+}
+
+#[test]
+fn test_headers_on_own_pages() {
+    // Every packet of noise.ogg on as few pages as possible
+    let mut reader = PacketReader::new(fs::File::open("tests/noise.ogg").unwrap());
+    let mut writer = PacketWriter::new(vec![]);
+    while let Some(packet) = reader.read_packet().unwrap() {
+        let end = if packet.last_in_stream() {
+            PacketWriteEndInfo::EndStream
+        } else {
+            PacketWriteEndInfo::NormalPacket
+        };
+        let (serial, granule) = (packet.stream_serial(), packet.absgp_page());
+        writer
+            .write_packet(packet.data, serial, end, granule)
+            .unwrap();
+    }
+    let input = writer.into_inner();
+    let issues = check_conformance(&input);
+    assert!(issues.contains(&ConformanceIssue::IdentificationNotAlone));
+    assert!(issues.contains(&ConformanceIssue::SetupNotPageAligned));
+
+    let options = ReplaceOptions {
+        pagination: Pagination {
+            headers_on_own_pages: true,
+            ..Default::default()
+        },
+        granule_mode: GranuleMode::Recompute,
+        ..Default::default()
+    };
+    let (f_out, _) = safe_replace_comment_header_with_options(
+        Cursor::new(&input),
+        CommentHeader::new(),
+        &options,
+    )
+    .unwrap();
+    let output = f_out.into_inner();
+    assert_eq!(check_conformance(&output), vec![]);
+    assert_eq!(packets(&output)[2..], packets(&input)[2..]);
+}
@@ -0,0 +1,83 @@
+use oggvorbismeta::{
+    make_comment_header, read_comment_header, safe_read_comment_header_lossy,
+    safe_read_comment_packet, CommentHeader, CommentHeaderRef, DecodeReport, LazyCommentHeader,
+    VorbisComments,
+};
+use std::fs::File;
+
+#[test]
+fn test_borrowed_header() {
+    let packet = safe_read_comment_packet(File::open("tests/noise.ogg").unwrap()).unwrap();
+    let borrowed = CommentHeaderRef::parse(&packet).unwrap();
+    let owned = read_comment_header(File::open("tests/noise.ogg").unwrap());
+    assert_eq!(borrowed.vendor(), owned.get_vendor());
+    assert_eq!(borrowed.len(), owned.len());
+    assert_eq!(borrowed.to_owned(), owned);
+
+    let mut header = CommentHeader::new();
+    header.add_tag_multi("Artist", &["One", "Two"]);
+    header.add_tag_single("title", "Noise");
+    let packet = make_comment_header(&header);
+    let borrowed = CommentHeaderRef::parse(&packet).unwrap();
+    assert_eq!(borrowed.get("TITLE"), Some("Noise"));
+    assert_eq!(
+        borrowed.get_all("artist").collect::<Vec<_>>(),
+        ["One", "Two"]
+    );
+    assert_eq!(borrowed.get("album"), None);
+    assert!(CommentHeaderRef::parse(&packet[..packet.len() - 1]).is_err());
+}
+
+#[test]
+fn test_lazy_header() {
+    let mut header = CommentHeader::new();
+    header.set_vendor("me");
+    header.add_tag_single("title", "Noise");
+    header.add_tag_single("lyrics", &"la".repeat(10_000));
+    header.add_tag_single("comment", "broken");
+    let mut packet = make_comment_header(&header);
+    // Make the last value invalid UTF-8
+    let end = packet.len() - 2;
+    packet[end] = 0xff;
+
+    let lazy = LazyCommentHeader::parse(&packet).unwrap();
+    assert_eq!(lazy.vendor(), "me");
+    assert_eq!(
+        lazy.keys().collect::<Vec<_>>(),
+        ["title", "lyrics", "comment"]
+    );
+    assert_eq!(lazy.get("Title"), Some("Noise"));
+    assert_eq!(lazy.get_all("lyrics")[0].len(), 20_000);
+    assert_eq!(lazy.get("comment"), None);
+    assert_eq!(lazy.decode(), CommentHeaderRef::parse(&packet).unwrap());
+}
+
+#[test]
+fn test_lossy_header() {
+    let mut header = CommentHeader::new();
+    header.set_vendor("Bad?");
+    header.add_tag_single("title", "caf?");
+    header.add_tag_single("artist", "ok");
+    let mut packet = make_comment_header(&header);
+    // 'Bad?' starts after the type, "vorbis" and the length, 'caf?' ends
+    // before the length of the next comment
+    packet[14] = 0xff;
+    let title_end = packet.len() - 1 - 4 - "artist=ok".len() - 1;
+    packet[title_end] = 0xe9;
+    assert!(CommentHeaderRef::parse(&packet).is_err());
+
+    let (lossy, report) = CommentHeaderRef::parse_lossy(&packet).unwrap();
+    assert_eq!(lossy.vendor(), "Bad\u{fffd}");
+    assert_eq!(lossy.get("title"), Some("caf\u{fffd}"));
+    assert_eq!(lossy.get("artist"), Some("ok"));
+    assert!(report.lossy_vendor);
+    assert_eq!(report.lossy_tags, ["title"]);
+
+    let (header, report) =
+        safe_read_comment_header_lossy(File::open("tests/noise.ogg").unwrap()).unwrap();
+    assert_eq!(
+        header,
+        read_comment_header(File::open("tests/noise.ogg").unwrap())
+    );
+    assert_eq!(report, DecodeReport::default());
+}
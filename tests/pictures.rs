@@ -0,0 +1,108 @@
+use oggvorbismeta::{
+    probe_image, read_comment_header, replace_comment_header, CommentHeader, Picture, PictureType,
+    VorbisComments, VorbisPictureError, VorbisPictures,
+};
+use std::fs::File;
+
+fn png_bytes() -> Vec<u8> {
+    let mut data = b"\x89PNG\r\n\x1a\n".to_vec();
+    data.extend(&13u32.to_be_bytes());
+    data.extend(b"IHDR");
+    data.extend(&640u32.to_be_bytes());
+    data.extend(&480u32.to_be_bytes());
+    // 8 bit RGBA
+    data.extend(&[8, 6, 0, 0, 0]);
+    data.extend(&[0, 0, 0, 0]);
+    data
+}
+
+fn jpeg_bytes() -> Vec<u8> {
+    let mut data = vec![0xff, 0xd8];
+    // APP0 segment to skip
+    data.extend(&[0xff, 0xe0, 0x00, 0x04, 0x00, 0x00]);
+    // SOF0: precision 8, 300x200, 3 components
+    data.extend(&[0xff, 0xc0, 0x00, 0x11, 0x08, 0x00, 0xc8, 0x01, 0x2c, 0x03]);
+    data
+}
+
+#[test]
+fn test_probe_png() {
+    let info = probe_image(&png_bytes()).unwrap();
+    assert_eq!(info.mime_type, "image/png");
+    assert_eq!((info.width, info.height, info.depth), (640, 480, 32));
+}
+
+#[test]
+fn test_probe_jpeg_gif_webp() {
+    let info = probe_image(&jpeg_bytes()).unwrap();
+    assert_eq!(info.mime_type, "image/jpeg");
+    assert_eq!((info.width, info.height, info.depth), (300, 200, 24));
+
+    let gif = b"GIF89a\x10\x00\x20\x00\xf7\x00\x00".to_vec();
+    let info = probe_image(&gif).unwrap();
+    assert_eq!((info.width, info.height, info.colors), (16, 32, 256));
+
+    let mut webp = b"RIFF\x00\x00\x00\x00WEBPVP8X\x0a\x00\x00\x00".to_vec();
+    webp.extend(&[0x10, 0, 0, 0, 99, 0, 0, 49, 0, 0]);
+    let info = probe_image(&webp).unwrap();
+    assert_eq!(info.mime_type, "image/webp");
+    assert_eq!((info.width, info.height, info.depth), (100, 50, 32));
+
+    assert!(probe_image(b"not an image").is_none());
+}
+
+#[test]
+fn test_picture_roundtrip_in_file() {
+    let mut header = CommentHeader::new();
+    header.set_vendor("Ogg");
+    let mut picture = Picture::from_image(PictureType::FrontCover, png_bytes()).unwrap();
+    picture.description = "Cover".to_string();
+    header.add_picture(&picture);
+
+    let f_in = File::open("tests/noise.ogg").expect("Can't open file");
+    let f_out = replace_comment_header(f_in, header);
+    let pictures = read_comment_header(f_out).get_pictures();
+    assert_eq!(pictures, vec![picture]);
+}
+
+#[test]
+fn test_front_cover() {
+    let mut header = CommentHeader::new();
+    assert!(header.get_front_cover().is_none());
+    header.add_picture(&Picture::new(
+        PictureType::BackCover,
+        "image/png",
+        png_bytes(),
+    ));
+    header.set_front_cover(jpeg_bytes(), "image/jpeg");
+    header.set_front_cover(png_bytes(), "image/png");
+
+    let cover = header.get_front_cover().unwrap();
+    assert_eq!(cover.mime_type, "image/png");
+    assert_eq!(cover.width, 640);
+    assert_eq!(header.get_pictures().len(), 2);
+}
+
+#[test]
+fn test_unknown_picture_type_roundtrip() {
+    let mut block = Picture::new(PictureType::Other, "image/png", vec![1, 2, 3]).to_block();
+    block[..4].copy_from_slice(&42u32.to_be_bytes());
+    let picture = Picture::from_block(&block).unwrap();
+    assert_eq!(picture.picture_type, PictureType::Unknown(42));
+    assert_eq!(picture.to_block(), block);
+    assert_eq!(PictureType::from_u32(0), PictureType::Other);
+    assert_eq!(PictureType::Other.to_u32(), 0);
+}
+
+#[test]
+fn test_oversized_block_lengths() {
+    let block = Picture::new(PictureType::Other, "image/png", vec![1, 2, 3]).to_block();
+    for offset in [4, block.len() - 7] {
+        let mut corrupt = block.clone();
+        corrupt[offset..offset + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(matches!(
+            Picture::from_block(&corrupt),
+            Err(VorbisPictureError::TruncatedBlock)
+        ));
+    }
+}
@@ -0,0 +1,47 @@
+use oggvorbismeta::{
+    m3u8_entry, read_duration, xspf_playlist, xspf_track, CommentHeader, ScanEntry, VorbisComments,
+};
+use std::fs::File;
+use std::path::PathBuf;
+use std::time::Duration;
+
+fn entry() -> ScanEntry {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("title", "Noise & Static");
+    header.add_tag_single("artist", "Band");
+    header.add_tag_single("tracknumber", "3/10");
+    ScanEntry {
+        path: PathBuf::from("/music/my song.ogg"),
+        header,
+        from_cache: false,
+    }
+}
+
+#[test]
+fn test_read_duration() {
+    let duration = read_duration(File::open("tests/noise.ogg").unwrap());
+    assert_eq!(duration, Duration::from_millis(100));
+}
+
+#[test]
+fn test_playlist_entries() {
+    let track = xspf_track(&entry(), Some(Duration::from_millis(1500)));
+    assert_eq!(
+        track,
+        "<track><location>file:///music/my%20song.ogg</location>\
+         <title>Noise &amp; Static</title><creator>Band</creator>\
+         <trackNum>3</trackNum><duration>1500</duration></track>"
+    );
+    assert!(xspf_playlist(&[track]).contains("<trackList>\n<track>"));
+
+    assert_eq!(
+        m3u8_entry(&entry(), Some(Duration::from_millis(1500))),
+        "#EXTINF:2,Band - Noise & Static\n/music/my song.ogg\n"
+    );
+    let mut untagged = entry();
+    untagged.header = CommentHeader::new();
+    assert_eq!(
+        m3u8_entry(&untagged, None),
+        "#EXTINF:-1,my song\n/music/my song.ogg\n"
+    );
+}
@@ -0,0 +1,61 @@
+use oggvorbismeta::{
+    read_comment_header, replace_comment_header, safe_replace_comment_header_with_options,
+    safe_strip_all_tags, CommentHeader, Picture, PictureType, ReplaceOptions, StripOptions,
+    VorbisComments, VorbisPictures, GENERIC_VENDOR,
+};
+use std::fs::File;
+use std::io::Cursor;
+
+fn tagged_file() -> Cursor<Vec<u8>> {
+    let mut header = CommentHeader::new();
+    header.set_vendor("Encoder");
+    header.add_tag_single("title", "Noise");
+    header.add_tag_single("location", "Home");
+    header.add_tag_single("encoder", "ffmpeg");
+    header.add_tag_single("encodersettings", "-q 6");
+    header.add_picture(&Picture::new(
+        PictureType::FrontCover,
+        "image/png",
+        vec![1, 2, 3],
+    ));
+    let f_in = File::open("tests/noise.ogg").expect("Can't open file");
+    replace_comment_header(f_in, header)
+}
+
+#[test]
+fn test_strip_all_tags() {
+    let f_out = safe_strip_all_tags(tagged_file(), &StripOptions::default()).unwrap();
+    let header = read_comment_header(f_out);
+    assert!(header.is_empty());
+    assert_eq!(header.get_vendor(), "Encoder");
+}
+
+#[test]
+fn test_strip_keeping_pictures() {
+    let options = StripOptions {
+        keep_pictures: true,
+        ..Default::default()
+    };
+    let f_out = safe_strip_all_tags(tagged_file(), &options).unwrap();
+    let header = read_comment_header(f_out);
+    assert_eq!(header.len(), 1);
+    assert_eq!(header.get_vendor(), "Encoder");
+    assert_eq!(header.get_pictures()[0].data, vec![1, 2, 3]);
+}
+
+#[test]
+fn test_anonymize_encoder() {
+    let options = ReplaceOptions {
+        anonymize_encoder: true,
+        ..Default::default()
+    };
+    let f_in = tagged_file();
+    let new_header = read_comment_header(f_in.clone());
+    let (f_out, _) = safe_replace_comment_header_with_options(f_in, new_header, &options).unwrap();
+    let header = read_comment_header(f_out);
+    assert_eq!(header.get_vendor(), GENERIC_VENDOR);
+    assert_eq!(
+        header.get_tag_names(),
+        vec!["location", "metadata_block_picture", "title"]
+    );
+}
@@ -0,0 +1,40 @@
+use oggvorbismeta::{
+    read_comment_header, safe_replace_comment_header_with_progress, CommentHeader, ReplaceOptions,
+    VorbisComments,
+};
+use std::io::Cursor;
+
+#[test]
+fn test_progress_callback() {
+    // Large enough for several reports
+    let mut header = CommentHeader::new();
+    header.add_tag_single("description", &"x".repeat(300_000));
+    let mut data = vec![];
+    safe_replace_comment_header_with_progress(
+        Cursor::new(std::fs::read("tests/noise.ogg").unwrap()),
+        &mut data,
+        header,
+        &ReplaceOptions::default(),
+        |_, _| {},
+    )
+    .unwrap();
+
+    let mut calls = vec![];
+    let mut out = vec![];
+    let mut new_header = CommentHeader::new();
+    new_header.add_tag_single("title", "Progress");
+    safe_replace_comment_header_with_progress(
+        Cursor::new(&data),
+        &mut out,
+        new_header.clone(),
+        &ReplaceOptions::default(),
+        |done, total| calls.push((done, total)),
+    )
+    .unwrap();
+    let total = data.len() as u64;
+    assert!(calls.len() > 2);
+    assert!(calls.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+    assert!(calls.iter().all(|call| call.1 == total));
+    assert_eq!(calls.last(), Some(&(total, total)));
+    assert_eq!(read_comment_header(Cursor::new(&out)), new_header);
+}
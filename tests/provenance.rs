@@ -0,0 +1,45 @@
+use oggvorbismeta::{CommentHeader, Provenance, Source, VorbisComments, PROVENANCE_TAG};
+use std::path::PathBuf;
+
+#[test]
+fn test_merge_records_sources() {
+    let mut target = CommentHeader::new();
+    target.add_tag_single("title", "Noise");
+    let mut from_db = CommentHeader::new();
+    from_db.add_tag_single("TITLE", "Static");
+    from_db.add_tag_multi("artist", &["A", "B"]);
+
+    let mut provenance = Provenance::new();
+    provenance.record("title", Source::File(PathBuf::from("/music/noise.ogg")));
+    provenance.merge(
+        &mut target,
+        &from_db,
+        Source::Database("musicbrainz".to_string()),
+        true,
+    );
+    assert_eq!(target.get_tag_single("title").unwrap(), "Noise");
+    assert_eq!(target.get_tag_multi("artist"), vec!["A", "B"]);
+    assert_eq!(
+        provenance.source("ARTIST"),
+        Some(&Source::Database("musicbrainz".to_string()))
+    );
+
+    provenance.merge(&mut target, &from_db, Source::UserEdit, false);
+    assert_eq!(target.get_tag_single("title").unwrap(), "Static");
+    assert_eq!(provenance.source("title"), Some(&Source::UserEdit));
+    assert_eq!(provenance.report(), "title: user\nartist: user\n");
+}
+
+#[test]
+fn test_provenance_tag_roundtrip() {
+    let mut provenance = Provenance::new();
+    provenance.record("title", Source::File(PathBuf::from("/a=b.ogg")));
+    provenance.record("album", Source::Other("tagger".to_string()));
+    let mut header = CommentHeader::new();
+    provenance.write_to(&mut header);
+    assert_eq!(
+        header.get_tag_multi(PROVENANCE_TAG),
+        vec!["title=file:/a=b.ogg", "album=other:tagger"]
+    );
+    assert_eq!(Provenance::from_header(&header), provenance);
+}
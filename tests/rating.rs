@@ -0,0 +1,44 @@
+use oggvorbismeta::{normalize_rating, CommentHeader, RatingFormat, VorbisComments, VorbisRating};
+
+#[test]
+fn test_normalize_scales() {
+    assert_eq!(normalize_rating("4"), Some(0.8));
+    assert_eq!(normalize_rating("80"), Some(0.8));
+    assert_eq!(normalize_rating("-1"), None);
+
+    let mut header = CommentHeader::new();
+    header.add_tag_single("rating", "60");
+    assert_eq!(header.rating(), Some(0.6));
+    header.add_tag_single("fmps_rating", "0.9");
+    assert_eq!(header.rating(), Some(0.9));
+}
+
+#[test]
+fn test_write_formats() {
+    let mut header = CommentHeader::new();
+    header.set_rating(
+        0.8,
+        &[
+            RatingFormat::Percent,
+            RatingFormat::Stars,
+            RatingFormat::Fmps,
+        ],
+    );
+    assert_eq!(header.get_tag_multi("rating"), vec!["80".to_string()]);
+    assert_eq!(header.get_tag_single("fmps_rating").unwrap(), "0.800000");
+
+    header.set_rating(1.5, &[RatingFormat::Stars]);
+    assert_eq!(header.get_tag_single("rating").unwrap(), "5");
+    assert!(header.get_tag_single("fmps_rating").is_none());
+}
+
+#[test]
+fn test_small_percent_roundtrip() {
+    let mut header = CommentHeader::new();
+    header.set_rating(0.03, &[RatingFormat::Percent]);
+    assert_eq!(header.get_tag_single("rating").unwrap(), "3%");
+    assert_eq!(header.rating(), Some(0.03));
+    header.set_rating(0.0, &[RatingFormat::Percent]);
+    assert_eq!(header.rating(), Some(0.0));
+    assert_eq!(normalize_rating("3"), Some(0.6));
+}
@@ -0,0 +1,52 @@
+use oggvorbismeta::{
+    read_comment_header, safe_read_comment_header, safe_read_comment_header_recovering,
+    RecoveryReport, VorbisReadCommentError,
+};
+use std::fs::File;
+
+#[test]
+fn test_recover_from_corrupt_pages() {
+    let original = read_comment_header(File::open("tests/noise.ogg").unwrap());
+    let data = std::fs::read("tests/noise.ogg").unwrap();
+
+    // A bad checksum on the identification page
+    let mut bad_checksum = data.clone();
+    bad_checksum[40] ^= 0xff;
+    assert!(safe_read_comment_header(&bad_checksum[..]).is_err());
+    let (header, report) = safe_read_comment_header_recovering(&bad_checksum[..]).unwrap();
+    assert_eq!(header, original);
+    assert_eq!(
+        report,
+        RecoveryReport {
+            dropped_pages: 1,
+            skipped_bytes: 58,
+            lost_packets: 0,
+        }
+    );
+
+    // A bad lacing value running the first page over the second, then
+    // garbage before it
+    let mut lost_sync = data[..58].to_vec();
+    lost_sync[27] = 0xff;
+    lost_sync.extend(b"garbage");
+    lost_sync.extend(&data[58..]);
+    let (header, report) = safe_read_comment_header_recovering(&lost_sync[..]).unwrap();
+    assert_eq!(header, original);
+    assert_eq!(report.dropped_pages, 1);
+    assert_eq!(report.skipped_bytes, 65);
+
+    let (header, report) = safe_read_comment_header_recovering(&data[..]).unwrap();
+    assert_eq!(header, original);
+    assert_eq!(report, RecoveryReport::default());
+}
+
+#[test]
+fn test_lost_comment_page() {
+    // The second of the pages of the comment header
+    let mut data = std::fs::read("tests/tiny_pages.ogg").unwrap();
+    data[400] ^= 0xff;
+    assert!(matches!(
+        safe_read_comment_header_recovering(&data[..]),
+        Err(VorbisReadCommentError::Truncated)
+    ));
+}
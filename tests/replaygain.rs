@@ -0,0 +1,42 @@
+use oggvorbismeta::{
+    format_gain, parse_gain, CommentHeader, ReplayGain, VorbisComments, VorbisReplayGain,
+};
+
+#[test]
+fn test_gain_format() {
+    assert_eq!(format_gain(1.234), "+1.23 dB");
+    assert_eq!(format_gain(-6.5), "-6.50 dB");
+    assert_eq!(parse_gain(" -6.50 dB"), Some(-6.5));
+    assert_eq!(parse_gain("+1.2dB"), Some(1.2));
+    assert_eq!(parse_gain("loud"), None);
+}
+
+#[test]
+fn test_gain_multibyte_suffix() {
+    assert_eq!(parse_gain("1€"), None);
+    assert_eq!(parse_gain("é"), None);
+    assert_eq!(parse_gain("2 dB€"), None);
+}
+
+#[test]
+fn test_replay_gain_roundtrip() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("replaygain_album_gain", "garbage");
+    let gain = ReplayGain {
+        track_gain: Some(-3.21),
+        track_peak: Some(0.988831),
+        album_gain: None,
+        album_peak: Some(1.0),
+    };
+    header.set_replay_gain(&gain);
+    assert_eq!(
+        header.get_tag_single("replaygain_track_gain").unwrap(),
+        "-3.21 dB"
+    );
+    assert_eq!(
+        header.get_tag_single("replaygain_track_peak").unwrap(),
+        "0.988831"
+    );
+    assert!(header.get_tag_single("replaygain_album_gain").is_none());
+    assert_eq!(header.replay_gain(), gain);
+}
@@ -0,0 +1,32 @@
+use oggvorbismeta::{
+    make_comment_header, roundtrip_check, CommentHeader, RoundTripOutcome, VorbisComments,
+};
+
+#[test]
+fn test_roundtrip_outcomes() {
+    let mut header = CommentHeader::new();
+    header.set_vendor("Ogg");
+    header.add_tag_single("title", "Noise");
+    let mut packet = make_comment_header(&header);
+    assert_eq!(roundtrip_check(&packet), RoundTripOutcome::Identical);
+
+    packet.extend(b"trailing");
+    assert_eq!(roundtrip_check(&packet), RoundTripOutcome::Equivalent);
+    assert!(roundtrip_check(&packet).is_consistent());
+
+    let outcome = roundtrip_check(b"\x01vorbis");
+    assert!(matches!(outcome, RoundTripOutcome::Unparseable(_)));
+}
+
+#[test]
+fn test_roundtrip_never_panics() {
+    let mut header = CommentHeader::new();
+    header.add_tag_multi("artist", &["A", "B"]);
+    let packet = make_comment_header(&header);
+    for len in 0..packet.len() {
+        let mut bytes = packet[..len].to_vec();
+        roundtrip_check(&bytes);
+        bytes.iter_mut().for_each(|byte| *byte ^= 0x5a);
+        roundtrip_check(&bytes);
+    }
+}
@@ -0,0 +1,45 @@
+use oggvorbismeta::{
+    CommentHeader, SanitizeOptions, SanitizeReport, VorbisComments, VorbisSanitize,
+};
+
+fn messy_header() -> CommentHeader {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("title", " Noise\0 ");
+    header.add_tag_single("lyrics", "line one\r\nline two");
+    header.add_tag_single("comment", "  ");
+    header.add_tag_single("artist", "A");
+    header.add_tag_verbatim("ARTIST", "A");
+    header
+}
+
+#[test]
+fn test_sanitize_defaults() {
+    let mut header = messy_header();
+    let report = header.sanitize(&SanitizeOptions::default());
+    assert_eq!(
+        report,
+        SanitizeReport {
+            cleaned: 2,
+            dropped_empty: 1,
+            dropped_duplicates: 0,
+        }
+    );
+    assert_eq!(header.get_tag_single("title").unwrap(), "Noise");
+    assert_eq!(
+        header.get_tag_single("lyrics").unwrap(),
+        "line one\r\nline two"
+    );
+    assert_eq!(header.get_tag_multi("artist"), vec!["A", "A"]);
+    assert!(header.sanitize(&SanitizeOptions::default()).is_empty());
+}
+
+#[test]
+fn test_sanitize_duplicates() {
+    let mut header = messy_header();
+    let options = SanitizeOptions {
+        drop_duplicates: true,
+        ..Default::default()
+    };
+    assert_eq!(header.sanitize(&options).dropped_duplicates, 1);
+    assert_eq!(header.get_tag_multi("artist"), vec!["A"]);
+}
@@ -0,0 +1,106 @@
+use oggvorbismeta::{
+    replace_comment_header, FileCache, MemoryCache, ScanCache, Scanner, Throttle, VorbisComments,
+};
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+fn scan_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("noise.ogg");
+    fs::copy("tests/noise.ogg", &path).unwrap();
+    dir
+}
+
+#[test]
+fn test_scan_uses_cache() {
+    let dir = scan_dir("scan_memory");
+    let path = dir.join("noise.ogg");
+    let mut scanner = Scanner::with_cache(Box::new(MemoryCache::new()));
+    let first = scanner.scan(&[&path]).remove(0).unwrap();
+    assert!(!first.from_cache);
+    assert_eq!(first.header.get_tag_single("title").unwrap(), "Noise");
+    let second = scanner.scan_file(&path).unwrap();
+    assert!(second.from_cache);
+    assert_eq!(second.header, first.header);
+
+    // A rewrite with a different header invalidates the entry
+    let mut header = first.header.clone();
+    header.clear_tag("title");
+    header.add_tag_single("title", "Other");
+    let mut f_out = replace_comment_header(File::open(&path).unwrap(), header);
+    std::io::copy(&mut f_out, &mut File::create(&path).unwrap()).unwrap();
+    let third = scanner.scan_file(&path).unwrap();
+    assert!(!third.from_cache);
+    assert_eq!(third.header.get_tag_single("title").unwrap(), "Other");
+}
+
+#[test]
+fn test_file_cache_persists() {
+    let dir = scan_dir("scan_file");
+    let path = dir.join("noise.ogg");
+    let cache_path = dir.join("cache.txt");
+    let mut scanner = Scanner::with_cache(Box::new(FileCache::open(&cache_path).unwrap()));
+    scanner.scan_file(&path).unwrap();
+    scanner.flush().unwrap();
+
+    let cache = FileCache::open(&cache_path).unwrap();
+    let cached = cache.get(&path).unwrap();
+    assert_eq!(cached.header.get_tag_single("title").unwrap(), "Noise");
+    let mut scanner = Scanner::with_cache(Box::new(cache));
+    assert!(scanner.scan_file(&path).unwrap().from_cache);
+}
+
+#[test]
+fn test_corrupt_file_cache_entry() {
+    let dir = scan_dir("scan_corrupt");
+    let path = dir.join("noise.ogg");
+    let cache_path = dir.join("cache.txt");
+    let mut scanner = Scanner::with_cache(Box::new(FileCache::open(&cache_path).unwrap()));
+    scanner.scan_file(&path).unwrap();
+    scanner.flush().unwrap();
+
+    // Nanoseconds carrying over the largest number of seconds
+    let text = fs::read_to_string(&cache_path).unwrap();
+    let mut fields: Vec<String> = text.trim_end().split('\t').map(String::from).collect();
+    fields[2] = u64::MAX.to_string();
+    fields[3] = "1500000000".to_string();
+    fs::write(&cache_path, fields.join("\t") + "\n").unwrap();
+    let cache = FileCache::open(&cache_path).unwrap();
+    assert!(cache.get(&path).is_none());
+    let mut scanner = Scanner::with_cache(Box::new(cache));
+    assert!(!scanner.scan_file(&path).unwrap().from_cache);
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_sqlite_cache() {
+    use oggvorbismeta::SqliteCache;
+
+    let dir = scan_dir("scan_sqlite");
+    let path = dir.join("noise.ogg");
+    let db = dir.join("cache.db");
+    let mut scanner = Scanner::with_cache(Box::new(SqliteCache::open(&db).unwrap()));
+    assert!(!scanner.scan_file(&path).unwrap().from_cache);
+    let mut scanner = Scanner::with_cache(Box::new(SqliteCache::open(&db).unwrap()));
+    assert!(scanner.scan_file(&path).unwrap().from_cache);
+}
+
+#[test]
+fn test_scan_throttled() {
+    let dir = scan_dir("scan_throttle");
+    let path = dir.join("noise.ogg");
+    let mut scanner = Scanner::new();
+    scanner.throttle(Throttle {
+        files_per_second: Some(20.0),
+        yield_between_files: true,
+        ..Default::default()
+    });
+    let start = Instant::now();
+    let entries = scanner.scan(&[&path, &path, &path]);
+    assert!(entries.iter().all(|entry| entry.is_ok()));
+    // The second and third file wait 50ms each
+    assert!(start.elapsed() >= Duration::from_millis(100));
+}
@@ -0,0 +1,45 @@
+use oggvorbismeta::{CommentHeader, SchemaViolation, TagSchema, VorbisComments};
+
+fn schema() -> TagSchema {
+    let mut schema = TagSchema::new();
+    schema
+        .require("ARTIST")
+        .require("TITLE")
+        .allow("DATE")
+        .allow("MUSICBRAINZ_*")
+        .validator("date", |value| {
+            value.len() >= 4 && value[..4].chars().all(|c| c.is_ascii_digit())
+        });
+    schema
+}
+
+#[test]
+fn test_schema_accepts_conforming_header() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("artist", "Nobody");
+    header.add_tag_verbatim("TITLE", "Noise");
+    header.add_tag_single("date", "2001-02-03");
+    header.add_tag_single("musicbrainz_trackid", "1234");
+    assert!(schema().validate(&header).is_empty());
+}
+
+#[test]
+fn test_schema_violations() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("title", "Noise");
+    header.add_tag_single("date", "soon");
+    header.add_tag_single("comment", "a");
+    header.add_tag_single("comment", "b");
+    assert_eq!(
+        schema().validate(&header),
+        vec![
+            SchemaViolation::Missing("ARTIST".to_string()),
+            SchemaViolation::InvalidValue {
+                tag: "date".to_string(),
+                value: "soon".to_string()
+            },
+            SchemaViolation::NotAllowed("comment".to_string()),
+        ]
+    );
+    assert!(TagSchema::new().validate(&header).is_empty());
+}
@@ -0,0 +1,24 @@
+use oggvorbismeta::{CommentHeader, SortField, VorbisComments, VorbisSortTags};
+
+#[test]
+fn test_sorted_fields() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("ARTIST", "The Band");
+    header.add_tag_single("ArtistSort", "Band, The");
+    header.add_tag_single("album", "Noise");
+
+    let artist = header.sorted(SortField::Artist);
+    assert_eq!(artist.display.as_deref(), Some("The Band"));
+    assert_eq!(artist.sort_key(), Some("Band, The"));
+    assert_eq!(header.sorted(SortField::Album).sort_key(), Some("Noise"));
+    assert_eq!(header.sorted(SortField::Title).sort_key(), None);
+
+    header.set_sorted(SortField::Artist, "Other", None);
+    assert_eq!(header.sorted(SortField::Artist).sort_key(), Some("Other"));
+    assert!(header.get_tag_single("artistsort").is_none());
+    header.set_sorted(SortField::AlbumArtist, "The Band", Some("Band, The"));
+    assert_eq!(
+        header.get_tag_single("albumartistsort").unwrap(),
+        "Band, The"
+    );
+}
@@ -0,0 +1,48 @@
+use oggvorbismeta::{
+    replace_comment_header, safe_replace_comment_header_spilled, CommentHeader, ReplaceOptions,
+    ReplaceOutput, VorbisComments,
+};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::PathBuf;
+
+fn new_header() -> CommentHeader {
+    let mut header = CommentHeader::new();
+    header.set_vendor("Ogg");
+    header.add_tag_single("title", "Noise");
+    header
+}
+
+fn spilled(threshold: Option<usize>, dir: PathBuf) -> ReplaceOutput {
+    let options = ReplaceOptions {
+        spill_threshold: threshold,
+        spill_dir: Some(dir),
+        ..Default::default()
+    };
+    let f_in = File::open("tests/noise.ogg").expect("Can't open file");
+    safe_replace_comment_header_spilled(f_in, new_header(), &options)
+        .unwrap()
+        .0
+}
+
+#[test]
+fn test_spill_to_file() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("spill");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let expected = replace_comment_header(File::open("tests/noise.ogg").unwrap(), new_header());
+
+    let mut output = spilled(Some(1000), dir.clone());
+    let spill_path = match &output {
+        ReplaceOutput::File(spill) => spill.path().to_path_buf(),
+        ReplaceOutput::Memory(_) => panic!("output was not spilled"),
+    };
+    let mut data = vec![];
+    output.read_to_end(&mut data).unwrap();
+    assert_eq!(&data, expected.get_ref());
+    drop(output);
+    assert!(!spill_path.exists());
+
+    let output = spilled(None, dir);
+    assert!(matches!(output, ReplaceOutput::Memory(_)));
+}
@@ -0,0 +1,69 @@
+use oggvorbismeta::{
+    is_valid_language_tag, CommentHeader, Segment, Transcript, VorbisComments, VorbisLanguageError,
+    VorbisSpokenWord,
+};
+use std::time::Duration;
+
+#[test]
+fn test_language_tags() {
+    for tag in [
+        "en",
+        "pt-BR",
+        "zh-Hant-TW",
+        "sl-rozaj-biske",
+        "es-419",
+        "de-CH-1901",
+        "x-klingon",
+        "en-a-bbb-x-private",
+    ] {
+        assert!(is_valid_language_tag(tag), "{}", tag);
+    }
+    for tag in ["", "e", "english-", "en--US", "en-US-a", "123", "en_US"] {
+        assert!(!is_valid_language_tag(tag), "{}", tag);
+    }
+
+    let mut header = CommentHeader::new();
+    header.set_languages(&["en", "fr-CA"]).unwrap();
+    assert_eq!(header.languages(), vec!["en", "fr-CA"]);
+    assert_eq!(
+        header.set_languages(&["en", "fr_CA"]),
+        Err(VorbisLanguageError::InvalidLanguageTag("fr_CA".to_string()))
+    );
+    assert_eq!(header.languages(), vec!["en", "fr-CA"]);
+}
+
+#[test]
+fn test_speakers_transcripts_segments() {
+    let mut header = CommentHeader::new();
+    header.set_speakers(&["Jane Doe", "Interviewer"]);
+    assert_eq!(header.speakers().len(), 2);
+
+    header.add_transcript(&Transcript {
+        language: None,
+        reference: "https://archive.example/t/1".to_string(),
+    });
+    header.add_tag_single("TRANSCRIPT-FR", "https://archive.example/t/1/fr");
+    let transcripts = header.transcripts();
+    assert_eq!(transcripts[1].language.as_deref(), Some("fr"));
+    header.clear_transcripts();
+    assert!(header.transcripts().is_empty());
+
+    let later = Segment {
+        start: Duration::from_secs(300),
+        end: None,
+        speaker: None,
+        description: "Questions | answers".to_string(),
+    };
+    let first = Segment {
+        start: Duration::ZERO,
+        end: Some(Duration::from_millis(299_500)),
+        speaker: Some("Jane Doe".to_string()),
+        description: "Childhood".to_string(),
+    };
+    header.set_segments(&[later.clone(), first.clone()]);
+    assert_eq!(
+        header.get_tag_single("segment").unwrap(),
+        "00:00:00.000 | 00:04:59.500 | Jane Doe | Childhood"
+    );
+    assert_eq!(header.segments(), vec![first, later]);
+}
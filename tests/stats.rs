@@ -0,0 +1,97 @@
+use oggvorbismeta::{
+    safe_patch_comment_header_in_place, safe_replace_comment_header_with_options, CommentHeader,
+    Pagination, ReplaceOptions, RewriteWarning, VorbisComments,
+};
+use std::fs;
+use std::io::Cursor;
+
+#[test]
+fn test_rewrite_stats() {
+    let data = fs::read("tests/noise.ogg").unwrap();
+    for (copy_pages, raw_copy) in [(false, false), (true, false), (false, true)] {
+        let options = ReplaceOptions {
+            copy_pages,
+            raw_copy,
+            ..Default::default()
+        };
+        let (f_out, report) = safe_replace_comment_header_with_options(
+            Cursor::new(&data),
+            CommentHeader::new(),
+            &options,
+        )
+        .unwrap();
+        let stats = report.stats;
+        assert_eq!(stats.pages_read, 3);
+        assert_eq!(stats.bytes_read, data.len() as u64);
+        assert_eq!(stats.pages_written, 3);
+        assert_eq!(stats.bytes_written, f_out.get_ref().len() as u64);
+        // The identification and setup headers and 8 audio packets
+        assert_eq!(stats.packets_copied, 10);
+        assert_eq!(stats.raw_copy, raw_copy);
+        assert!(!stats.in_place);
+        assert!(stats.warnings.is_empty());
+    }
+
+    let mut file = Cursor::new(data.clone());
+    let mut header = CommentHeader::new();
+    header.add_tag_single("title", "Noise");
+    let stats = safe_patch_comment_header_in_place(&mut file, &header)
+        .unwrap()
+        .stats;
+    assert!(stats.in_place);
+    assert_eq!((stats.pages_read, stats.pages_written), (2, 1));
+    assert_eq!(stats.bytes_written, 4429 - 58);
+}
+
+#[test]
+fn test_rewrite_warnings() {
+    // Four audio pages, cut in the middle of the last one
+    let options = ReplaceOptions {
+        pagination: Pagination {
+            max_packets_per_page: Some(2),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let f_in = fs::File::open("tests/noise.ogg").unwrap();
+    let (f_out, _) =
+        safe_replace_comment_header_with_options(f_in, CommentHeader::new(), &options).unwrap();
+    let data = f_out.into_inner();
+    let data = &data[..data.len() - 100];
+    for copy_pages in [false, true] {
+        let options = ReplaceOptions {
+            copy_pages,
+            mark_end_of_stream: true,
+            ..Default::default()
+        };
+        let (_, report) = safe_replace_comment_header_with_options(
+            Cursor::new(data),
+            CommentHeader::new(),
+            &options,
+        )
+        .unwrap();
+        assert!(report.truncated);
+        assert_eq!(
+            report.stats.warnings,
+            [
+                RewriteWarning::PartialPageDropped,
+                RewriteWarning::EndOfStreamMarked
+            ]
+        );
+    }
+}
+
+#[test]
+fn test_stats_with_leading_junk() {
+    // The reader seeks back while looking for the first page
+    let mut data = b"junk before the first page".repeat(10);
+    data.extend(fs::read("tests/noise.ogg").unwrap());
+    let (_, report) = safe_replace_comment_header_with_options(
+        Cursor::new(&data),
+        CommentHeader::new(),
+        &ReplaceOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(report.stats.pages_read, 3);
+    assert_eq!(report.stats.bytes_read, data.len() as u64);
+}
@@ -0,0 +1,192 @@
+use ogg::PacketReader;
+use oggvorbismeta::keys::PADDING;
+use oggvorbismeta::{
+    check_conformance, plan_replace, read_comment_header, safe_patch_comment_header_file,
+    safe_replace_comment_header_with_options, shift_page_sequences,
+    surgical_replace_comment_header, CommentHeader, ConformanceIssue, ReplaceOptions,
+    VorbisComments, VorbisSurgicalError,
+};
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+
+fn packets(data: &[u8]) -> Vec<Vec<u8>> {
+    let mut reader = PacketReader::new(Cursor::new(data));
+    let mut packets = vec![];
+    while let Some(packet) = reader.read_packet().unwrap() {
+        packets.push(packet.data);
+    }
+    packets
+}
+
+fn surgical_check(fixture: &str, new_header: CommentHeader) {
+    let data = fs::read(fixture).unwrap();
+    let out = surgical_replace_comment_header(&data, &new_header);
+    assert_eq!(check_conformance(&out), vec![]);
+    assert_eq!(read_comment_header(Cursor::new(&out)), new_header);
+    let (before, after) = (packets(&data), packets(&out));
+    assert_eq!(before.len(), after.len());
+    assert_eq!(before[0], after[0]);
+    assert_eq!(before[2..], after[2..]);
+}
+
+#[test]
+fn test_surgical_grow_and_shrink() {
+    // Grows from one header page to several
+    let mut header = CommentHeader::new();
+    header.set_vendor("Ogg");
+    header.add_tag_single("description", &"x".repeat(200_000));
+    surgical_check("tests/noise.ogg", header);
+
+    // Shrinks from many tiny pages to one
+    let mut header = CommentHeader::new();
+    header.add_tag_single("title", "Noise");
+    surgical_check("tests/tiny_pages.ogg", header);
+}
+
+#[test]
+fn test_replace_copying_pages() {
+    let options = ReplaceOptions {
+        copy_pages: true,
+        check_conformance: true,
+        ..Default::default()
+    };
+    let mut header = CommentHeader::new();
+    header.set_vendor("Ogg");
+    header.add_tag_single("title", "Copied");
+
+    // The audio page of noise.ogg starts at 4429 and is copied byte for byte
+    let data = fs::read("tests/noise.ogg").unwrap();
+    let (out, report) =
+        safe_replace_comment_header_with_options(Cursor::new(&data), header.clone(), &options)
+            .unwrap();
+    assert!(!report.truncated);
+    let out = out.into_inner();
+    assert!(out.ends_with(&data[4429..]));
+    assert_eq!(read_comment_header(Cursor::new(&out)), header);
+
+    // Fewer header pages, the audio pages are renumbered
+    let data = fs::read("tests/tiny_pages.ogg").unwrap();
+    let (out, _) =
+        safe_replace_comment_header_with_options(Cursor::new(&data), header.clone(), &options)
+            .unwrap();
+    let out = out.into_inner();
+    assert_eq!(read_comment_header(Cursor::new(&out)), header);
+    assert_eq!(packets(&data)[2..], packets(&out)[2..]);
+}
+
+#[test]
+fn test_replace_raw_copy() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("title", "Raw");
+    for fixture in ["tests/noise.ogg", "tests/tiny_pages.ogg"] {
+        let data = fs::read(fixture).unwrap();
+        let replace = |options: ReplaceOptions| {
+            let input = Cursor::new(&data);
+            safe_replace_comment_header_with_options(input, header.clone(), &options)
+                .unwrap()
+                .0
+                .into_inner()
+        };
+        let raw = replace(ReplaceOptions {
+            raw_copy: true,
+            ..Default::default()
+        });
+        let copied = replace(ReplaceOptions {
+            copy_pages: true,
+            ..Default::default()
+        });
+        assert_eq!(raw, copied);
+        assert_eq!(check_conformance(&raw), vec![]);
+    }
+}
+
+#[test]
+fn test_shift_page_sequences() {
+    let data = fs::read("tests/noise.ogg").unwrap();
+    let serial = u32::from_le_bytes([data[14], data[15], data[16], data[17]]);
+    let shifted = shift_page_sequences(&data, serial, 2, 1).unwrap();
+    assert!(
+        check_conformance(&shifted).contains(&ConformanceIssue::SequenceGap {
+            expected: 2,
+            found: 3
+        })
+    );
+    assert_eq!(shift_page_sequences(&shifted, serial, 3, -1).unwrap(), data);
+}
+
+#[test]
+fn test_patch_in_place() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("patch_in_place");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("noise.ogg");
+    fs::copy("tests/noise.ogg", &path).unwrap();
+    let data = fs::read(&path).unwrap();
+
+    let mut header = CommentHeader::new();
+    header.set_vendor("Ogg");
+    header.add_tag_single("title", "Patched");
+    let report = safe_patch_comment_header_file(&path, &header).unwrap();
+    assert!(report.shrink.is_some());
+
+    let out = fs::read(&path).unwrap();
+    assert_eq!(out.len(), data.len());
+    assert_eq!(check_conformance(&out), vec![]);
+    let mut patched = read_comment_header(Cursor::new(&out));
+    patched.clear_tag(PADDING);
+    assert_eq!(patched, header);
+    assert_eq!(packets(&data)[2..], packets(&out)[2..]);
+
+    // Too large, the file is left alone
+    header.add_tag_single("description", &"x".repeat(100_000));
+    assert!(matches!(
+        safe_patch_comment_header_file(&path, &header),
+        Err(VorbisSurgicalError::DoesNotFit { .. })
+    ));
+    assert_eq!(fs::read(&path).unwrap(), out);
+
+    // A comment header spread over many pages
+    let path = dir.join("tiny_pages.ogg");
+    fs::copy("tests/tiny_pages.ogg", &path).unwrap();
+    let header: CommentHeader = vec![("title".to_string(), "Tiny".to_string())]
+        .into_iter()
+        .collect();
+    safe_patch_comment_header_file(&path, &header).unwrap();
+    let out = fs::read(&path).unwrap();
+    assert_eq!(check_conformance(&out), vec![]);
+    let mut patched = read_comment_header(Cursor::new(&out));
+    patched.clear_tag(PADDING);
+    assert_eq!(patched, header);
+}
+
+#[test]
+fn test_plan_replace() {
+    let data = fs::read("tests/noise.ogg").unwrap();
+    let copied_size = |header: &CommentHeader| {
+        let options = ReplaceOptions {
+            copy_pages: true,
+            ..Default::default()
+        };
+        let input = Cursor::new(&data);
+        let (out, _) =
+            safe_replace_comment_header_with_options(input, header.clone(), &options).unwrap();
+        out.into_inner().len() as u64
+    };
+
+    let mut header = CommentHeader::new();
+    header.add_tag_single("title", "Planned");
+    let plan = plan_replace(Cursor::new(&data), &header);
+    assert!(plan.in_place);
+    assert!(!plan.renumbers_pages);
+    assert_eq!(plan.header_pages, [1]);
+    assert_eq!(plan.input_size, data.len() as u64);
+    assert_eq!(plan.output_size, copied_size(&header));
+
+    header.add_tag_single("description", &"x".repeat(100_000));
+    let plan = plan_replace(Cursor::new(&data), &header);
+    assert!(!plan.in_place);
+    assert!(plan.renumbers_pages);
+    assert!(plan.new_header_pages > 1);
+    assert_eq!(plan.output_size, copied_size(&header));
+}
@@ -0,0 +1,49 @@
+use oggvorbismeta::{
+    CommentHeader, FieldFilter, TagTransaction, Transform, ValueChange, VorbisComments,
+};
+
+fn make_header() -> CommentHeader {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("title", "the  return of   the king");
+    header.add_tag_single("artist", "AC/DC");
+    header.add_tag_single("album", "CafÃ© del mar");
+    header
+}
+
+#[test]
+fn test_title_case_and_spaces() {
+    let mut header = make_header();
+    let mut transaction = TagTransaction::new(&mut header);
+    let filter = FieldFilter::Except(vec!["album".to_string()]);
+    assert_eq!(
+        transaction.apply(&Transform::CollapseSpaces, &filter).len(),
+        1
+    );
+    let changes = transaction.apply(&Transform::TitleCase, &filter).to_vec();
+    assert_eq!(
+        changes,
+        vec![ValueChange {
+            tag: "title".to_string(),
+            old: "the return of the king".to_string(),
+            new: "The Return of the King".to_string(),
+        }]
+    );
+    assert_eq!(transaction.commit().len(), 2);
+    assert_eq!(header.get_tag_single("artist").unwrap(), "AC/DC");
+    assert_eq!(
+        header.get_tag_single("title").unwrap(),
+        "The Return of the King"
+    );
+}
+
+#[test]
+fn test_mojibake_and_rollback() {
+    let mut header = make_header();
+    let table = vec![("Ã©".to_string(), "é".to_string())];
+    {
+        let mut transaction = TagTransaction::new(&mut header);
+        transaction.apply(&Transform::FixMojibake(table.clone()), &FieldFilter::All);
+        assert_eq!(transaction.changes()[0].new, "Café del mar");
+    }
+    assert_eq!(header.get_tag_single("album").unwrap(), "CafÃ© del mar");
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "nfc")]
+
+use oggvorbismeta::{
+    read_comment_header, safe_read_comment_header_nfc, safe_replace_comment_header,
+    safe_replace_comment_header_with_options, to_nfc, CommentHeader, ReplaceOptions,
+    VorbisComments, VorbisUnicode,
+};
+use std::fs::File;
+
+const DECOMPOSED: &str = "Cafe\u{301}";
+const COMPOSED: &str = "Caf\u{e9}";
+
+#[test]
+fn test_normalize_nfc() {
+    assert_eq!(to_nfc(DECOMPOSED), COMPOSED);
+    let mut header = CommentHeader::new();
+    header.add_tag_single("title", DECOMPOSED);
+    header.add_tag_single("artist", COMPOSED);
+    assert_eq!(header.normalize_nfc(), 1);
+    assert_eq!(header.get_tag_multi("title"), vec![COMPOSED]);
+}
+
+#[test]
+fn test_nfc_on_write_and_read() {
+    let mut header = CommentHeader::new();
+    header.add_tag_single("title", DECOMPOSED);
+
+    let f_in = File::open("tests/noise.ogg").expect("Can't open file");
+    let f_out = safe_replace_comment_header(f_in, header.clone()).unwrap();
+    assert_eq!(
+        read_comment_header(f_out.clone())
+            .get_tag_single("title")
+            .unwrap(),
+        DECOMPOSED
+    );
+    let read = safe_read_comment_header_nfc(f_out).unwrap();
+    assert_eq!(read.get_tag_single("title").unwrap(), COMPOSED);
+
+    let options = ReplaceOptions {
+        normalize_nfc: true,
+        ..Default::default()
+    };
+    let f_in = File::open("tests/noise.ogg").expect("Can't open file");
+    let (f_out, _) = safe_replace_comment_header_with_options(f_in, header, &options).unwrap();
+    assert_eq!(
+        read_comment_header(f_out).get_tag_single("title").unwrap(),
+        COMPOSED
+    );
+}
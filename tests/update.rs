@@ -0,0 +1,49 @@
+use oggvorbismeta::{
+    read_file_comments, update_file_comments, write_file_comments, VorbisBatchError, VorbisComments,
+};
+use std::fs;
+use std::path::PathBuf;
+
+fn work_file(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("noise.ogg");
+    fs::copy("tests/noise.ogg", &path).unwrap();
+    path
+}
+
+#[test]
+fn test_update_file_comments() {
+    let path = work_file("update_simple");
+    update_file_comments(&path, |header| {
+        header.clear_tag("title");
+        header.add_tag_single("title", "Updated");
+    })
+    .unwrap();
+    let snapshot = read_file_comments(&path).unwrap();
+    assert_eq!(snapshot.header.get_tag_single("title").unwrap(), "Updated");
+    assert!(snapshot.is_current().unwrap());
+}
+
+#[test]
+fn test_concurrent_modification() {
+    let path = work_file("update_race");
+    let first = read_file_comments(&path).unwrap();
+    let second = read_file_comments(&path).unwrap();
+
+    let mut header = first.header.clone();
+    header.add_tag_single("artist", "First");
+    write_file_comments(&first, header).unwrap();
+
+    let mut header = second.header.clone();
+    header.add_tag_single("artist", "Second");
+    match write_file_comments(&second, header) {
+        Err(VorbisBatchError::ConcurrentModification(failed)) => assert_eq!(failed, path),
+        other => panic!("expected a concurrent modification, got {:?}", other),
+    }
+    let header = read_file_comments(&path).unwrap().header;
+    assert_eq!(header.get_tag_multi("artist"), vec!["Nobody", "First"]);
+    let leftovers = fs::read_dir(path.parent().unwrap()).unwrap().count();
+    assert_eq!(leftovers, 1);
+}
@@ -0,0 +1,123 @@
+use oggvorbismeta::{
+    ogg_crc32, safe_compare_audio, safe_replace_comment_header_streaming,
+    safe_replace_comment_header_with_options, verify_file, CommentHeader, PageIssue, Pagination,
+    ReplaceOptions, VorbisComments,
+};
+use std::fs;
+use std::io::Cursor;
+
+#[test]
+fn test_verify_audio() {
+    let data = fs::read("tests/noise.ogg").unwrap();
+    let mut header = CommentHeader::new();
+    header.add_tag_single("title", "Noise");
+    let paginated = Pagination {
+        max_packets_per_page: Some(2),
+        ..Default::default()
+    };
+    for options in [
+        ReplaceOptions::default(),
+        ReplaceOptions {
+            copy_pages: true,
+            ..Default::default()
+        },
+        ReplaceOptions {
+            raw_copy: true,
+            ..Default::default()
+        },
+        ReplaceOptions {
+            pagination: paginated,
+            ..Default::default()
+        },
+    ] {
+        let options = ReplaceOptions {
+            verify_audio: true,
+            ..options
+        };
+        let (f_out, _) =
+            safe_replace_comment_header_with_options(Cursor::new(&data), header.clone(), &options)
+                .unwrap();
+        let mut streamed = vec![];
+        safe_replace_comment_header_streaming(&data[..], &mut streamed, header.clone(), &options)
+            .unwrap();
+        assert_eq!(
+            safe_compare_audio(Cursor::new(&data), Cursor::new(&streamed)).unwrap(),
+            None
+        );
+        assert_eq!(safe_compare_audio(Cursor::new(&data), f_out).unwrap(), None);
+    }
+}
+
+#[test]
+fn test_compare_audio_finds_changes() {
+    let data = fs::read("tests/noise.ogg").unwrap();
+    assert_eq!(
+        safe_compare_audio(Cursor::new(&data), Cursor::new(&data)).unwrap(),
+        None
+    );
+
+    // A bit of the first audio packet flipped, with the checksum updated
+    let mut changed = data.clone();
+    let page = &mut changed[4429..];
+    page[27 + 14 + 10] ^= 1;
+    page[22..26].copy_from_slice(&[0; 4]);
+    let checksum = ogg_crc32(page);
+    page[22..26].copy_from_slice(&checksum.to_le_bytes());
+    assert_eq!(
+        safe_compare_audio(Cursor::new(&data), Cursor::new(&changed)).unwrap(),
+        Some(3)
+    );
+
+    // The audio page missing
+    assert_eq!(
+        safe_compare_audio(Cursor::new(&data), Cursor::new(&data[..4429])).unwrap(),
+        Some(3)
+    );
+}
+
+#[test]
+fn test_verify_file() {
+    let data = fs::read("tests/noise.ogg").unwrap();
+    assert_eq!(verify_file(&data[..]), vec![]);
+    assert_eq!(
+        verify_file(fs::File::open("tests/tiny_pages.ogg").unwrap()),
+        vec![]
+    );
+    let serial = u32::from_le_bytes([data[14], data[15], data[16], data[17]]);
+
+    let mut corrupt = data.clone();
+    corrupt[5000] ^= 1;
+    assert!(matches!(
+        verify_file(&corrupt[..])[..],
+        [PageIssue::ChecksumMismatch {
+            offset: 4429,
+            sequence: 2,
+            ..
+        }]
+    ));
+
+    assert_eq!(
+        verify_file(&data[..5000]),
+        vec![
+            PageIssue::Truncated { offset: 4429 },
+            PageIssue::MissingEndOfStream { serial }
+        ]
+    );
+
+    // Junk between the pages, and the comment header page missing
+    let mut damaged = data[..58].to_vec();
+    damaged.extend(b"junk");
+    damaged.extend(&data[4429..]);
+    assert_eq!(
+        verify_file(&damaged[..]),
+        vec![
+            PageIssue::Garbage { offset: 58, len: 4 },
+            PageIssue::SequenceGap {
+                offset: 62,
+                serial,
+                expected: 1,
+                found: 2
+            }
+        ]
+    );
+}